@@ -0,0 +1,291 @@
+//! This module performs the Approximate Entropy Test.
+//!
+//! Description of test from NIST SP 800-22:
+//!
+//! "As with the Serial test of Section 2.11, the focus of this test is the frequency of all possible
+//! overlapping m-bit patterns across the entire sequence. The purpose of the test is to compare the
+//! frequency of overlapping blocks of two consecutive/adjacent lengths (m and m+1) against the expected
+//! result for a random sequence."
+
+use crate::bit_sequence::BitSequence;
+use crate::constants;
+use crate::customtypes;
+use crate::customtypes::StatisticalTest;
+use crate::utils;
+use anyhow::{Context, Result};
+
+const TEST_NAME: customtypes::Test = customtypes::Test::ApproximateEntropy;
+
+/// Perform the Approximate Entropy Test by determining the p-value.
+///
+/// # Arguments
+///
+/// bit_sequence - The bit sequence to be tested for randomness
+/// block_len_m - The length m of the overlapping blocks
+///
+/// # Return
+///
+/// Ok(p-value) - The p-value which indicates whether randomness is given or not
+/// Err(err) - Some error occured
+pub fn perform_test(bit_sequence: &BitSequence, block_len_m: usize) -> Result<f64> {
+    log::trace!("approximate_entropy::perform_test()");
+
+    let result =
+        ApproximateEntropy::create(block_len_m).run(bit_sequence, constants::P_VALUE_THRESHOLD)?;
+
+    Ok(result.p_values[0].1)
+}
+
+/// Parameters of the "Approximate Entropy" test
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ApproximateEntropy {
+    pub block_len_m: usize,
+}
+
+impl ApproximateEntropy {
+    pub fn create(block_len_m: usize) -> Self {
+        ApproximateEntropy { block_len_m }
+    }
+}
+
+impl customtypes::StatisticalTest for ApproximateEntropy {
+    fn test(&self) -> customtypes::Test {
+        TEST_NAME
+    }
+
+    fn params(&self) -> Vec<(String, String)> {
+        vec![("block_len_m".to_owned(), self.block_len_m.to_string())]
+    }
+
+    fn compute(&self, bit_sequence: &BitSequence) -> Result<customtypes::TestStatistics> {
+        log::trace!("approximate_entropy::compute()");
+
+        let block_len_m = self.block_len_m;
+
+        // check if bit sequence is valid
+        let length =
+            utils::evaluate_bit_sequence(TEST_NAME, bit_sequence, constants::RECOMMENDED_SIZE)
+                .with_context(|| "Invalid bit sequence passed")?;
+
+        // check block length m for validity
+        evaluate_block_len(length, block_len_m)
+            .with_context(|| "Block length m does not match defined requirements")?;
+
+        // compute phi for block lengths m and m + 1 and the approximate entropy
+        // ApEn(m) = phi(m) - phi(m+1)
+        let phi_m = compute_phi(bit_sequence, block_len_m);
+        let phi_m_1 = compute_phi(bit_sequence, block_len_m + 1);
+        let approximate_entropy = phi_m - phi_m_1;
+        log::debug!(
+            "{}: phi(m) = {}, phi(m+1) = {}, ApEn(m) = {}",
+            TEST_NAME,
+            phi_m,
+            phi_m_1,
+            approximate_entropy
+        );
+
+        // compute chi_square statistics: chi_square = 2 * n * (ln(2) - ApEn(m))
+        let chi_square = 2.0 * (length as f64) * (std::f64::consts::LN_2 - approximate_entropy);
+        log::debug!("{}: Chi_square value: {}", TEST_NAME, chi_square);
+
+        // finally, compute p-value with the incomplete gamma function: igamc(2^(m-1), chi_square/2)
+        // Note: If chi_square is zero, which is an invalid input for igamc, return p-value of 1
+        let p_value = if chi_square <= 0.0 {
+            1.0
+        } else {
+            statrs::function::gamma::gamma_ur(
+                2.0_f64.powi(block_len_m as i32 - 1),
+                chi_square * 0.5,
+            )
+        };
+        log::info!("{}: p-value = {}", TEST_NAME, p_value);
+
+        Ok(customtypes::TestStatistics::create(
+            vec![
+                ("ApEn".to_owned(), approximate_entropy),
+                ("chi_square".to_owned(), chi_square),
+            ],
+            vec![("p-value".to_owned(), p_value)],
+        ))
+    }
+}
+
+/// Evaluate passed block length m.
+///
+/// # Arguments
+///
+/// bit_string_length - Length of bit string
+/// block_len_m - The length m of the overlapping blocks
+///
+/// # Return
+///
+/// Ok() - Block length is valid
+/// Err(err) - Some error occured
+fn evaluate_block_len(bit_string_length: usize, block_len_m: usize) -> Result<()> {
+    log::trace!("approximate_entropy::evaluate_block_len()");
+
+    // more than n different blocks of length m make no sense
+    let log2_length = bit_string_length.ilog2() as usize;
+    if block_len_m == 0 || block_len_m > log2_length {
+        anyhow::bail!(
+            "{}: Block length m ({}) must be between 1 and {}",
+            TEST_NAME,
+            block_len_m,
+            log2_length
+        );
+    }
+
+    // NIST recommends m < floor(log2(n)) - 5. Log a warning if this does not hold
+    if block_len_m + 5 >= log2_length {
+        log::warn!(
+            "{}: Recommended block length m is less than {}, passed: {}",
+            TEST_NAME,
+            log2_length.saturating_sub(5),
+            block_len_m
+        );
+    }
+
+    log::info!("{}: Block length m = {}", TEST_NAME, block_len_m);
+
+    Ok(())
+}
+
+/// Compute phi for all overlapping blocks of the given length. The bit string is augmented by
+/// appending its first m - 1 bits to the end.
+///
+/// # Arguments
+///
+/// bit_sequence - The bit sequence to count the blocks in
+/// block_len - The length of the blocks
+///
+/// # Return
+///
+/// phi - The sum of pi_i * ln(pi_i) over all blocks with pi_i = #occurences / n
+fn compute_phi(bit_sequence: &BitSequence, block_len: usize) -> f64 {
+    log::trace!("approximate_entropy::compute_phi()");
+
+    let length = bit_sequence.len() as f64;
+
+    // blocks which do not occur do not contribute since lim x * ln(x) = 0 for x -> 0
+    let phi = utils::count_overlapping_patterns(bit_sequence, block_len)
+        .iter()
+        .filter(|&&count| count > 0)
+        .map(|&count| {
+            let pi = (count as f64) / length;
+            pi * pi.ln()
+        })
+        .sum::<f64>();
+    log::debug!("{}: phi for block length {}: {}", TEST_NAME, block_len, phi);
+
+    phi
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::approximate_entropy;
+    use crate::bit_sequence::BitSequence;
+    use crate::constants;
+    use crate::logger;
+    use crate::utils;
+
+    const LOGLEVEL: &str = "Debug";
+    const BIT_STRING_NIST_1: &str = "0100110101";
+    const PHI_NIST_1: (f64, f64) = (-1.643418, -1.834372);
+    const P_VALUE_NIST_1: f64 = 0.261961;
+    const BIT_STRING_NIST_2: &str = "1100100100001111110110101010001000100001011010001100001000110100110001001100011001100010100010111000";
+    const P_VALUE_NIST_2: f64 = 0.235301;
+    const BIT_STRING_SAME_PATTERN: &str = "1101101101101101101101101101101101101101101101101101101101101101101101101101101101101101101101101101";
+
+    #[test]
+    fn test_phi() {
+        logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
+
+        assert!(
+            (approximate_entropy::compute_phi(&BIT_STRING_NIST_1.parse().unwrap(), 3)
+                - PHI_NIST_1.0)
+                .abs()
+                < 1e-6
+        );
+        assert!(
+            (approximate_entropy::compute_phi(&BIT_STRING_NIST_1.parse().unwrap(), 4)
+                - PHI_NIST_1.1)
+                .abs()
+                < 1e-6
+        );
+    }
+
+    #[test]
+    fn test_approximate_entropy() {
+        logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
+
+        let p_value =
+            approximate_entropy::perform_test(&BIT_STRING_NIST_1.parse().unwrap(), 3).unwrap();
+        assert!((p_value - P_VALUE_NIST_1).abs() < 1e-6);
+        let p_value =
+            approximate_entropy::perform_test(&BIT_STRING_NIST_2.parse().unwrap(), 2).unwrap();
+        assert!((p_value - P_VALUE_NIST_2).abs() < 1e-6);
+        assert!(
+            approximate_entropy::perform_test(&BIT_STRING_SAME_PATTERN.parse().unwrap(), 2)
+                .unwrap()
+                < 0.01
+        );
+
+        // test pi, e, sqrt(2), sqrt(3) and SHA-3 in their respective binary representations
+        let e_file = std::env::current_dir()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_owned()
+            + constants::E_FILE;
+        let e_bit_string = utils::read_random_numbers(&e_file).unwrap();
+        assert!(approximate_entropy::perform_test(&e_bit_string, 10).unwrap() >= 0.01);
+
+        let pi_file = std::env::current_dir()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_owned()
+            + constants::PI_FILE;
+        let pi_bit_string = utils::read_random_numbers(&pi_file).unwrap();
+        assert!(approximate_entropy::perform_test(&pi_bit_string, 10).unwrap() >= 0.01);
+
+        let sqrt_2_file = std::env::current_dir()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_owned()
+            + constants::SQRT_2_FILE;
+        let sqrt_2_bit_string = utils::read_random_numbers(&sqrt_2_file).unwrap();
+        assert!(approximate_entropy::perform_test(&sqrt_2_bit_string, 10).unwrap() >= 0.01);
+
+        let sqrt_3_file = std::env::current_dir()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_owned()
+            + constants::SQRT_3_FILE;
+        let sqrt_3_bit_string = utils::read_random_numbers(&sqrt_3_file).unwrap();
+        assert!(approximate_entropy::perform_test(&sqrt_3_bit_string, 10).unwrap() >= 0.01);
+
+        let sha_3_file = std::env::current_dir()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_owned()
+            + constants::SHA_3_FILE;
+        let sha_3_bit_string = utils::read_random_numbers(&sha_3_file).unwrap();
+        assert!(approximate_entropy::perform_test(&sha_3_bit_string, 10).unwrap() >= 0.01);
+    }
+
+    #[test]
+    fn test_approximate_entropy_error_cases() {
+        logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
+
+        // pass empty sequence
+        assert!(approximate_entropy::perform_test(&BitSequence::new(), 2).is_err());
+
+        // pass invalid block lengths
+        assert!(approximate_entropy::perform_test(&BIT_STRING_NIST_1.parse().unwrap(), 0).is_err());
+        assert!(approximate_entropy::perform_test(&BIT_STRING_NIST_1.parse().unwrap(), 4).is_err());
+    }
+}
@@ -5,8 +5,10 @@
 //! "The focus of the test is the rank of disjoint sub-matrices of the entire sequence. The purpose of this test is
 //! to check for linear dependence among fixed length substrings of the original sequence."
 
+use crate::bit_sequence::BitSequence;
 use crate::constants;
 use crate::customtypes;
+use crate::customtypes::StatisticalTest;
 use crate::utils;
 use anyhow::Result;
 use std::collections::HashMap;
@@ -17,7 +19,7 @@ const TEST_NAME: customtypes::Test = customtypes::Test::BinaryMatrixRank;
 ///
 /// # Arguments
 ///
-/// bit_string - The bit string to be tested for randomnesshas been running for over 60 seconds
+/// bit_sequence - The bit sequence to be tested for randomness
 /// matrix_rows_m - The numbers of rows all matrices need to have
 /// matrix_columns_q - The number of columns all matrices need to have
 ///
@@ -26,201 +28,253 @@ const TEST_NAME: customtypes::Test = customtypes::Test::BinaryMatrixRank;
 /// Ok(p-value) - The p-value which indicates whether randomness is given or not
 /// Err(err) - Some error occured
 pub fn perform_test(
-    bit_string: &str,
+    bit_sequence: &BitSequence,
     matrix_rows_m: usize,
     matrix_columns_q: usize,
 ) -> Result<f64> {
     log::trace!("binary_matrix_rank::perform_test()");
 
-    // capture the current time before executing the actual test
-    let start_time = std::time::Instant::now();
+    let result = BinaryMatrixRank::create(matrix_rows_m, matrix_columns_q)
+        .run(bit_sequence, constants::P_VALUE_THRESHOLD)?;
 
-    // check if bit string contains invalid characters
-    let length = utils::evaluate_bit_string(
-        TEST_NAME,
-        bit_string,
-        constants::RECOMMENDED_SIZE_MATRIX_TEST,
-    )?;
-
-    // the test is optimized for M = Q = 32 and a bit size of n = 32 * 32 * 38. If the values are
-    // not matching, log a warning because approximations may not fit anymore
-    if matrix_rows_m != constants::MATRIX_ROWS_M {
-        log::warn!(
-            "{}: Recommended size for rows: {}, passed rows: {}",
-            TEST_NAME,
-            constants::MATRIX_ROWS_M,
-            matrix_rows_m
-        );
+    Ok(result.p_values[0].1)
+}
+
+/// Parameters of the "Binary Matrix Rank" test
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BinaryMatrixRank {
+    pub matrix_rows_m: usize,
+    pub matrix_columns_q: usize,
+}
+
+impl BinaryMatrixRank {
+    pub fn create(matrix_rows_m: usize, matrix_columns_q: usize) -> Self {
+        BinaryMatrixRank {
+            matrix_rows_m,
+            matrix_columns_q,
+        }
     }
-    if matrix_columns_q != constants::MATRIX_COLUMNS_Q {
-        log::warn!(
-            "{}: Recommended size for columns: {}, passed columns: {}",
-            TEST_NAME,
-            constants::MATRIX_COLUMNS_Q,
-            matrix_columns_q
-        );
+}
+
+impl customtypes::StatisticalTest for BinaryMatrixRank {
+    fn test(&self) -> customtypes::Test {
+        TEST_NAME
     }
 
-    // create matrices from the given bit string by iterating over chunks of size M * Q
-    let matrices = construct_matrices(bit_string, matrix_rows_m, matrix_columns_q);
+    fn params(&self) -> Vec<(String, String)> {
+        vec![
+            ("matrix_rows_m".to_owned(), self.matrix_rows_m.to_string()),
+            (
+                "matrix_columns_q".to_owned(),
+                self.matrix_columns_q.to_string(),
+            ),
+        ]
+    }
 
-    // determine the rank of each matrix and count their occurences
-    let n_matrices = length / (matrix_rows_m * matrix_columns_q);
-    let mut rank_counts: HashMap<usize, usize> = HashMap::new();
+    fn compute(&self, bit_sequence: &BitSequence) -> Result<customtypes::TestStatistics> {
+        log::trace!("binary_matrix_rank::compute()");
 
-    for mut matrix in matrices.into_iter() {
-        *rank_counts.entry(compute_rank(&mut matrix)).or_insert(0) += 1;
-    }
+        let matrix_rows_m = self.matrix_rows_m;
+        let matrix_columns_q = self.matrix_columns_q;
 
-    log::debug!("{}: Counts of ranks: {:?}", TEST_NAME, rank_counts);
+        // check if bit sequence is valid
+        utils::evaluate_bit_sequence(
+            TEST_NAME,
+            bit_sequence,
+            constants::RECOMMENDED_SIZE_MATRIX_TEST,
+        )?;
+
+        // the test is optimized for M = Q = 32 and a bit size of n = 32 * 32 * 38. If the values are
+        // not matching, log a warning because approximations may not fit anymore
+        if matrix_rows_m != constants::MATRIX_ROWS_M {
+            log::warn!(
+                "{}: Recommended size for rows: {}, passed rows: {}",
+                TEST_NAME,
+                constants::MATRIX_ROWS_M,
+                matrix_rows_m
+            );
+        }
+        if matrix_columns_q != constants::MATRIX_COLUMNS_Q {
+            log::warn!(
+                "{}: Recommended size for columns: {}, passed columns: {}",
+                TEST_NAME,
+                constants::MATRIX_COLUMNS_Q,
+                matrix_columns_q
+            );
+        }
 
-    // determine the number of full ranks F_M, full ranks F_(M - 1) and the remaining
-    // ranks (N - F_M - F_(M - 1))
-    let full_rank_m = if let Some(&full_rank) = rank_counts.get(&matrix_rows_m) {
-        full_rank
-    } else {
-        0
-    };
-
-    let full_rank_m_minus_one =
-        if let Some(&full_rank_below) = rank_counts.get(&(matrix_rows_m - 1)) {
-            full_rank_below
-        } else {
-            0
-        };
+        // create matrices from the given bit string, determine the rank of each matrix and count
+        // the full ranks F_M, full ranks F_(M - 1) and the remaining ranks (N - F_M - F_(M - 1))
+        let (full_rank_m, full_rank_m_minus_one, remaining_ranks) =
+            count_ranks(bit_sequence, matrix_rows_m, matrix_columns_q)?;
+        let n_matrices = full_rank_m + full_rank_m_minus_one + remaining_ranks;
 
-    let remaining_ranks = n_matrices - full_rank_m - full_rank_m_minus_one;
+        log::debug!(
+            "{}: Number of full rank matrices: {}, full rank - 1 matrices: {}, remaining matrices: {}",
+            TEST_NAME,
+            full_rank_m,
+            full_rank_m_minus_one,
+            remaining_ranks
+        );
 
-    log::debug!(
-        "{}: Number of full rank matrices: {}, full rank - 1 matrices: {}, remaining matrices: {}",
-        TEST_NAME,
-        full_rank_m,
-        full_rank_m_minus_one,
-        remaining_ranks
-    );
+        // Compute chi_square statistics by calculating the three fractions (one fraction per rank)
+        let first_fraction =
+            compute_fraction(full_rank_m, n_matrices, constants::APPROXIMATIONS[0]);
+        let second_fraction = compute_fraction(
+            full_rank_m_minus_one,
+            n_matrices,
+            constants::APPROXIMATIONS[1],
+        );
+        let third_fraction =
+            compute_fraction(remaining_ranks, n_matrices, constants::APPROXIMATIONS[2]);
 
-    // Compute chi_square statistics by calculating the three fractions (one fraction per rank)
-    let first_fraction = compute_fraction(full_rank_m, n_matrices, constants::APPROXIMATIONS[0]);
-    let second_fraction = compute_fraction(
-        full_rank_m_minus_one,
-        n_matrices,
-        constants::APPROXIMATIONS[1],
-    );
-    let third_fraction =
-        compute_fraction(remaining_ranks, n_matrices, constants::APPROXIMATIONS[2]);
+        let chi_square = first_fraction + second_fraction + third_fraction;
+        log::debug!("{}: Chi_square value: {}", TEST_NAME, chi_square);
 
-    let chi_square = first_fraction + second_fraction + third_fraction;
-    log::debug!("{}: Chi_square value: {}", TEST_NAME, chi_square);
+        // finally, compute p-value with exp(-chi_square / 2.0)
+        let p_value = (-chi_square * 0.5).exp();
+        log::info!("{}: p-value = {}", TEST_NAME, p_value);
 
-    // finally, compute p-value with exp(-chi_square / 2.0)
-    let p_value = (-chi_square * 0.5).exp();
-    log::info!("{}: p-value = {}", TEST_NAME, p_value);
+        Ok(customtypes::TestStatistics::create(
+            vec![("chi_square".to_owned(), chi_square)],
+            vec![("p-value".to_owned(), p_value)],
+        ))
+    }
+}
 
-    // capture the current time after the test got executed and calculate elapsed time
-    let end_time = std::time::Instant::now();
-    let elapsed_time = end_time.duration_since(start_time).as_secs_f64();
-    log::info!("{} took {:.6} seconds", TEST_NAME, elapsed_time);
+/// Count the ranks of all matrices constructed from the given bit sequence.
+///
+/// # Arguments
+///
+/// bit_sequence - The bit sequence the matrices have to be constructed from
+/// rows - The number of rows M the matrices will have
+/// columns - The number of columns Q the matrices will have
+///
+/// # Return
+///
+/// Ok((F_M, F_(M - 1), N - F_M - F_(M - 1))) - The number of matrices with full rank M, with rank
+/// M - 1 and with a lower rank
+/// Err(err) - Some error occured
+fn count_ranks(
+    bit_sequence: &BitSequence,
+    rows: usize,
+    columns: usize,
+) -> Result<(usize, usize, usize)> {
+    log::trace!("binary_matrix_rank::count_ranks()");
 
-    Ok(p_value)
+    // each row is packed into a single word
+    if rows == 0 || columns == 0 || columns > constants::MAX_MATRIX_COLUMNS_Q {
+        anyhow::bail!(
+            "{}: Matrices need at least 1 row and between 1 and {} columns, passed: {} x {}",
+            TEST_NAME,
+            constants::MAX_MATRIX_COLUMNS_Q,
+            rows,
+            columns
+        );
+    }
+    if bit_sequence.len() < rows * columns {
+        anyhow::bail!(
+            "{}: Bit sequence of length {} is too short for a single {} x {} matrix",
+            TEST_NAME,
+            bit_sequence.len(),
+            rows,
+            columns
+        );
+    }
+
+    let mut rank_counts: HashMap<usize, usize> = HashMap::new();
+    for mut matrix in construct_matrices(bit_sequence, rows, columns) {
+        *rank_counts
+            .entry(compute_rank(&mut matrix, columns))
+            .or_insert(0) += 1;
+    }
+    log::debug!("{}: Counts of ranks: {:?}", TEST_NAME, rank_counts);
+
+    let full_rank_m = rank_counts.get(&rows).copied().unwrap_or(0);
+    let full_rank_m_minus_one = rank_counts.get(&(rows - 1)).copied().unwrap_or(0);
+    let remaining_ranks = rank_counts.values().sum::<usize>() - full_rank_m - full_rank_m_minus_one;
+
+    Ok((full_rank_m, full_rank_m_minus_one, remaining_ranks))
 }
 
-/// Construct matrices from the given bit string.
+/// Construct matrices from the given bit string. The matrices are filled row by row and each row
+/// is packed into a word, the first bit of the row being the most significant bit.
 ///
 /// # Arguments
 ///
-/// bit_string - The bit string the matrices have to be constructed from
+/// bit_sequence - The bit sequence the matrices have to be constructed from
 /// rows - The number of rows the matrices will have
-/// columns - The number of columns the matrices will have
+/// columns - The number of columns the matrices will have, at most 64
 ///
 /// # Return
 ///
 /// matrices - All of the constructed matrices
-fn construct_matrices(
-    bit_string: &str,
-    rows: usize,
-    columns: usize,
-) -> Vec<nalgebra::DMatrix<rug::Integer>> {
+fn construct_matrices(bit_sequence: &BitSequence, rows: usize, columns: usize) -> Vec<Vec<u64>> {
     log::trace!("binary_matrix_rank::construct_matrices()");
 
     let total_elements = rows * columns;
-    let mut matrices = Vec::new();
 
-    // Divide the bitstring into substrings of length rows * columns
-    let binding = bit_string.chars().collect::<Vec<_>>();
-    let substrings = binding.chunks(total_elements);
+    // Divide the bit sequence into blocks of length rows * columns
     log::debug!(
         "{}: Discarded {} bits from input",
         TEST_NAME,
-        bit_string.len() % total_elements
+        bit_sequence.len() % total_elements
     );
 
-    // Iterate over the substrings to construct matrices
-    for chunk in substrings {
-        if chunk.len() == total_elements {
-            let mut matrix = nalgebra::DMatrix::from_element(rows, columns, rug::Integer::new());
-            for (index, &bit) in chunk.iter().enumerate() {
-                let row = index / columns;
-                let col = index % columns;
-                matrix[(row, col)] = rug::Integer::from(bit.to_digit(2).unwrap());
-            }
-            log::trace!("{}: Constructed matrix: {}", TEST_NAME, &matrix);
-            matrices.push(matrix);
-        }
-    }
+    // Iterate over the blocks to construct matrices
+    let matrices = bit_sequence
+        .blocks(total_elements)
+        .map(|block| {
+            (0..rows)
+                .map(|row| block.bits(row * columns, columns))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
 
     log::debug!(
         "{}: Number of constructed matrices: {}",
         TEST_NAME,
-        &matrices.len()
+        matrices.len()
     );
     matrices
 }
 
-/// Compute the rank of the given matrix.
+/// Compute the rank of the given matrix over GF(2) by Gaussian elimination, where adding rows is
+/// an XOR of their words.
 ///
 /// # Arguments
 ///
-/// matrix - The matrix the rank has to be determined from
+/// matrix - The rows of the matrix, each packed into a word. The rows are modified
+/// columns - The number of columns of the matrix, i.e., the number of used bits of each row
 ///
 /// # Return
 ///
 /// rank - The rank of the given matrix
-fn compute_rank(matrix: &mut nalgebra::DMatrix<rug::Integer>) -> usize {
-    log::trace!("binary_matrix_rank::compute_rank()");
-
-    let (mut row, mut col) = (0, 0);
+fn compute_rank(matrix: &mut [u64], columns: usize) -> usize {
     let mut rank = 0;
 
-    while row < matrix.nrows() && col < matrix.ncols() {
-        // Find the pivot for this column
-        let mut max_row = row;
-        for i in row + 1..matrix.nrows() {
-            let abs_value_i = matrix[(i, col)].clone().abs();
-            let abs_value_max_row = matrix[(max_row, col)].clone().abs();
-            if abs_value_i > abs_value_max_row {
-                max_row = i;
-            }
-        }
+    for col in 0..columns {
+        let mask = 1_u64 << (columns - 1 - col);
+
+        // Find a pivot for this column among the rows not yet used as pivot
+        let pivot = match (rank..matrix.len()).find(|&row| matrix[row] & mask != 0) {
+            Some(pivot) => pivot,
+            // All remaining elements in this column are zero
+            None => continue,
+        };
+        matrix.swap(rank, pivot);
 
-        if matrix[(max_row, col)].is_zero() {
-            // All elements in this column are zero
-            col += 1;
-        } else {
-            // Swap the rows to move the pivot to the current row
-            matrix.swap_rows(row, max_row);
-
-            // Perform row operations to eliminate elements below the pivot
-            for i in row + 1..matrix.nrows() {
-                let factor = matrix[(i, col)].clone() / matrix[(row, col)].clone();
-                for j in col..matrix.ncols() {
-                    let temp = factor.clone() * matrix[(row, j)].clone();
-                    matrix[(i, j)] -= temp;
-                }
+        // Eliminate the column in all rows below the pivot
+        let pivot_row = matrix[rank];
+        for row in matrix[rank + 1..].iter_mut() {
+            if *row & mask != 0 {
+                *row ^= pivot_row;
             }
+        }
 
-            row += 1;
-            col += 1;
-            rank += 1;
+        rank += 1;
+        if rank == matrix.len() {
+            break;
         }
     }
 
@@ -251,121 +305,140 @@ fn compute_fraction(rank: usize, n_matrices: usize, approximation: f64) -> f64 {
 #[cfg(test)]
 mod tests {
     use crate::binary_matrix_rank;
+    use crate::bit_sequence::BitSequence;
     use crate::constants;
+    use crate::customtypes::StatisticalTest;
     use crate::logger;
     use crate::utils;
 
     const LOGLEVEL: &str = "Debug";
-    const BIT_STRING_1: &str = "01011001001010101101"; // example from NIST Paper. p-value should be 0.741948
-    const INVALID_BIT_STRING: &str = "010101111010101010101010101010a0101010101010100101010101";
-    const PI_FILE: &str = "/src/testdata/data.pi";
-    const E_FILE: &str = "/src/testdata/data.e";
-    const SQRT_2_FILE: &str = "/src/estdata/data.sqrt2";
-    const SQRT_3_FILE: &str = "/src/testdata/data.sqrt3";
-    const SHA_3_FILE: &str = "/src/testdata/data.sha3";
-
-    // XXX Fix binary matrix rank
+    const BIT_STRING_NIST_1: &str = "01011001001010101101";
+    const P_VALUE_NIST_1: f64 = 0.741948;
+    const CHI_SQUARE_NIST_1: f64 = 0.596953;
+    const NIST_LENGTH_E: usize = 100000;
+    const RANK_COUNTS_NIST_E: (usize, usize, usize) = (23, 60, 14);
+    const CHI_SQUARE_NIST_E: f64 = 1.2619656;
+    const P_VALUE_NIST_E: f64 = 0.532069;
+
     #[test]
-    fn test_binary_matrix_rank() {
+    fn test_compute_rank() {
         logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
 
-        assert!(binary_matrix_rank::perform_test(BIT_STRING_1, 3, 3).unwrap() >= 0.01);
-
-        // test pi, e, sqrt(2) and sqrt(3) in their respective binary representations
-        let pi_file = std::env::current_dir()
-            .unwrap()
-            .to_str()
-            .unwrap()
-            .to_owned()
-            + PI_FILE;
-        let pi_bit_string = utils::read_random_numbers(&pi_file).unwrap();
-        assert!(
-            binary_matrix_rank::perform_test(
-                &pi_bit_string,
-                constants::MATRIX_ROWS_M,
-                constants::MATRIX_COLUMNS_Q
-            )
-            .unwrap()
-                >= 0.01
+        // matrices of the example from NIST SP 800-22
+        assert_eq!(
+            binary_matrix_rank::compute_rank(&mut [0b010, 0b110, 0b010], 3),
+            2
         );
-
-        let e_file = std::env::current_dir()
-            .unwrap()
-            .to_str()
-            .unwrap()
-            .to_owned()
-            + E_FILE;
-        let e_bit_string = utils::read_random_numbers(&e_file).unwrap();
-        assert!(
-            binary_matrix_rank::perform_test(
-                &e_bit_string,
-                constants::MATRIX_ROWS_M,
-                constants::MATRIX_COLUMNS_Q
-            )
-            .unwrap()
-                >= 0.01
+        assert_eq!(
+            binary_matrix_rank::compute_rank(&mut [0b010, 0b101, 0b011], 3),
+            3
         );
 
-        let sqrt_2_file = std::env::current_dir()
-            .unwrap()
-            .to_str()
-            .unwrap()
-            .to_owned()
-            + SQRT_2_FILE;
-        let sqrt_2_bit_string = utils::read_random_numbers(&sqrt_2_file).unwrap();
-        assert!(
-            binary_matrix_rank::perform_test(
-                &sqrt_2_bit_string,
-                constants::MATRIX_ROWS_M,
-                constants::MATRIX_COLUMNS_Q
-            )
-            .unwrap()
-                >= 0.01
+        // the rank over GF(2) is lower than over the rationals since the rows sum up to zero
+        assert_eq!(
+            binary_matrix_rank::compute_rank(&mut [0b110, 0b011, 0b101], 3),
+            2
         );
 
-        let sqrt_3_file = std::env::current_dir()
-            .unwrap()
-            .to_str()
-            .unwrap()
-            .to_owned()
-            + SQRT_3_FILE;
-        let sqrt_3_bit_string = utils::read_random_numbers(&sqrt_3_file).unwrap();
-        assert!(
-            binary_matrix_rank::perform_test(
-                &sqrt_3_bit_string,
-                constants::MATRIX_ROWS_M,
-                constants::MATRIX_COLUMNS_Q
-            )
-            .unwrap()
-                >= 0.01
+        // zero, identity and non-square matrices
+        assert_eq!(binary_matrix_rank::compute_rank(&mut [0; 32], 32), 0);
+        let mut identity = (0..64).map(|row| 1_u64 << row).collect::<Vec<_>>();
+        assert_eq!(binary_matrix_rank::compute_rank(&mut identity, 64), 64);
+        assert_eq!(
+            binary_matrix_rank::compute_rank(&mut [0b1000, 0b0100, 0b1100, 0b0001, 0b0011], 4),
+            4
         );
+        assert_eq!(
+            binary_matrix_rank::compute_rank(&mut [0b1111, 0b0101], 4),
+            2
+        );
+    }
 
-        let sha_3_file = std::env::current_dir()
+    #[test]
+    fn test_binary_matrix_rank() {
+        logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
+
+        // example from NIST SP 800-22 with M = Q = 3: one matrix with rank 3 and one with rank 2
+        let bit_sequence = BIT_STRING_NIST_1.parse().unwrap();
+        assert_eq!(
+            binary_matrix_rank::count_ranks(&bit_sequence, 3, 3).unwrap(),
+            (1, 1, 0)
+        );
+        let result = binary_matrix_rank::BinaryMatrixRank::create(3, 3)
+            .compute(&bit_sequence)
+            .unwrap();
+        // the example rounds the probabilities to four digits
+        assert!((result.statistics[0].1 - CHI_SQUARE_NIST_1).abs() < 1e-3);
+        assert!((result.p_values[0].1 - P_VALUE_NIST_1).abs() < 1e-4);
+
+        // the first 100000 bits of e must match the example of NIST SP 800-22
+        let e_file = std::env::current_dir()
             .unwrap()
             .to_str()
             .unwrap()
             .to_owned()
-            + SHA_3_FILE;
-        let sha_3_bit_string = utils::read_random_numbers(&sha_3_file).unwrap();
-        assert!(
-            binary_matrix_rank::perform_test(
-                &sha_3_bit_string,
+            + constants::E_FILE;
+        let e_bit_string = utils::read_random_numbers(&e_file).unwrap();
+        let e_bit_string = e_bit_string.slice(0..NIST_LENGTH_E);
+        assert_eq!(
+            binary_matrix_rank::count_ranks(
+                &e_bit_string,
                 constants::MATRIX_ROWS_M,
                 constants::MATRIX_COLUMNS_Q
             )
-            .unwrap()
-                >= 0.01
+            .unwrap(),
+            RANK_COUNTS_NIST_E
         );
+        let result = binary_matrix_rank::BinaryMatrixRank::create(
+            constants::MATRIX_ROWS_M,
+            constants::MATRIX_COLUMNS_Q,
+        )
+        .compute(&e_bit_string)
+        .unwrap();
+        assert!((result.statistics[0].1 - CHI_SQUARE_NIST_E).abs() < 1e-6);
+        assert!((result.p_values[0].1 - P_VALUE_NIST_E).abs() < 1e-6);
+
+        // test pi, e, sqrt(2), sqrt(3) and sha3 in their respective binary representations
+        for file in [
+            constants::PI_FILE,
+            constants::E_FILE,
+            constants::SQRT_2_FILE,
+            constants::SQRT_3_FILE,
+            constants::SHA_3_FILE,
+        ] {
+            let file = std::env::current_dir()
+                .unwrap()
+                .to_str()
+                .unwrap()
+                .to_owned()
+                + file;
+            let bit_string = utils::read_random_numbers(&file).unwrap();
+            assert!(
+                binary_matrix_rank::perform_test(
+                    &bit_string,
+                    constants::MATRIX_ROWS_M,
+                    constants::MATRIX_COLUMNS_Q
+                )
+                .unwrap()
+                    >= 0.01,
+                "{}",
+                file
+            );
+        }
     }
 
     #[test]
     fn test_binary_matrix_rank_error_cases() {
         logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
 
-        // pass empty string
-        assert!(binary_matrix_rank::perform_test("", 3, 3).is_err());
+        // pass empty sequence
+        assert!(binary_matrix_rank::perform_test(&BitSequence::new(), 3, 3).is_err());
 
-        // pass invalid bit string
-        assert!(binary_matrix_rank::perform_test(INVALID_BIT_STRING, 32, 32).is_err());
+        // invalid matrix dimensions or too short for a single matrix
+        let bit_sequence = BIT_STRING_NIST_1.parse().unwrap();
+        assert!(binary_matrix_rank::perform_test(&bit_sequence, 0, 3).is_err());
+        assert!(binary_matrix_rank::perform_test(&bit_sequence, 3, 0).is_err());
+        assert!(binary_matrix_rank::perform_test(&bit_sequence, 1, 65).is_err());
+        assert!(binary_matrix_rank::perform_test(&bit_sequence, 5, 5).is_err());
     }
 }
@@ -0,0 +1,579 @@
+//! This module contains the packed bit sequence all statistical tests operate on.
+//!
+//! The bits are stored in 64-bit words, most significant bit first, i.e., bit i of the sequence
+//! is bit (63 - i % 64) of word i / 64. Unused bits of the last word are always zero. A bit
+//! sequence only ever contains valid bits, so the tests do not need to validate their input
+//! again.
+
+use crate::customtypes;
+use anyhow::Result;
+use std::fmt;
+use std::ops::Range;
+use std::str::FromStr;
+
+const WORD_BITS: usize = u64::BITS as usize;
+
+/// A sequence of bits packed into 64-bit words
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct BitSequence {
+    words: Vec<u64>,
+    length: usize,
+}
+
+impl BitSequence {
+    /// Create an empty bit sequence.
+    pub fn new() -> Self {
+        BitSequence::default()
+    }
+
+    /// Create an empty bit sequence with space for at least the given number of bits.
+    ///
+    /// # Arguments
+    ///
+    /// capacity - The number of bits to reserve space for
+    pub fn with_capacity(capacity: usize) -> Self {
+        BitSequence {
+            words: Vec::with_capacity(capacity.div_ceil(WORD_BITS)),
+            length: 0,
+        }
+    }
+
+    /// Create a bit sequence from bytes. The most significant bit of each byte comes first.
+    ///
+    /// # Arguments
+    ///
+    /// bytes - The bytes to be converted
+    ///
+    /// # Return
+    ///
+    /// bit_sequence - The bit sequence of length 8 * bytes.len()
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        log::trace!("bit_sequence::from_bytes()");
+
+        let words = bytes
+            .chunks(WORD_BITS / 8)
+            .map(|chunk| {
+                let mut word = [0_u8; WORD_BITS / 8];
+                word[..chunk.len()].copy_from_slice(chunk);
+                u64::from_be_bytes(word)
+            })
+            .collect();
+
+        BitSequence {
+            words,
+            length: bytes.len() * 8,
+        }
+    }
+
+    /// Create a bit sequence from bytes with the given order of the bits within each byte.
+    ///
+    /// # Arguments
+    ///
+    /// bytes - The bytes to be converted
+    /// bit_order - Whether the most or the least significant bit of each byte comes first
+    ///
+    /// # Return
+    ///
+    /// bit_sequence - The bit sequence of length 8 * bytes.len()
+    pub fn from_bytes_with_order(bytes: &[u8], bit_order: customtypes::BitOrder) -> Self {
+        match bit_order {
+            customtypes::BitOrder::MsbFirst => BitSequence::from_bytes(bytes),
+            customtypes::BitOrder::LsbFirst => BitSequence::from_bytes(
+                &bytes
+                    .iter()
+                    .map(|byte| byte.reverse_bits())
+                    .collect::<Vec<_>>(),
+            ),
+        }
+    }
+
+    /// Create a bit sequence from a string of hexadecimal digits, e.g. "c0ffee".
+    ///
+    /// # Arguments
+    ///
+    /// hex_string - The hexadecimal string to be converted
+    ///
+    /// # Return
+    ///
+    /// Ok(bit_sequence) - The bit sequence of length 4 * hex_string.len()
+    /// Err(err) - The string has an odd length or contains a non-hexadecimal character
+    pub fn from_hex(hex_string: &str) -> Result<Self> {
+        log::trace!("bit_sequence::from_hex()");
+
+        let bytes = hex::decode(hex_string)
+            .map_err(|e| anyhow::anyhow!("Failed to parse hexadecimal string: {}", e))?;
+
+        Ok(BitSequence::from_bytes(&bytes))
+    }
+
+    /// Create a bit sequence from a string of ASCII characters '0' and '1'.
+    ///
+    /// # Arguments
+    ///
+    /// bit_string - The bit string to be converted
+    ///
+    /// # Return
+    ///
+    /// Ok(bit_sequence) - The bit sequence of length bit_string.len()
+    /// Err(err) - The string contains a character other than '0' and '1'
+    pub fn from_ascii_bits(bit_string: &str) -> Result<Self> {
+        log::trace!("bit_sequence::from_ascii_bits()");
+
+        let mut bit_sequence = BitSequence::with_capacity(bit_string.len());
+
+        for (index, byte) in bit_string.bytes().enumerate() {
+            match byte {
+                b'0' => bit_sequence.push(false),
+                b'1' => bit_sequence.push(true),
+                _ => anyhow::bail!(
+                    "Bit string contains invalid character '{}' at position {}",
+                    bit_string[index..].chars().next().unwrap_or_default(),
+                    index
+                ),
+            }
+        }
+
+        Ok(bit_sequence)
+    }
+
+    /// Append a bit to the end of the sequence.
+    ///
+    /// # Arguments
+    ///
+    /// bit - The bit to append
+    pub fn push(&mut self, bit: bool) {
+        let offset = self.length % WORD_BITS;
+        if offset == 0 {
+            self.words.push(0);
+        }
+        if bit {
+            *self.words.last_mut().unwrap() |= 1 << (WORD_BITS - 1 - offset);
+        }
+        self.length += 1;
+    }
+
+    /// Append bytes to the end of the sequence with the given order of the bits within each byte.
+    ///
+    /// # Arguments
+    ///
+    /// bytes - The bytes to append
+    /// bit_order - Whether the most or the least significant bit of each byte comes first
+    pub fn extend_from_bytes(&mut self, bytes: &[u8], bit_order: customtypes::BitOrder) {
+        self.words
+            .reserve((self.length + bytes.len() * 8).div_ceil(WORD_BITS) - self.words.len());
+
+        for &byte in bytes {
+            let byte = match bit_order {
+                customtypes::BitOrder::MsbFirst => byte as u64,
+                customtypes::BitOrder::LsbFirst => byte.reverse_bits() as u64,
+            };
+
+            let offset = self.length % WORD_BITS;
+            if offset == 0 {
+                self.words.push(0);
+            }
+            let last_word = self.words.last_mut().unwrap();
+            if offset <= WORD_BITS - 8 {
+                *last_word |= byte << (WORD_BITS - 8 - offset);
+            } else {
+                // the byte is split between the last and a new word
+                *last_word |= byte >> (offset - (WORD_BITS - 8));
+                self.words.push(byte << (2 * WORD_BITS - 8 - offset));
+            }
+            self.length += 8;
+        }
+    }
+
+    /// Shorten the sequence to the given length. Nothing happens if the sequence is not longer.
+    ///
+    /// # Arguments
+    ///
+    /// length - The new length of the sequence
+    pub fn truncate(&mut self, length: usize) {
+        if length >= self.length {
+            return;
+        }
+
+        // keep the unused bits of the last word zero
+        self.words.truncate(length.div_ceil(WORD_BITS));
+        let offset = length % WORD_BITS;
+        if offset != 0 {
+            *self.words.last_mut().unwrap() &= !(u64::MAX >> offset);
+        }
+        self.length = length;
+    }
+
+    /// Return the number of bits in the sequence.
+    pub fn len(&self) -> usize {
+        self.length
+    }
+
+    /// Return whether the sequence contains no bits.
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    /// Return the bit at the given position.
+    ///
+    /// # Arguments
+    ///
+    /// index - The position of the bit, starting at 0
+    ///
+    /// # Return
+    ///
+    /// bit - Whether the bit is set. Panics if index is out of bounds
+    pub fn get(&self, index: usize) -> bool {
+        assert!(
+            index < self.length,
+            "Bit index {} out of range for sequence of length {}",
+            index,
+            self.length
+        );
+
+        (self.words[index / WORD_BITS] >> (WORD_BITS - 1 - index % WORD_BITS)) & 1 == 1
+    }
+
+    /// Return up to 64 consecutive bits as an integer, the first bit being the most significant.
+    ///
+    /// # Arguments
+    ///
+    /// start - The position of the first bit
+    /// len - The number of bits (at most 64)
+    ///
+    /// # Return
+    ///
+    /// value - The binary value of the bits. Panics if the bits are out of bounds
+    pub fn bits(&self, start: usize, len: usize) -> u64 {
+        assert!(
+            len <= WORD_BITS && start + len <= self.length,
+            "Bits {}..{} out of range for sequence of length {}",
+            start,
+            start + len,
+            self.length
+        );
+
+        if len == 0 {
+            return 0;
+        }
+
+        let index = start / WORD_BITS;
+        let offset = start % WORD_BITS;
+        let mut value = self.words[index] << offset;
+        if offset + len > WORD_BITS {
+            value |= self.words[index + 1] >> (WORD_BITS - offset);
+        }
+
+        value >> (WORD_BITS - len)
+    }
+
+    /// Return the number of ones in the sequence.
+    pub fn count_ones(&self) -> usize {
+        self.words
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    /// Return the number of ones in the given range of the sequence.
+    ///
+    /// # Arguments
+    ///
+    /// range - The range of bit positions
+    ///
+    /// # Return
+    ///
+    /// count - The number of ones. Panics if the range is out of bounds
+    pub fn count_ones_in(&self, range: Range<usize>) -> usize {
+        range
+            .clone()
+            .step_by(WORD_BITS)
+            .map(|start| {
+                self.bits(start, WORD_BITS.min(range.end - start))
+                    .count_ones() as usize
+            })
+            .sum()
+    }
+
+    /// Copy the given range of the sequence into a new bit sequence.
+    ///
+    /// # Arguments
+    ///
+    /// range - The range of bit positions
+    ///
+    /// # Return
+    ///
+    /// bit_sequence - The bits of the range. Panics if the range is out of bounds
+    pub fn slice(&self, range: Range<usize>) -> BitSequence {
+        assert!(
+            range.start <= range.end && range.end <= self.length,
+            "Range {:?} out of range for sequence of length {}",
+            range,
+            self.length
+        );
+
+        let words = range
+            .clone()
+            .step_by(WORD_BITS)
+            .map(|start| {
+                let len = WORD_BITS.min(range.end - start);
+                self.bits(start, len) << (WORD_BITS - len)
+            })
+            .collect();
+
+        BitSequence {
+            words,
+            length: range.len(),
+        }
+    }
+
+    /// Split the sequence into non-overlapping blocks of the given length. Remaining bits which do
+    /// not fill a complete block are discarded.
+    ///
+    /// # Arguments
+    ///
+    /// block_len - The length of each block (must not be 0)
+    ///
+    /// # Return
+    ///
+    /// blocks - An iterator over the floor(n / block_len) blocks
+    pub fn blocks(&self, block_len: usize) -> impl Iterator<Item = BitSequence> + '_ {
+        assert!(block_len > 0, "Block length must not be 0");
+
+        (0..self.length / block_len)
+            .map(move |block| self.slice(block * block_len..(block + 1) * block_len))
+    }
+
+    /// Return an iterator over all bits of the sequence.
+    pub fn iter(&self) -> Iter<'_> {
+        Iter {
+            bit_sequence: self,
+            range: 0..self.length,
+        }
+    }
+}
+
+/// An iterator over the bits of a bit sequence
+#[derive(Debug, Clone)]
+pub struct Iter<'a> {
+    bit_sequence: &'a BitSequence,
+    range: Range<usize>,
+}
+
+impl Iterator for Iter<'_> {
+    type Item = bool;
+
+    fn next(&mut self) -> Option<bool> {
+        self.range.next().map(|index| self.bit_sequence.get(index))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.range.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for Iter<'_> {
+    fn next_back(&mut self) -> Option<bool> {
+        self.range
+            .next_back()
+            .map(|index| self.bit_sequence.get(index))
+    }
+}
+
+impl ExactSizeIterator for Iter<'_> {}
+
+impl<'a> IntoIterator for &'a BitSequence {
+    type Item = bool;
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
+
+impl FromIterator<bool> for BitSequence {
+    fn from_iter<I: IntoIterator<Item = bool>>(iter: I) -> Self {
+        let iter = iter.into_iter();
+        let mut bit_sequence = BitSequence::with_capacity(iter.size_hint().0);
+        for bit in iter {
+            bit_sequence.push(bit);
+        }
+
+        bit_sequence
+    }
+}
+
+impl FromStr for BitSequence {
+    type Err = anyhow::Error;
+
+    fn from_str(bit_string: &str) -> Result<Self> {
+        BitSequence::from_ascii_bits(bit_string)
+    }
+}
+
+impl fmt::Display for BitSequence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for bit in self {
+            f.write_str(if bit { "1" } else { "0" })?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bit_sequence::BitSequence;
+    use crate::customtypes;
+    use crate::logger;
+
+    const LOGLEVEL: &str = "Debug";
+    static RANDOM_BYTES_1: [u8; 6] = [0xab, 0x00, 0xde, 0xd6, 0xf3, 0xc0];
+    static RANDOM_BYTES_2: [u8; 9] = [0x00, 0xFF, 0xDE, 0x89, 0xC0, 0x3D, 0xA6, 0xC2, 0xB5];
+    const HEX_STRING_2: &str = "00ffde89c03da6c2b5";
+    const BIT_STRING_1: &str = "101010110000000011011110110101101111001111000000";
+    const BIT_STRING_1_LSB_FIRST: &str = "110101010000000001111011011010111100111100000011";
+    const BIT_STRING_2: &str =
+        "000000001111111111011110100010011100000000111101101001101100001010110101";
+    const BIT_STRING_LONG: &str = "1100100100001111110110101010001000100001011010001100001000110100110001001100011001100010100010111000";
+    const INVALID_BIT_STRING: &str = "010101010101011110101010101010101X0101010101010101010101";
+
+    #[test]
+    fn test_construction() {
+        logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
+
+        // bytes, hexadecimal and ASCII bits must result in the same sequence
+        let bit_sequence_1 = BitSequence::from_bytes(&RANDOM_BYTES_1);
+        assert_eq!(bit_sequence_1.len(), RANDOM_BYTES_1.len() * 8);
+        assert_eq!(bit_sequence_1.to_string(), BIT_STRING_1);
+        assert_eq!(
+            bit_sequence_1,
+            BitSequence::from_ascii_bits(BIT_STRING_1).unwrap()
+        );
+
+        let bit_sequence_2 = BitSequence::from_hex(HEX_STRING_2).unwrap();
+        assert_eq!(bit_sequence_2, BitSequence::from_bytes(&RANDOM_BYTES_2));
+        assert_eq!(bit_sequence_2.to_string(), BIT_STRING_2);
+        assert_eq!(bit_sequence_2, BIT_STRING_2.parse().unwrap());
+
+        let bit_sequence_long: BitSequence = BIT_STRING_LONG.parse().unwrap();
+        assert_eq!(bit_sequence_long.to_string(), BIT_STRING_LONG);
+        assert_eq!(
+            bit_sequence_long,
+            BIT_STRING_LONG.chars().map(|c| c == '1').collect()
+        );
+
+        // the bits of each byte can be extracted in reverse order
+        assert_eq!(
+            BitSequence::from_bytes_with_order(&RANDOM_BYTES_1, customtypes::BitOrder::LsbFirst)
+                .to_string(),
+            BIT_STRING_1_LSB_FIRST
+        );
+        assert_eq!(
+            BitSequence::from_bytes_with_order(&RANDOM_BYTES_1, customtypes::BitOrder::MsbFirst),
+            bit_sequence_1
+        );
+
+        // bytes can be appended at any position
+        let mut bit_sequence = BitSequence::new();
+        for offset in 0..70 {
+            bit_sequence.extend_from_bytes(&RANDOM_BYTES_1, customtypes::BitOrder::MsbFirst);
+            bit_sequence.extend_from_bytes(&RANDOM_BYTES_1, customtypes::BitOrder::LsbFirst);
+            assert_eq!(
+                bit_sequence
+                    .slice(bit_sequence.len() - 16 * RANDOM_BYTES_1.len()..bit_sequence.len()),
+                (BIT_STRING_1.to_owned() + BIT_STRING_1_LSB_FIRST)
+                    .parse()
+                    .unwrap(),
+                "offset {}",
+                offset
+            );
+            bit_sequence.push(offset % 3 == 0);
+        }
+
+        // empty input results in an empty sequence
+        assert!(BitSequence::from_bytes(&[]).is_empty());
+        assert!(BitSequence::from_ascii_bits("").unwrap().is_empty());
+        assert_eq!(BitSequence::new().to_string(), "");
+
+        // invalid input
+        assert!(BitSequence::from_ascii_bits(INVALID_BIT_STRING).is_err());
+        assert!(BitSequence::from_ascii_bits("0 1").is_err());
+        assert!(BitSequence::from_hex("abc").is_err());
+        assert!(BitSequence::from_hex("xy").is_err());
+    }
+
+    #[test]
+    fn test_access() {
+        logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
+
+        let bit_sequence: BitSequence = BIT_STRING_LONG.parse().unwrap();
+
+        // bit indexing and iteration
+        for (index, c) in BIT_STRING_LONG.chars().enumerate() {
+            assert_eq!(bit_sequence.get(index), c == '1');
+        }
+        assert_eq!(bit_sequence.iter().len(), BIT_STRING_LONG.len());
+        assert!(bit_sequence
+            .iter()
+            .rev()
+            .eq(BIT_STRING_LONG.chars().rev().map(|c| c == '1')));
+
+        // popcount over the whole sequence and over ranges crossing word boundaries
+        assert_eq!(
+            bit_sequence.count_ones(),
+            BIT_STRING_LONG.chars().filter(|&c| c == '1').count()
+        );
+        for (start, end) in [(0, 0), (0, 100), (3, 64), (60, 70), (63, 65), (10, 99)] {
+            assert_eq!(
+                bit_sequence.count_ones_in(start..end),
+                BIT_STRING_LONG[start..end]
+                    .chars()
+                    .filter(|&c| c == '1')
+                    .count()
+            );
+        }
+
+        // integer values of bit ranges
+        assert_eq!(bit_sequence.bits(0, 0), 0);
+        assert_eq!(bit_sequence.bits(0, 4), 0b1100);
+        assert_eq!(
+            bit_sequence.bits(60, 8),
+            u64::from_str_radix(&BIT_STRING_LONG[60..68], 2).unwrap()
+        );
+        assert_eq!(
+            bit_sequence.bits(30, 64),
+            u64::from_str_radix(&BIT_STRING_LONG[30..94], 2).unwrap()
+        );
+
+        // slicing and blocks
+        assert_eq!(bit_sequence.slice(5..5), BitSequence::new());
+        assert_eq!(
+            bit_sequence.slice(7..90).to_string(),
+            BIT_STRING_LONG[7..90]
+        );
+        let blocks = bit_sequence.blocks(30).collect::<Vec<_>>();
+        assert_eq!(blocks.len(), 3);
+        for (index, block) in blocks.iter().enumerate() {
+            assert_eq!(
+                block.to_string(),
+                BIT_STRING_LONG[index * 30..(index + 1) * 30]
+            );
+        }
+
+        // truncating keeps the first bits
+        let mut truncated = bit_sequence.clone();
+        truncated.truncate(bit_sequence.len() + 1);
+        assert_eq!(truncated, bit_sequence);
+        truncated.truncate(70);
+        assert_eq!(truncated, bit_sequence.slice(0..70));
+        truncated.truncate(64);
+        assert_eq!(truncated, bit_sequence.slice(0..64));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_out_of_range() {
+        let bit_sequence: BitSequence = BIT_STRING_1.parse().unwrap();
+        bit_sequence.get(BIT_STRING_1.len());
+    }
+}
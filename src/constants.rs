@@ -7,12 +7,22 @@ pub const SQRT_2_FILE: &str = "/src/testdata/data.sqrt2";
 pub const SQRT_3_FILE: &str = "/src/testdata/data.sqrt3";
 pub const SHA_3_FILE: &str = "/src/testdata/data.sha3";
 
-/// Treshold for p-value to check if bit string is random or not
+/// Name of the input source which reads from the standard input
+pub const STDIN_SOURCE: &str = "-";
+
+/// Default significance level alpha to check if bit string is random or not
 pub const P_VALUE_THRESHOLD: f64 = 0.01;
 
 /// Usual recommended size for several tests
 pub const RECOMMENDED_SIZE: usize = 100;
 
+/// Default parameters of the command line interface as used by the NIST reference implementation
+pub const DEFAULT_TEMPLATE_LEN: usize = 9;
+pub const DEFAULT_TEMPLATE_BLOCKS: usize = 8;
+pub const DEFAULT_BLOCK_SIZE_LINEAR_COMPLEXITY: usize = 500;
+pub const DEFAULT_BLOCK_LEN_SERIAL: usize = 16;
+pub const DEFAULT_BLOCK_LEN_APPROXIMATE_ENTROPY: usize = 10;
+
 /// Recommended size for "Discrete Fourier Transform (Spectral) Test
 pub const RECOMMENDED_SIZE_DFT: usize = 1000;
 
@@ -49,8 +59,12 @@ pub const RECOMMENDED_SIZE_MATRIX_TEST: usize = 38912;
 
 pub const MATRIX_ROWS_M: usize = 32;
 pub const MATRIX_COLUMNS_Q: usize = 32;
+/// Each row of a matrix is packed into a 64-bit word
+pub const MAX_MATRIX_COLUMNS_Q: usize = 64;
 
-pub static APPROXIMATIONS: [f64; 3] = [0.2888, 0.5776, 0.1336];
+/// Probabilities of rank 32, 31 and below for a random 32 x 32 matrix over GF(2), which NIST SP
+/// 800-22 rounds to 0.2888, 0.5776 and 0.1336
+pub static APPROXIMATIONS: [f64; 3] = [0.2887880951538411, 0.5775761901732046, 0.1336357146729543];
 
 /// Constants for the "Discrete Fourier Transform (Spectral)" Test
 pub const LOG_ARG: f64 = 1.0 / 0.05;
@@ -60,11 +74,97 @@ pub const NORMALIZED_DIFF_CONSTANT: f64 = 0.95 * 0.05 * 0.25;
 /// Constants for the "Non-overlapping Template Matching" Test
 pub const TEMPLATE_LEN: (usize, usize) = (2, 21);
 pub const RECOMMENDED_TEMPLATE_LEN: (usize, usize) = (9, 10);
-pub const TEMPLATE_SUB_PATH: &str = "/templates/template";
-pub const TMP_DIR: &str = "/tmp";
 
 /// Constants for the "Overlapping Template Matching" Test
 pub const RECOMMENDED_SIZE_OVERLAPPING_TEMPLATE: usize = 1000000;
-pub const MAX_N_OVERLAPPING_TEMPLATE: f64 = 5.0;
+pub const BLOCK_SIZE_OVERLAPPING_TEMPLATE: usize = 1032;
+pub const MAX_N_OVERLAPPING_TEMPLATE: usize = 5;
+pub const MIN_EXPECTED_OVERLAPPING_TEMPLATE: f64 = 5.0;
+/// Theoretical probabilities for m = 9 and M = 1032 as printed in the paper. The NIST reference
+/// implementation approximates them with Pr(u, eta), which the published results are based on
 pub static PI_VALUES_OVERLAPPING_TEMPLATE: [f64; 6] =
     [0.364091, 0.185659, 0.139381, 0.100571, 0.0704323, 0.139865];
+
+/// Constants for the "Maurer's Universal Statistical" Test
+pub const BLOCK_LEN_L: (usize, usize) = (1, 16);
+pub const RECOMMENDED_BLOCK_LEN_L: (usize, usize) = (6, 16);
+pub const RECOMMENDED_SIZE_UNIVERSAL: usize = 387840;
+
+/// Minimum bit string lengths n for L = 6..16 (Q = 10 * 2^L) as recommended by NIST SP 800-22
+pub static UNIVERSAL_MIN_LENGTHS: [(usize, usize); 11] = [
+    (6, 387840),
+    (7, 904960),
+    (8, 2068480),
+    (9, 4654080),
+    (10, 10342400),
+    (11, 22753280),
+    (12, 49643520),
+    (13, 107560960),
+    (14, 231669760),
+    (15, 496435200),
+    (16, 1059061760),
+];
+
+/// Expected values and variances of the test statistic f_n for L = 1..16 (index 0 is unused)
+pub static UNIVERSAL_EXPECTED_VALUES: [f64; 17] = [
+    0.0, 0.7326495, 1.5374383, 2.4016068, 3.3112247, 4.2534266, 5.2177052, 6.1962507, 7.1836656,
+    8.1764248, 9.1723243, 10.170032, 11.168765, 12.168070, 13.167693, 14.167488, 15.167379,
+];
+pub static UNIVERSAL_VARIANCES: [f64; 17] = [
+    0.0, 0.690, 1.338, 1.901, 2.358, 2.705, 2.954, 3.125, 3.238, 3.311, 3.356, 3.384, 3.401, 3.410,
+    3.416, 3.419, 3.421,
+];
+
+/// Constants for the "Linear Complexity" Test
+pub const RECOMMENDED_SIZE_LINEAR_COMPLEXITY: usize = 1000000;
+pub const RECOMMENDED_BLOCK_SIZE_M: (usize, usize) = (500, 5000);
+pub const MIN_BLOCKS_N_LINEAR_COMPLEXITY: usize = 200;
+/// pi_0 is 0.01047 as in the NIST reference implementation (the paper rounds it to 0.010417)
+pub static PI_VALUES_LINEAR_COMPLEXITY: [f64; 7] =
+    [0.01047, 0.03125, 0.125, 0.5, 0.25, 0.0625, 0.020833];
+
+/// Constants for the "Random Excursions" Test
+pub const RECOMMENDED_SIZE_RANDOM_EXCURSIONS: usize = 1000000;
+pub const MIN_CYCLES_J: usize = 500;
+pub const CYCLES_J_FACTOR: f64 = 0.005;
+pub const RANDOM_EXCURSIONS_STATES: [i64; 8] = [-4, -3, -2, -1, 1, 2, 3, 4];
+
+/// Probabilities pi_k(x) that state |x| is visited exactly k times (k = 0..4) or at least 5 times
+/// (k = 5) in a cycle, for |x| = 1..4
+pub static PI_VALUES_RANDOM_EXCURSIONS: [[f64; 6]; 4] = [
+    [0.5, 0.25, 0.125, 0.0625, 0.03125, 0.03125],
+    [
+        0.75,
+        0.0625,
+        0.046875,
+        0.03515625,
+        0.0263671875,
+        0.0791015625,
+    ],
+    [
+        0.8333333333,
+        0.02777777778,
+        0.02314814815,
+        0.01929012346,
+        0.01607510288,
+        0.0803755143,
+    ],
+    [
+        0.875,
+        0.015625,
+        0.013671875,
+        0.01196289063,
+        0.0104675293,
+        0.0732727051,
+    ],
+];
+
+/// Constants for the "Random Excursions Variant" Test
+pub const RANDOM_EXCURSIONS_VARIANT_STATES: [i64; 18] = [
+    -9, -8, -7, -6, -5, -4, -3, -2, -1, 1, 2, 3, 4, 5, 6, 7, 8, 9,
+];
+
+/// Constants for the second-level analysis of multiple sequences (NIST SP 800-22 section 4.2)
+pub const UNIFORMITY_BINS: usize = 10;
+pub const UNIFORMITY_THRESHOLD: f64 = 0.0001;
+pub const MIN_SEQUENCES_UNIFORMITY: usize = 55;
@@ -10,8 +10,10 @@
 //! zero. For certain types of non-random sequences, the excursions of this random walk from zero will be
 //! large."
 
+use crate::bit_sequence::BitSequence;
 use crate::constants;
 use crate::customtypes;
+use crate::customtypes::StatisticalTest;
 use crate::utils;
 use anyhow::{Context, Result};
 use statrs::distribution::ContinuousCDF;
@@ -22,113 +24,181 @@ const TEST_NAME: customtypes::Test = customtypes::Test::CumulativeSums;
 ///
 /// # Arguments
 ///
-/// bit_string - The bit string to be tested for randomness
+/// bit_sequence - The bit sequence to be tested for randomness
 /// mode - A switch to process forward (mode = 0) or backward (mode = 1) through sequence
 ///
 /// # Return
 ///
 /// Ok(p-value) - The p-value which indicates whether randomness is given or not
 /// Err(err) - Some error occured
-pub fn perform_test(bit_string: &str, mode: customtypes::Mode) -> Result<f64> {
+pub fn perform_test(bit_sequence: &BitSequence, mode: customtypes::Mode) -> Result<f64> {
     log::trace!("cumulative_sums::perform_test()");
 
-    // capture the current time before executing the actual test
-    let start_time = std::time::Instant::now();
-
-    // check if bit string contains invalid characters
-    let length = utils::evaluate_bit_string(TEST_NAME, bit_string, constants::RECOMMENDED_SIZE)
-        .with_context(|| "Invalid character(s) in passed bit string detected")?;
-
-    // Create cumulative sums depending on chosen mode
-    // In "Forward" mode, the bit string remains unchanged.
-    // In "Backward" mode, just revert the bit string
-    let new_bit_string = if mode == customtypes::Mode::Forward {
-        bit_string.to_string()
-    } else {
-        bit_string.chars().rev().collect::<String>()
-    };
-
-    // now compute the particular sums and determine the maximum sum. '1' is a +1 whereas '0' is a
-    // -1
-    let mut current_sum: i64 = 0;
-    let mut max_sum_z = 0;
-
-    for bit in new_bit_string.chars() {
-        if bit == '1' {
-            current_sum += 1;
-        } else {
-            current_sum -= 1;
-        }
+    let result = CumulativeSums::create(mode).run(bit_sequence, constants::P_VALUE_THRESHOLD)?;
+
+    Ok(result.p_values[0].1)
+}
+
+/// Parameters of the "Cumulative Sums (Cusum)" test
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CumulativeSums {
+    pub mode: customtypes::Mode,
+}
 
-        max_sum_z = max_sum_z.max(current_sum.abs());
+impl CumulativeSums {
+    pub fn create(mode: customtypes::Mode) -> Self {
+        CumulativeSums { mode }
     }
-    log::debug!(
-        "{}: Determined maximum value z of cumulative sums: {}",
-        TEST_NAME,
-        max_sum_z
-    );
-
-    // compute lower and upper limits for the sums before generating p-value
-    let upper_limit = (((length as f64) / (max_sum_z as f64) - 1.0) * 0.25) as i64;
-    let lower_limit_1 = ((-1.0 * (length as f64) / (max_sum_z as f64) + 1.0) * 0.25) as i64;
-    let lower_limit_2 = ((-1.0 * (length as f64) / (max_sum_z as f64) - 3.0) * 0.25) as i64;
-    log::debug!(
-        "{}: Upper limit: {}, Lower Limit 1: {}, Lower Limit 2: {}",
-        TEST_NAME,
-        upper_limit,
-        lower_limit_1,
-        lower_limit_2
-    );
-
-    // finally, compute p-value with the standard normal cumulative probability distribution
-    // function
-    let mut sum_1 = 0.0;
-    let mut sum_2 = 0.0;
-    let normal = statrs::distribution::Normal::new(0.0, 1.0).unwrap();
-    let denominator = (length as f64).sqrt();
-
-    // we do have two sums to generate to get the p-value in the end
-    for k in lower_limit_1..=upper_limit {
-        let numerator_1 = (4.0 * (k as f64) + 1.0) * (max_sum_z as f64);
-        let numerator_2 = (4.0 * (k as f64) - 1.0) * (max_sum_z as f64);
-
-        sum_1 += normal.cdf(numerator_1 / denominator) - normal.cdf(numerator_2 / denominator);
-        log::trace!(
-            "{}: Value of sum in first loop for k = {}: {}",
-            TEST_NAME,
-            k,
-            sum_1
-        );
+}
+
+impl customtypes::StatisticalTest for CumulativeSums {
+    fn test(&self) -> customtypes::Test {
+        TEST_NAME
+    }
+
+    fn params(&self) -> Vec<(String, String)> {
+        vec![("mode".to_owned(), format!("{:?}", self.mode))]
+    }
+
+    fn compute(&self, bit_sequence: &BitSequence) -> Result<customtypes::TestStatistics> {
+        log::trace!("cumulative_sums::compute()");
+
+        self.compute_on_walk(bit_sequence, &compute_random_walk(bit_sequence))
+    }
+
+    fn uses_random_walk(&self) -> bool {
+        true
     }
 
-    for k in lower_limit_2..=upper_limit {
-        let numerator_1 = (4.0 * (k as f64) + 3.0) * (max_sum_z as f64);
-        let numerator_2 = (4.0 * (k as f64) + 1.0) * (max_sum_z as f64);
+    fn compute_on_walk(
+        &self,
+        bit_sequence: &BitSequence,
+        random_walk: &customtypes::RandomWalk,
+    ) -> Result<customtypes::TestStatistics> {
+        log::trace!("cumulative_sums::compute_on_walk()");
+
+        let mode = self.mode;
+
+        // check if bit sequence is valid
+        let length =
+            utils::evaluate_bit_sequence(TEST_NAME, bit_sequence, constants::RECOMMENDED_SIZE)
+                .with_context(|| "Invalid bit sequence passed")?;
+
+        // determine the maximum of the absolute partial sums, where '1' is a +1 and '0' is a -1.
+        // In "Forward" mode, these are the partial sums S_1, ..., S_n of the random walk.
+        // In "Backward" mode, the partial sums of the reverted bit sequence are S_n - S_(n-k) for
+        // k = 1, ..., n with S_0 = 0
+        let partial_sums = &random_walk.partial_sums;
+        let max_sum_z = match mode {
+            customtypes::Mode::Forward => partial_sums
+                .iter()
+                .map(|partial_sum| partial_sum.abs())
+                .max()
+                .unwrap_or(0),
+            customtypes::Mode::Backward => {
+                let total_sum = partial_sums.last().copied().unwrap_or(0);
+                std::iter::once(&0)
+                    .chain(partial_sums.iter())
+                    .take(partial_sums.len())
+                    .map(|partial_sum| (total_sum - partial_sum).abs())
+                    .max()
+                    .unwrap_or(0)
+            }
+        };
+        log::debug!(
+            "{}: Determined maximum value z of cumulative sums: {}",
+            TEST_NAME,
+            max_sum_z
+        );
 
-        sum_2 += normal.cdf(numerator_1 / denominator) - normal.cdf(numerator_2 / denominator);
-        log::trace!(
-            "{}: Value of sum in second loop for k = {}: {}",
+        // compute lower and upper limits for the sums before generating p-value
+        let upper_limit = (((length as f64) / (max_sum_z as f64) - 1.0) * 0.25) as i64;
+        let lower_limit_1 = ((-(length as f64) / (max_sum_z as f64) + 1.0) * 0.25) as i64;
+        let lower_limit_2 = ((-(length as f64) / (max_sum_z as f64) - 3.0) * 0.25) as i64;
+        log::debug!(
+            "{}: Upper limit: {}, Lower Limit 1: {}, Lower Limit 2: {}",
             TEST_NAME,
-            k,
-            sum_2
+            upper_limit,
+            lower_limit_1,
+            lower_limit_2
         );
+
+        // finally, compute p-value with the standard normal cumulative probability distribution
+        // function
+        let mut sum_1 = 0.0;
+        let mut sum_2 = 0.0;
+        let normal = statrs::distribution::Normal::new(0.0, 1.0).unwrap();
+        let denominator = (length as f64).sqrt();
+
+        // we do have two sums to generate to get the p-value in the end
+        for k in lower_limit_1..=upper_limit {
+            let numerator_1 = (4.0 * (k as f64) + 1.0) * (max_sum_z as f64);
+            let numerator_2 = (4.0 * (k as f64) - 1.0) * (max_sum_z as f64);
+
+            sum_1 += normal.cdf(numerator_1 / denominator) - normal.cdf(numerator_2 / denominator);
+            log::trace!(
+                "{}: Value of sum in first loop for k = {}: {}",
+                TEST_NAME,
+                k,
+                sum_1
+            );
+        }
+
+        for k in lower_limit_2..=upper_limit {
+            let numerator_1 = (4.0 * (k as f64) + 3.0) * (max_sum_z as f64);
+            let numerator_2 = (4.0 * (k as f64) + 1.0) * (max_sum_z as f64);
+
+            sum_2 += normal.cdf(numerator_1 / denominator) - normal.cdf(numerator_2 / denominator);
+            log::trace!(
+                "{}: Value of sum in second loop for k = {}: {}",
+                TEST_NAME,
+                k,
+                sum_2
+            );
+        }
+
+        let p_value = 1.0 - sum_1 + sum_2;
+        log::info!("{}: p-value = {} ('{:?}' Mode)", TEST_NAME, p_value, mode);
+
+        Ok(customtypes::TestStatistics::create(
+            vec![("z".to_owned(), max_sum_z as f64)],
+            vec![("p-value".to_owned(), p_value)],
+        ))
     }
+}
 
-    let p_value = 1.0 - sum_1 + sum_2;
-    log::info!("{}: p-value = {} ('{:?}' Mode)", TEST_NAME, p_value, mode);
+/// Compute the random walk S_1, ..., S_n of the given bit string, where S_k is the partial sum of
+/// the first k bits converted to -1 ('0') and +1 ('1'), and split it into cycles. The walk is
+/// shared by the cumulative sums test in both modes and both random excursions tests, so that it
+/// is only computed once per bit string.
+///
+/// # Arguments
+///
+/// bit_sequence - The (already evaluated) bit sequence to compute the random walk from
+///
+/// # Return
+///
+/// random_walk - The partial sums S_1, ..., S_n and their cycles
+pub fn compute_random_walk(bit_sequence: &BitSequence) -> customtypes::RandomWalk {
+    log::trace!("cumulative_sums::compute_random_walk()");
 
-    // capture the current time after the test got executed and calculate elapsed time
-    let end_time = std::time::Instant::now();
-    let elapsed_time = end_time.duration_since(start_time).as_secs_f64();
-    log::info!("{} took {:.6} seconds", TEST_NAME, elapsed_time);
+    let partial_sums = bit_sequence
+        .iter()
+        .scan(0_i64, |current_sum, bit| {
+            *current_sum += if bit { 1 } else { -1 };
+            Some(*current_sum)
+        })
+        .collect();
 
-    Ok(p_value)
+    customtypes::RandomWalk::create(partial_sums)
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::bit_sequence::BitSequence;
     use crate::cumulative_sums;
     use crate::customtypes;
+    use crate::customtypes::StatisticalTest;
     use crate::logger;
     use crate::utils;
 
@@ -140,58 +210,134 @@ mod tests {
     const P_VALUE_NIST_2_BACKWARD: f64 = 0.11486621529731965;
     const BIT_STRING_ONLY_ONES: &str = "1111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111";
     const BIT_STRING_ONLY_ZEROS: &str = "0000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000";
-    const INVALID_BIT_STRING: &str = "1100110000010101011011000100110011100000000000100100110101010001000100a111010110100000001101011111001100111001101101100010110010";
     const PI_FILE: &str = "/src/testdata/data.pi";
     const E_FILE: &str = "/src/testdata/data.e";
     const SQRT_2_FILE: &str = "/src/testdata/data.sqrt2";
     const SQRT_3_FILE: &str = "/src/testdata/data.sqrt3";
     const SHA_3_FILE: &str = "/src/testdata/data.sha3";
 
+    #[test]
+    fn test_random_walk() {
+        logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
+
+        let random_walk = cumulative_sums::compute_random_walk(&BIT_STRING_NIST_1.parse().unwrap());
+        assert_eq!(random_walk.partial_sums, vec![1, 0, 1, 2, 1, 2, 1, 2, 3, 4]);
+        assert_eq!(random_walk.number_of_cycles(), 2);
+
+        // example from NIST SP 800-22 (Random Excursions Test)
+        let random_walk = cumulative_sums::compute_random_walk(&"0110110101".parse().unwrap());
+        assert_eq!(
+            random_walk.partial_sums,
+            vec![-1, 0, 1, 0, 1, 2, 1, 2, 1, 2]
+        );
+        assert_eq!(random_walk.cycle_ends, vec![2, 4, 10]);
+        assert_eq!(
+            random_walk.cycles().collect::<Vec<_>>(),
+            vec![&[-1, 0][..], &[1, 0][..], &[1, 2, 1, 2, 1, 2][..]]
+        );
+
+        // a walk ending at zero must not get an additional cycle
+        let random_walk = cumulative_sums::compute_random_walk(&"0110".parse().unwrap());
+        assert_eq!(random_walk.cycle_ends, vec![2, 4]);
+
+        let random_walk = cumulative_sums::compute_random_walk(&BitSequence::new());
+        assert!(random_walk.partial_sums.is_empty());
+        assert_eq!(random_walk.number_of_cycles(), 0);
+    }
+
     #[test]
     fn test_cumulative_sums() {
         logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
 
         assert_eq!(
-            cumulative_sums::perform_test(BIT_STRING_NIST_1, customtypes::Mode::Forward).unwrap(),
+            cumulative_sums::perform_test(
+                &BIT_STRING_NIST_1.parse().unwrap(),
+                customtypes::Mode::Forward
+            )
+            .unwrap(),
             P_VALUE_NIST_1
         );
         assert_eq!(
-            cumulative_sums::perform_test(BIT_STRING_NIST_1, customtypes::Mode::Backward).unwrap(),
+            cumulative_sums::perform_test(
+                &BIT_STRING_NIST_1.parse().unwrap(),
+                customtypes::Mode::Backward
+            )
+            .unwrap(),
             P_VALUE_NIST_1
         );
         assert_eq!(
-            cumulative_sums::perform_test(BIT_STRING_NIST_2, customtypes::Mode::Forward).unwrap(),
+            cumulative_sums::perform_test(
+                &BIT_STRING_NIST_2.parse().unwrap(),
+                customtypes::Mode::Forward
+            )
+            .unwrap(),
             P_VALUE_NIST_2_FORWARD
         );
         assert_eq!(
-            cumulative_sums::perform_test(BIT_STRING_NIST_2, customtypes::Mode::Backward).unwrap(),
+            cumulative_sums::perform_test(
+                &BIT_STRING_NIST_2.parse().unwrap(),
+                customtypes::Mode::Backward
+            )
+            .unwrap(),
             P_VALUE_NIST_2_BACKWARD
         );
+
+        // one random walk serves both modes
+        let bit_sequence: BitSequence = BIT_STRING_NIST_2.parse().unwrap();
+        let random_walk = cumulative_sums::compute_random_walk(&bit_sequence);
+        for (mode, p_value) in [
+            (customtypes::Mode::Forward, P_VALUE_NIST_2_FORWARD),
+            (customtypes::Mode::Backward, P_VALUE_NIST_2_BACKWARD),
+        ] {
+            let result = cumulative_sums::CumulativeSums::create(mode)
+                .compute_on_walk(&bit_sequence, &random_walk)
+                .unwrap();
+            assert_eq!(result.p_values[0].1, p_value);
+        }
+
         assert!(
-            cumulative_sums::perform_test(BIT_STRING_ONLY_ONES, customtypes::Mode::Forward)
-                .unwrap()
+            cumulative_sums::perform_test(
+                &BIT_STRING_ONLY_ONES.parse().unwrap(),
+                customtypes::Mode::Forward
+            )
+            .unwrap()
                 <= 0.01
         );
         assert!(
-            cumulative_sums::perform_test(BIT_STRING_ONLY_ONES, customtypes::Mode::Backward)
-                .unwrap()
+            cumulative_sums::perform_test(
+                &BIT_STRING_ONLY_ONES.parse().unwrap(),
+                customtypes::Mode::Backward
+            )
+            .unwrap()
                 <= 0.01
         );
         assert!(
-            cumulative_sums::perform_test(BIT_STRING_ONLY_ZEROS, customtypes::Mode::Forward)
-                .unwrap()
+            cumulative_sums::perform_test(
+                &BIT_STRING_ONLY_ZEROS.parse().unwrap(),
+                customtypes::Mode::Forward
+            )
+            .unwrap()
                 <= 0.01
         );
         assert!(
-            cumulative_sums::perform_test(BIT_STRING_ONLY_ZEROS, customtypes::Mode::Backward)
-                .unwrap()
+            cumulative_sums::perform_test(
+                &BIT_STRING_ONLY_ZEROS.parse().unwrap(),
+                customtypes::Mode::Backward
+            )
+            .unwrap()
                 <= 0.01
         );
         assert_eq!(
-            cumulative_sums::perform_test(BIT_STRING_ONLY_ZEROS, customtypes::Mode::Forward)
-                .unwrap(),
-            cumulative_sums::perform_test(BIT_STRING_ONLY_ONES, customtypes::Mode::Backward)
-                .unwrap()
+            cumulative_sums::perform_test(
+                &BIT_STRING_ONLY_ZEROS.parse().unwrap(),
+                customtypes::Mode::Forward
+            )
+            .unwrap(),
+            cumulative_sums::perform_test(
+                &BIT_STRING_ONLY_ONES.parse().unwrap(),
+                customtypes::Mode::Backward
+            )
+            .unwrap()
         );
 
         // test pi, e, sqrt(2) and sqrt(3) in their respective binary representations
@@ -280,10 +426,10 @@ mod tests {
     fn test_cumulative_sums_error_cases() {
         logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
 
-        // pass empty string
-        assert!(cumulative_sums::perform_test("", customtypes::Mode::Backward).is_err());
-
-        // pass invalid bit string
-        assert!(cumulative_sums::perform_test(INVALID_BIT_STRING, customtypes::Mode::Forward).is_err());
+        // pass empty sequence
+        assert!(
+            cumulative_sums::perform_test(&BitSequence::new(), customtypes::Mode::Backward)
+                .is_err()
+        );
     }
 }
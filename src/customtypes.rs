@@ -1,6 +1,11 @@
 //! This module contains custom types like enums and structs and their respective implementations.
 
+use crate::bit_sequence::BitSequence;
+use crate::constants;
+use anyhow::Result;
+
 /// The names of the particular tests
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Test {
     FrequencyMonobit,
     FrequencyBlock,
@@ -43,6 +48,98 @@ impl std::fmt::Display for Test {
     }
 }
 
+impl Test {
+    /// All tests in the order of NIST SP 800-22
+    pub const ALL: [Test; 15] = [
+        Test::FrequencyMonobit,
+        Test::FrequencyBlock,
+        Test::Runs,
+        Test::LongestRun,
+        Test::BinaryMatrixRank,
+        Test::DFTSpectral,
+        Test::NonOverlappingTemplate,
+        Test::OverlappingTemplate,
+        Test::MaurersUniversalStatistical,
+        Test::LinearComplexity,
+        Test::Serial,
+        Test::ApproximateEntropy,
+        Test::CumulativeSums,
+        Test::RandomExcursions,
+        Test::RandomExcursionsVariant,
+    ];
+
+    /// The identifier of the test, which is the name of its module
+    pub fn id(&self) -> &'static str {
+        match self {
+            Test::FrequencyMonobit => "frequency_monobit",
+            Test::FrequencyBlock => "frequency_block",
+            Test::Runs => "runs",
+            Test::LongestRun => "longest_run",
+            Test::BinaryMatrixRank => "binary_matrix_rank",
+            Test::DFTSpectral => "dft_spectral",
+            Test::NonOverlappingTemplate => "non_overlapping_template",
+            Test::OverlappingTemplate => "overlapping_template",
+            Test::MaurersUniversalStatistical => "maurers_universal",
+            Test::LinearComplexity => "linear_complexity",
+            Test::Serial => "serial",
+            Test::ApproximateEntropy => "approximate_entropy",
+            Test::CumulativeSums => "cumulative_sums",
+            Test::RandomExcursions => "random_excursions",
+            Test::RandomExcursionsVariant => "random_excursions_variant",
+        }
+    }
+
+    /// The name of the test in the reports of the NIST reference implementation (sts)
+    pub fn sts_name(&self) -> &'static str {
+        match self {
+            Test::FrequencyMonobit => "Frequency",
+            Test::FrequencyBlock => "BlockFrequency",
+            Test::Runs => "Runs",
+            Test::LongestRun => "LongestRun",
+            Test::BinaryMatrixRank => "Rank",
+            Test::DFTSpectral => "FFT",
+            Test::NonOverlappingTemplate => "NonOverlappingTemplate",
+            Test::OverlappingTemplate => "OverlappingTemplate",
+            Test::MaurersUniversalStatistical => "Universal",
+            Test::LinearComplexity => "LinearComplexity",
+            Test::Serial => "Serial",
+            Test::ApproximateEntropy => "ApproximateEntropy",
+            Test::CumulativeSums => "CumulativeSums",
+            Test::RandomExcursions => "RandomExcursions",
+            Test::RandomExcursionsVariant => "RandomExcursionsVariant",
+        }
+    }
+
+    /// The number of the test in NIST SP 800-22, starting at 1
+    pub fn number(&self) -> usize {
+        Test::ALL.iter().position(|test| test == self).unwrap_or(0) + 1
+    }
+}
+
+impl std::str::FromStr for Test {
+    type Err = anyhow::Error;
+
+    /// Parse a test either by its identifier or by its number in NIST SP 800-22
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+
+        if let Ok(number) = s.parse::<usize>() {
+            return match number {
+                1..=15 => Ok(Test::ALL[number - 1]),
+                _ => anyhow::bail!("Test number '{}' must be between 1 and 15", number),
+            };
+        }
+
+        match Test::ALL
+            .iter()
+            .find(|test| test.id().eq_ignore_ascii_case(s))
+        {
+            Some(test) => Ok(*test),
+            None => anyhow::bail!("Unknown test '{}'", s),
+        }
+    }
+}
+
 /// Struct for "Longest Run of Ones in a Block" test
 #[derive(Debug)]
 pub struct LongestRunConfig<'a> {
@@ -69,8 +166,552 @@ impl<'a> LongestRunConfig<'a> {
 }
 
 /// Enum for "Cumulative Sums (Cusum)" test
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Mode {
     Forward,
     Backward,
 }
+
+/// The format of a file containing random numbers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputFormat {
+    /// Guess between ASCII bits and hexadecimal text. A file of only '0' and '1' is read as bits
+    Auto,
+    /// Raw binary bytes, e.g. a dump of a random number generator
+    Binary,
+    /// Text of the characters '0' and '1'
+    Ascii,
+    /// Text of hexadecimal digits, two per byte
+    Hex,
+    /// Base64 encoded bytes
+    Base64,
+}
+
+impl std::str::FromStr for InputFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "auto" => Ok(InputFormat::Auto),
+            "binary" | "raw" => Ok(InputFormat::Binary),
+            "ascii" | "bits" => Ok(InputFormat::Ascii),
+            "hex" => Ok(InputFormat::Hex),
+            "base64" => Ok(InputFormat::Base64),
+            _ => anyhow::bail!("Unknown input format '{}'", s),
+        }
+    }
+}
+
+impl std::fmt::Display for InputFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InputFormat::Auto => write!(f, "auto"),
+            InputFormat::Binary => write!(f, "binary"),
+            InputFormat::Ascii => write!(f, "ascii"),
+            InputFormat::Hex => write!(f, "hex"),
+            InputFormat::Base64 => write!(f, "base64"),
+        }
+    }
+}
+
+/// The order in which the bits are extracted from each byte
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BitOrder {
+    /// The most significant bit of each byte comes first
+    MsbFirst,
+    /// The least significant bit of each byte comes first
+    LsbFirst,
+}
+
+impl std::str::FromStr for BitOrder {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "msb" | "msb-first" => Ok(BitOrder::MsbFirst),
+            "lsb" | "lsb-first" => Ok(BitOrder::LsbFirst),
+            _ => anyhow::bail!("Unknown bit order '{}'", s),
+        }
+    }
+}
+
+impl std::fmt::Display for BitOrder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BitOrder::MsbFirst => write!(f, "msb"),
+            BitOrder::LsbFirst => write!(f, "lsb"),
+        }
+    }
+}
+
+/// Struct for the metadata of the tested input, which is stored along with exported results
+#[derive(Debug, Clone, PartialEq)]
+pub struct InputMetadata {
+    /// The name of the input, usually the path of the file
+    pub source: String,
+    pub input_format: InputFormat,
+    pub bit_order: BitOrder,
+    /// The number of bits read from the input
+    pub length: usize,
+    /// The SHA-256 of the consumed raw input as lowercase hexadecimal digits
+    pub sha256: String,
+}
+
+impl InputMetadata {
+    pub fn create(
+        source: &str,
+        input_format: InputFormat,
+        bit_order: BitOrder,
+        length: usize,
+        sha256: String,
+    ) -> Self {
+        InputMetadata {
+            source: source.to_owned(),
+            input_format,
+            bit_order,
+            length,
+            sha256,
+        }
+    }
+}
+
+/// Enum for the results of the "Random Excursions" and "Random Excursions Variant" tests
+#[derive(Debug, PartialEq)]
+pub enum ExcursionsResult {
+    /// The p-values for each state x, sorted by state
+    Applicable(Vec<(i64, f64)>),
+    /// The number of cycles J is too small for the test to be meaningful
+    NotApplicable { cycles: usize, min_cycles: usize },
+}
+
+/// Struct for the random walk S_1, ..., S_n used by the "Cumulative Sums (Cusum)", "Random
+/// Excursions" and "Random Excursions Variant" tests
+#[derive(Debug)]
+pub struct RandomWalk {
+    pub partial_sums: Vec<i64>,
+    pub cycle_ends: Vec<usize>,
+}
+
+impl RandomWalk {
+    /// Create the random walk from the given partial sums and split it into cycles. A cycle ends
+    /// whenever the walk returns to zero. Since the walk is extended by a zero at its end, the
+    /// last cycle is completed even if S_n != 0.
+    pub fn create(partial_sums: Vec<i64>) -> Self {
+        let mut cycle_ends: Vec<usize> = partial_sums
+            .iter()
+            .enumerate()
+            .filter(|(_, &partial_sum)| partial_sum == 0)
+            .map(|(index, _)| index + 1)
+            .collect();
+
+        if partial_sums
+            .last()
+            .is_some_and(|&partial_sum| partial_sum != 0)
+        {
+            cycle_ends.push(partial_sums.len());
+        }
+
+        RandomWalk {
+            partial_sums,
+            cycle_ends,
+        }
+    }
+
+    /// The number of cycles J
+    pub fn number_of_cycles(&self) -> usize {
+        self.cycle_ends.len()
+    }
+
+    /// Iterate over the partial sums of each cycle (without the leading zero)
+    pub fn cycles(&self) -> impl Iterator<Item = &[i64]> {
+        let starts = std::iter::once(0).chain(self.cycle_ends.iter().copied());
+        starts
+            .zip(self.cycle_ends.iter())
+            .map(|(start, &end)| &self.partial_sums[start..end])
+    }
+}
+
+/// Struct for the result of a single template of the "Non-overlapping Template Matching" test
+#[derive(Debug)]
+pub struct TemplateResult {
+    pub template: String,
+    pub counts: Vec<usize>,
+    pub chi_square: f64,
+    pub p_value: f64,
+}
+
+impl TemplateResult {
+    pub fn create(template: String, counts: Vec<usize>, chi_square: f64, p_value: f64) -> Self {
+        TemplateResult {
+            template,
+            counts,
+            chi_square,
+            p_value,
+        }
+    }
+}
+
+/// Struct for the results of all templates of the "Non-overlapping Template Matching" test
+#[derive(Debug)]
+pub struct NonOverlappingTemplateResult {
+    pub template_results: Vec<TemplateResult>,
+}
+
+impl NonOverlappingTemplateResult {
+    pub fn create(template_results: Vec<TemplateResult>) -> Self {
+        NonOverlappingTemplateResult { template_results }
+    }
+
+    /// The p-values of all templates in the order of the templates
+    pub fn p_values(&self) -> Vec<f64> {
+        self.template_results
+            .iter()
+            .map(|result| result.p_value)
+            .collect()
+    }
+
+    /// The mean of the p-values of all templates as summary
+    pub fn p_values_mean(&self) -> f64 {
+        self.template_results
+            .iter()
+            .map(|result| result.p_value)
+            .sum::<f64>()
+            / (self.template_results.len() as f64)
+    }
+}
+
+/// Trait implemented by every test of the suite. The implementing struct holds the parameters of
+/// the test, so all tests can be run the same way, also concurrently from multiple threads.
+pub trait StatisticalTest: Send + Sync {
+    /// The test which is implemented
+    fn test(&self) -> Test;
+
+    /// The name of the test
+    fn name(&self) -> String {
+        self.test().to_string()
+    }
+
+    /// The parameters of the test as pairs of name and value
+    fn params(&self) -> Vec<(String, String)>;
+
+    /// Compute the test statistic(s) and p-value(s) of the passed bit sequence
+    fn compute(&self, bit_sequence: &BitSequence) -> Result<TestStatistics>;
+
+    /// Whether the test is computed on the random walk of the bit sequence. The random walk can be
+    /// computed once per bit sequence and passed to all of these tests
+    fn uses_random_walk(&self) -> bool {
+        false
+    }
+
+    /// Compute the test statistic(s) and p-value(s) of the passed bit sequence from its already
+    /// computed random walk
+    fn compute_on_walk(
+        &self,
+        bit_sequence: &BitSequence,
+        _random_walk: &RandomWalk,
+    ) -> Result<TestStatistics> {
+        self.compute(bit_sequence)
+    }
+
+    /// Check the requirements of the test on the passed bit sequence before it is performed.
+    /// Returns the reason if the test must not be performed on the bit sequence
+    fn pre_test(&self, _bit_sequence: &BitSequence) -> Option<String> {
+        None
+    }
+
+    /// Run the test on the passed bit sequence and decide about randomness with significance
+    /// level alpha
+    fn run(&self, bit_sequence: &BitSequence, alpha: f64) -> Result<TestResult> {
+        self.run_on_walk(bit_sequence, None, alpha)
+    }
+
+    /// Run the test like run(), but on the already computed random walk of the bit sequence if it
+    /// is passed and used by the test
+    fn run_on_walk(
+        &self,
+        bit_sequence: &BitSequence,
+        random_walk: Option<&RandomWalk>,
+        alpha: f64,
+    ) -> Result<TestResult> {
+        log::trace!("customtypes::StatisticalTest::run_on_walk()");
+
+        if !(alpha > 0.0 && alpha < 1.0) {
+            anyhow::bail!(
+                "{}: Significance level alpha ({}) must be between 0 and 1",
+                self.test(),
+                alpha
+            );
+        }
+
+        // capture the current time before executing the actual test
+        let start_time = std::time::Instant::now();
+
+        let statistics = match random_walk {
+            Some(random_walk) if self.uses_random_walk() => {
+                self.compute_on_walk(bit_sequence, random_walk)?
+            }
+            _ => self.compute(bit_sequence)?,
+        };
+
+        // capture the current time after the test got executed and calculate elapsed time
+        let end_time = std::time::Instant::now();
+        let elapsed_time = end_time.duration_since(start_time).as_secs_f64();
+        log::info!("{} took {:.6} seconds", self.test(), elapsed_time);
+
+        Ok(TestResult::create(
+            self.test(),
+            self.params(),
+            statistics,
+            elapsed_time,
+            alpha,
+        ))
+    }
+}
+
+/// Struct for the test statistic(s) and p-value(s) computed by a test, each labeled by a name
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestStatistics {
+    pub statistics: Vec<(String, f64)>,
+    pub p_values: Vec<(String, f64)>,
+}
+
+impl TestStatistics {
+    pub fn create(statistics: Vec<(String, f64)>, p_values: Vec<(String, f64)>) -> Self {
+        TestStatistics {
+            statistics,
+            p_values,
+        }
+    }
+}
+
+/// Enum for the decision about randomness of a single p-value or of a whole test
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    /// The p-value is at least alpha, or no p-value of the test is less than alpha / k for k
+    /// p-values
+    Pass,
+    /// The p-value is less than alpha, or a p-value of the test is less than alpha / k for k
+    /// p-values
+    Fail,
+    /// The test did not compute any p-value, e.g. too few cycles for the random excursions tests
+    NotApplicable,
+    /// The test was not performed because a prerequisite did not hold
+    Skipped,
+}
+
+impl std::fmt::Display for Verdict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Verdict::Pass => write!(f, "PASS"),
+            Verdict::Fail => write!(f, "FAIL"),
+            Verdict::NotApplicable => write!(f, "NOT APPLICABLE"),
+            Verdict::Skipped => write!(f, "SKIPPED"),
+        }
+    }
+}
+
+/// Struct for the result of a test run
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestResult {
+    pub test: Test,
+    pub params: Vec<(String, String)>,
+    pub statistics: Vec<(String, f64)>,
+    pub p_values: Vec<(String, f64)>,
+    pub p_value_verdicts: Vec<(String, Verdict)>,
+    pub elapsed_time: f64,
+    pub alpha: f64,
+    pub verdict: Verdict,
+    /// The reason why the test was skipped
+    pub reason: Option<String>,
+}
+
+impl TestResult {
+    pub fn create(
+        test: Test,
+        params: Vec<(String, String)>,
+        statistics: TestStatistics,
+        elapsed_time: f64,
+        alpha: f64,
+    ) -> Self {
+        // every p-value on its own is compared against alpha
+        let p_value_verdicts: Vec<(String, Verdict)> = statistics
+            .p_values
+            .iter()
+            .map(|(label, p_value)| (label.clone(), Self::verdict_of(*p_value, alpha)))
+            .collect();
+
+        // for k p-values, about alpha * k of them are expected to be less than alpha even for
+        // random data. So the test as a whole only fails if a p-value is less than alpha / k
+        // (Bonferroni correction), which is the same as alpha for tests with a single p-value
+        let verdict = if statistics.p_values.is_empty() {
+            Verdict::NotApplicable
+        } else {
+            let corrected_alpha = alpha / (statistics.p_values.len() as f64);
+            statistics
+                .p_values
+                .iter()
+                .map(|(_, p_value)| Self::verdict_of(*p_value, corrected_alpha))
+                .find(|verdict| *verdict == Verdict::Fail)
+                .unwrap_or(Verdict::Pass)
+        };
+
+        TestResult {
+            test,
+            params,
+            statistics: statistics.statistics,
+            p_values: statistics.p_values,
+            p_value_verdicts,
+            elapsed_time,
+            alpha,
+            verdict,
+            reason: None,
+        }
+    }
+
+    /// Create the result of a test which was skipped for the passed reason
+    pub fn skipped(test: Test, params: Vec<(String, String)>, reason: String, alpha: f64) -> Self {
+        TestResult {
+            test,
+            params,
+            statistics: Vec::new(),
+            p_values: Vec::new(),
+            p_value_verdicts: Vec::new(),
+            elapsed_time: 0.0,
+            alpha,
+            verdict: Verdict::Skipped,
+            reason: Some(reason),
+        }
+    }
+
+    /// The first p-value, which is the only one for most of the tests
+    pub fn p_value(&self) -> Option<f64> {
+        self.p_values.first().map(|(_, p_value)| *p_value)
+    }
+
+    /// The verdict of the p-value with the passed label
+    pub fn p_value_verdict(&self, label: &str) -> Option<Verdict> {
+        self.p_value_verdicts
+            .iter()
+            .find(|(p_value_label, _)| p_value_label == label)
+            .map(|(_, verdict)| *verdict)
+    }
+
+    /// Compare a single p-value against the significance level
+    fn verdict_of(p_value: f64, alpha: f64) -> Verdict {
+        if p_value < alpha {
+            Verdict::Fail
+        } else {
+            Verdict::Pass
+        }
+    }
+}
+
+/// Struct for the results of a test performed on multiple streams
+#[derive(Debug)]
+pub struct MultiStreamResult {
+    pub test: Test,
+    pub params: Vec<(String, String)>,
+    /// The result of each stream in stream order. A stream whose test failed to run holds the error
+    pub stream_results: Vec<Result<TestResult>>,
+}
+
+impl MultiStreamResult {
+    pub fn create(
+        test: Test,
+        params: Vec<(String, String)>,
+        stream_results: Vec<Result<TestResult>>,
+    ) -> Self {
+        MultiStreamResult {
+            test,
+            params,
+            stream_results,
+        }
+    }
+
+    /// The p-values of all streams grouped by their label in the order the labels first occur.
+    /// Streams for which the test was not applicable or failed to run do not contribute
+    pub fn p_values(&self) -> Vec<(String, Vec<f64>)> {
+        let mut p_values = Vec::<(String, Vec<f64>)>::new();
+
+        for result in self.stream_results.iter().flatten() {
+            for (label, p_value) in &result.p_values {
+                match p_values.iter_mut().find(|(existing, _)| existing == label) {
+                    Some((_, values)) => values.push(*p_value),
+                    None => p_values.push((label.clone(), vec![*p_value])),
+                }
+            }
+        }
+
+        p_values
+    }
+
+    /// The number of streams the test could not be performed on
+    pub fn number_of_errors(&self) -> usize {
+        self.stream_results
+            .iter()
+            .filter(|result| result.is_err())
+            .count()
+    }
+
+    /// The number of streams the test was skipped on
+    pub fn number_of_skipped(&self) -> usize {
+        self.stream_results
+            .iter()
+            .flatten()
+            .filter(|result| result.verdict == Verdict::Skipped)
+            .count()
+    }
+}
+
+/// Struct for the second-level analysis of the p-values of multiple sequences
+#[derive(Debug, Clone, PartialEq)]
+pub struct SecondLevelResult {
+    /// The number of sequences m
+    pub sample_size: usize,
+    /// The number of sequences with a p-value >= alpha
+    pub passed: usize,
+    /// The proportion of passing sequences
+    pub proportion: f64,
+    /// The confidence interval of the proportion p ± 3 * sqrt(p * (1 - p) / m) with p = 1 - alpha
+    pub confidence_interval: (f64, f64),
+    /// The number of p-values in each of the ten bins [0.0, 0.1), ..., [0.9, 1.0]
+    pub histogram: [usize; constants::UNIFORMITY_BINS],
+    /// The p-value of the chi_square test for uniformity of the p-values
+    pub uniformity_p_value: f64,
+}
+
+impl SecondLevelResult {
+    pub fn create(
+        passed: usize,
+        confidence_interval: (f64, f64),
+        histogram: [usize; constants::UNIFORMITY_BINS],
+        uniformity_p_value: f64,
+    ) -> Self {
+        let sample_size = histogram.iter().sum();
+
+        SecondLevelResult {
+            sample_size,
+            passed,
+            proportion: (passed as f64) / (sample_size as f64),
+            confidence_interval,
+            histogram,
+            uniformity_p_value,
+        }
+    }
+
+    /// Whether the proportion of passing sequences lies within the confidence interval
+    pub fn proportion_passed(&self) -> bool {
+        self.proportion >= self.confidence_interval.0
+            && self.proportion <= self.confidence_interval.1
+    }
+
+    /// Whether the p-values are uniformly distributed
+    pub fn uniformity_passed(&self) -> bool {
+        self.uniformity_p_value >= constants::UNIFORMITY_THRESHOLD
+    }
+
+    /// Whether both the proportion and the uniformity of the p-values pass
+    pub fn passed(&self) -> bool {
+        self.proportion_passed() && self.uniformity_passed()
+    }
+}
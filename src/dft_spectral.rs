@@ -6,54 +6,123 @@
 //! of this test is to detect periodic features (i.e., repetitive patterns that are near each other) in the tested
 //! sequence that would indicate a deviation from the assumption of randomness. The intention is to detect
 //! whether the number of peaks exceeding the 95 % threshold is significantly different than 5 %."
+//!
+//! The peak height threshold is T = sqrt(ln(1/0.05) * n) as defined in NIST SP 800-22 rev1a.
+//! Earlier versions of this suite used log10 instead of ln, which can be selected with the legacy
+//! threshold to reproduce their results.
 
+use crate::bit_sequence::BitSequence;
 use crate::constants;
 use crate::customtypes;
+use crate::customtypes::StatisticalTest;
 use crate::utils;
 use anyhow::{Context, Result};
-use rustfft::{num_complex::Complex, FftPlanner};
+use realfft::{num_complex::Complex, RealFftPlanner};
 
 const TEST_NAME: customtypes::Test = customtypes::Test::DFTSpectral;
 
-/// Perform the Discrete Fourier Transform (Spectral) Test by determining the p-value.
+/// Perform the Discrete Fourier Transform (Spectral) Test with the threshold of the specification
+/// by determining the p-value.
 ///
 /// # Arguments
 ///
-/// bit_string - The bit string to be tested for randomness
+/// bit_sequence - The bit sequence to be tested for randomness
 ///
 /// # Return
 ///
 /// Ok(p-value) - The p-value which indicates whether randomness is given or not
 /// Err(err) - Some error occured
-pub fn perform_test(bit_string: &str) -> Result<f64> {
+pub fn perform_test(bit_sequence: &BitSequence) -> Result<f64> {
     log::trace!("dft_spectral::perform_test()");
 
-    // capture the current time before executing the actual test
-    let start_time = std::time::Instant::now();
+    let result = DFTSpectral::default().run(bit_sequence, constants::P_VALUE_THRESHOLD)?;
 
-    // check if bit string contains invalid characters
-    let length = utils::evaluate_bit_string(TEST_NAME, bit_string, constants::RECOMMENDED_SIZE_DFT)
-        .with_context(|| "Invalid character(s) in passed bit string detected")?;
+    Ok(result.p_values[0].1)
+}
 
-    // perform discrete fourier transform on given bit string to retrieve the results
-    let spectrum = apply_dft(bit_string, length);
+/// Parameters of the "Discrete Fourier Transform (Spectral)" test
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct DFTSpectral {
+    /// Compute the threshold with log10 like earlier versions of this suite instead of ln
+    pub legacy_threshold: bool,
+}
 
-    // calculate height threshold T = sqrt(log(1/0.05) * length)
-    let height_threshold = (constants::LOG_ARG.log10() * (length as f64)).sqrt();
-    log::debug!("{}: Height Threshold T = {}", TEST_NAME, height_threshold);
+impl DFTSpectral {
+    pub fn create(legacy_threshold: bool) -> Self {
+        DFTSpectral { legacy_threshold }
+    }
+}
 
-    // calculate expected theoretical (95%) number of peaks N_0 = (0.95 * length) / 2.0
-    // also calculate actual observed number N_1 of peaks in M with peaks < T
-    let n_0 = constants::N_0_CONSTANT * (length as f64);
+impl customtypes::StatisticalTest for DFTSpectral {
+    fn test(&self) -> customtypes::Test {
+        TEST_NAME
+    }
 
-    let mut n_1 = 0.0;
-    for value in spectrum.iter().take(length / 2) {
-        // calculate modulus defined as |z| = sqrt(a^2 + b^2)
-        let modulus = value.norm();
-        if modulus < height_threshold {
-            n_1 += 1.0;
+    fn params(&self) -> Vec<(String, String)> {
+        // the threshold of the specification is the default and not listed
+        if self.legacy_threshold {
+            vec![("threshold".to_owned(), "legacy".to_owned())]
+        } else {
+            Vec::new()
         }
     }
+
+    fn compute(&self, bit_sequence: &BitSequence) -> Result<customtypes::TestStatistics> {
+        log::trace!("dft_spectral::compute()");
+
+        // check if bit sequence is valid
+        let length =
+            utils::evaluate_bit_sequence(TEST_NAME, bit_sequence, constants::RECOMMENDED_SIZE_DFT)
+                .with_context(|| "Invalid bit sequence passed")?;
+
+        // perform discrete fourier transform on given bit string to retrieve the results
+        let spectrum = apply_dft(bit_sequence, length)
+            .with_context(|| "Failed to perform the discrete fourier transform")?;
+
+        // calculate height threshold T = sqrt(ln(1/0.05) * length), or with log10 for the legacy
+        // threshold
+        let log_arg = if self.legacy_threshold {
+            constants::LOG_ARG.log10()
+        } else {
+            constants::LOG_ARG.ln()
+        };
+        let height_threshold = (log_arg * (length as f64)).sqrt();
+        log::debug!("{}: Height Threshold T = {}", TEST_NAME, height_threshold);
+
+        // calculate the observed number N_1 of peaks in M with peaks < T
+        let n_1 = spectrum
+            .iter()
+            .take(length / 2)
+            // calculate modulus defined as |z| = sqrt(a^2 + b^2)
+            .filter(|value| value.norm() < height_threshold)
+            .count() as f64;
+
+        let (normalized_diff, p_value) = compute_p_value(n_1, length);
+        log::info!("{}: p-value = {}", TEST_NAME, p_value);
+
+        Ok(customtypes::TestStatistics::create(
+            vec![("N_1".to_owned(), n_1), ("d".to_owned(), normalized_diff)],
+            vec![("p-value".to_owned(), p_value)],
+        ))
+    }
+}
+
+/// Compute the normalized difference between the observed and the expected number of peaks and
+/// the resulting p-value.
+///
+/// # Arguments
+///
+/// n_1 - The observed number N_1 of peaks below the threshold
+/// length - The length n of the bit string
+///
+/// # Return
+///
+/// (normalized_diff, p-value) - The normalized difference d and the p-value
+fn compute_p_value(n_1: f64, length: usize) -> (f64, f64) {
+    log::trace!("dft_spectral::compute_p_value()");
+
+    // calculate expected theoretical (95%) number of peaks N_0 = (0.95 * length) / 2.0
+    let n_0 = constants::N_0_CONSTANT * (length as f64);
     log::debug!("{}: N_0 = {}, N_1 = {}", TEST_NAME, n_0, n_1);
 
     // compute normalized difference d = (N_1 - N_0) / (sqrt((length * 0.95 * 0.05) / 4.0))
@@ -68,55 +137,47 @@ pub fn perform_test(bit_string: &str) -> Result<f64> {
     // finally, compute p-value to decide whether given bit string is random or not
     // Therefore we need the complementary error function: erfc(|normalized_diff| / sqrt(2))
     let p_value = statrs::function::erf::erfc(normalized_diff.abs() / std::f64::consts::SQRT_2);
-    log::info!("{}: p-value = {}", TEST_NAME, p_value);
-
-    // capture the current time after the test got executed and calculate elapsed time
-    let end_time = std::time::Instant::now();
-    let elapsed_time = end_time.duration_since(start_time).as_secs_f64();
-    log::info!("{} took {:.6} seconds", TEST_NAME, elapsed_time);
 
-    Ok(p_value)
+    (normalized_diff, p_value)
 }
 
-/// Perform the discrete fourier transform on given bit string.
+/// Perform the discrete fourier transform on given bit string. As the signal is real, only the
+/// first n / 2 + 1 frequency bins are computed, the others are their complex conjugates.
 ///
 /// # Arguments
 ///
-/// bit_string - The bit string the DFT has to be applied on
+/// bit_sequence - The bit sequence the DFT has to be applied on
 /// signal_len - The length of the given bit string
 ///
 /// # Return
 ///
-/// abs_real_part - The performed DFT
-fn apply_dft(bit_string: &str, signal_len: usize) -> Vec<Complex<f64>> {
+/// Ok(spectrum) - The frequency bins 0, ..., n / 2 of the DFT
+/// Err(err) - Some error occured
+fn apply_dft(bit_sequence: &BitSequence, signal_len: usize) -> Result<Vec<Complex<f64>>> {
     log::trace!("dft_spectral::apply_dft()");
 
-    // convert the bit string into a sequence of real numbers
-    let signal: Vec<f64> = bit_string
-        .chars()
-        .map(|c| if c == '1' { 1.0 } else { -1.0 })
-        .collect();
-    log::trace!("{}: Signal: {:?}", TEST_NAME, signal);
-
-    // create a planner for FFT with the given signal length
-    let mut planner = FftPlanner::<f64>::new();
+    // create a planner for the real-to-complex FFT with the given signal length
+    let mut planner = RealFftPlanner::<f64>::new();
     let fft = planner.plan_fft_forward(signal_len);
 
-    // convert the real numbers into complex numbers
-    let mut spectrum: Vec<Complex<f64>> = signal.iter().map(|&x| Complex::new(x, 0.0)).collect();
+    // convert the bit sequence into a sequence of real numbers
+    let mut signal = fft.make_input_vec();
+    for (x, bit) in signal.iter_mut().zip(bit_sequence) {
+        *x = if bit { 1.0 } else { -1.0 };
+    }
 
     // perform the DFT
-    fft.process(&mut spectrum);
-
-    for (i, value) in spectrum.iter().enumerate() {
-        log::trace!("{}: Frequency Bin {}: {:?}", TEST_NAME, i, value);
-    }
+    let mut spectrum = fft.make_output_vec();
+    fft.process(&mut signal, &mut spectrum)
+        .map_err(|err| anyhow::anyhow!("{}", err))?;
 
-    spectrum
+    Ok(spectrum)
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::bit_sequence::BitSequence;
+    use crate::customtypes::StatisticalTest;
     use crate::dft_spectral;
     use crate::logger;
     use crate::utils;
@@ -124,20 +185,47 @@ mod tests {
     const LOGLEVEL: &str = "Debug";
     const BIT_STRING_1: &str = "1001010011"; // example from NIST Paper. p-value should be 0.029523
     const BIT_STRING_2: &str = "1100100100001111110110101010001000100001011010001100001000110100110001001100011001100010100010111000";
-    const INVALID_BIT_STRING: &str = "010101111010101010101010101010a0101010101010100101010101";
+    const N_1_NIST_2: f64 = 46.0;
+    const D_NIST_2: f64 = -1.376494;
+    const P_VALUE_NIST_2: f64 = 0.168669;
+    const N_1_DFT_2: f64 = 48.0;
+    const P_VALUE_DFT_2: f64 = 0.646355;
+    const N_1_E_LEGACY: f64 = 365355.0;
     const PI_FILE: &str = "/src/testdata/data.pi";
     const E_FILE: &str = "/src/testdata/data.e";
     const SQRT_2_FILE: &str = "/src/testdata/data.sqrt2";
     const SQRT_3_FILE: &str = "/src/testdata/data.sqrt3";
     const SHA_3_FILE: &str = "/src/testdata/data.sha3";
 
+    #[test]
+    fn test_compute_p_value() {
+        logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
+
+        // this only checks the formula of the p-value with the N_1 given by the example from NIST
+        // SP 800-22 section 2.6.8: N_1 = 46 and N_0 = 47.5 for n = 100 result in d = -1.376494 and
+        // the p-value 0.168669. The test itself counts N_1 = 48 on the example sequence
+        let (normalized_diff, p_value) = dft_spectral::compute_p_value(N_1_NIST_2, 100);
+        assert!((normalized_diff - D_NIST_2).abs() < 1e-6);
+        assert!((p_value - P_VALUE_NIST_2).abs() < 1e-6);
+    }
+
     #[test]
     fn test_dft_spectral() {
         logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
 
-        //  XXX Fix dicrete fourier transform
-        assert!(dft_spectral::perform_test(BIT_STRING_1).unwrap() != 1.00);
-        assert!(dft_spectral::perform_test(BIT_STRING_2).unwrap() != 1.00);
+        // Note: The exact DFT of the example sequence has 48 peaks below T = sqrt(ln(1/0.05) * 100),
+        // the example of section 2.6.8 states 46. The examples of section 2.6.4 and 2.6.8 can not
+        // be reproduced with the threshold they define
+        let result = dft_spectral::DFTSpectral::default()
+            .compute(&BIT_STRING_2.parse().unwrap())
+            .unwrap();
+        assert_eq!(result.statistics[0].1, N_1_DFT_2);
+        assert!((result.p_values[0].1 - P_VALUE_DFT_2).abs() < 1e-6);
+        assert_eq!(
+            dft_spectral::perform_test(&BIT_STRING_2.parse().unwrap()).unwrap(),
+            result.p_values[0].1
+        );
+        assert!(dft_spectral::perform_test(&BIT_STRING_1.parse().unwrap()).unwrap() >= 0.01);
 
         // test pi, e, sqrt(2) and sqrt(3) in their respective binary representations
         let pi_file = std::env::current_dir()
@@ -187,13 +275,62 @@ mod tests {
     }
 
     #[test]
-    fn test_dft_spectral_error_cases() {
+    fn test_dft_spectral_legacy_threshold() {
+        logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
+
+        // the legacy threshold reproduces the results of earlier versions
+        let legacy = dft_spectral::DFTSpectral::create(true);
+        assert_eq!(
+            legacy.params(),
+            [("threshold".to_owned(), "legacy".to_owned())]
+        );
+        assert!(dft_spectral::DFTSpectral::default().params().is_empty());
+
+        let e_file = std::env::current_dir()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_owned()
+            + E_FILE;
+        let e_bit_string = utils::read_random_numbers(&e_file).unwrap();
+        let result = legacy.compute(&e_bit_string).unwrap();
+        assert_eq!(result.statistics[0].1, N_1_E_LEGACY);
+        assert!(result.p_values[0].1 < 0.01);
+    }
+
+    #[test]
+    fn test_apply_dft() {
         logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
 
-        // pass empty string
-        assert!(dft_spectral::perform_test("").is_err());
+        // the bins of the real FFT match the DFT sum(x_k * exp(-2 * pi * i * j * k / n))
+        for bit_string in [BIT_STRING_1, BIT_STRING_2] {
+            let bit_sequence: BitSequence = bit_string.parse().unwrap();
+            let length = bit_sequence.len();
+            let spectrum = dft_spectral::apply_dft(&bit_sequence, length).unwrap();
+            assert_eq!(spectrum.len(), length / 2 + 1);
+
+            for (j, value) in spectrum.iter().enumerate() {
+                let (re, im) =
+                    bit_sequence
+                        .iter()
+                        .enumerate()
+                        .fold((0.0, 0.0), |(re, im), (k, bit)| {
+                            let x = if bit { 1.0 } else { -1.0 };
+                            let angle =
+                                -2.0 * std::f64::consts::PI * ((j * k) as f64) / (length as f64);
+                            (re + x * angle.cos(), im + x * angle.sin())
+                        });
+                assert!((value.re - re).abs() < 1e-9);
+                assert!((value.im - im).abs() < 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn test_dft_spectral_error_cases() {
+        logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
 
-        // pass invalid bit string
-        assert!(dft_spectral::perform_test(INVALID_BIT_STRING).is_err());
+        // pass empty sequence
+        assert!(dft_spectral::perform_test(&BitSequence::new()).is_err());
     }
 }
@@ -0,0 +1,409 @@
+//! This module exports the results of the tests in machine-readable formats.
+//!
+//! The JSON export holds the full structure of a run: the metadata of the input and, for each
+//! test, its parameters and the statistics, p-values, verdicts and elapsed time of every stream.
+//! The CSV export has one row per p-value with its own verdict next to the verdict of the test. A
+//! run on the whole input is exported as a single stream. Streams without p-values, e.g. because
+//! the test was skipped, get a single row with the verdict.
+
+use crate::customtypes;
+use anyhow::Result;
+
+const CSV_HEADER: [&str; 12] = [
+    "source",
+    "sha256",
+    "test",
+    "params",
+    "stream",
+    "label",
+    "p_value",
+    "alpha",
+    "result",
+    "verdict",
+    "elapsed_time",
+    "error",
+];
+
+/// Write the results as JSON.
+///
+/// # Arguments
+///
+/// writer - The destination of the JSON document
+/// metadata - The metadata of the tested input
+/// results - The results of all streams per test
+/// alpha - The significance level the streams were tested with
+///
+/// # Return
+///
+/// Ok(()) - The results were written
+/// Err(err) - Some error occured
+pub fn write_json(
+    writer: &mut impl std::io::Write,
+    metadata: &customtypes::InputMetadata,
+    results: &[customtypes::MultiStreamResult],
+    alpha: f64,
+) -> Result<()> {
+    log::trace!("export::write_json()");
+
+    let number_of_streams = results
+        .first()
+        .map_or(0, |result| result.stream_results.len());
+    let tests = results
+        .iter()
+        .map(|result| {
+            let streams = result
+                .stream_results
+                .iter()
+                .enumerate()
+                .map(|(stream, stream_result)| match stream_result {
+                    Ok(test_result) => {
+                        let mut stream_result = serde_json::json!({
+                            "stream": stream,
+                            "statistics": named_values(&test_result.statistics),
+                            "p_values": p_values_with_verdicts(test_result),
+                            "verdict": test_result.verdict.to_string(),
+                            "elapsed_time": test_result.elapsed_time,
+                        });
+                        if let Some(reason) = &test_result.reason {
+                            stream_result["reason"] = serde_json::json!(reason);
+                        }
+
+                        stream_result
+                    }
+                    Err(err) => serde_json::json!({
+                        "stream": stream,
+                        "error": format!("{:#}", err),
+                    }),
+                })
+                .collect::<Vec<_>>();
+
+            serde_json::json!({
+                "test": result.test.id(),
+                "name": result.test.to_string().trim_matches('\'').to_owned(),
+                "params": named_values(&result.params),
+                "streams": streams,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let document = serde_json::json!({
+        "input": {
+            "source": metadata.source,
+            "format": metadata.input_format.to_string(),
+            "bit_order": metadata.bit_order.to_string(),
+            "length": metadata.length,
+            "sha256": metadata.sha256,
+        },
+        "alpha": alpha,
+        "number_of_streams": number_of_streams,
+        "results": tests,
+    });
+
+    serde_json::to_writer_pretty(&mut *writer, &document)?;
+    writeln!(writer)?;
+
+    Ok(())
+}
+
+/// Write the results as CSV with one row per p-value. A stream on which a test could not be
+/// performed gets a single row holding the error, a skipped stream holds the reason instead.
+///
+/// # Arguments
+///
+/// writer - The destination of the CSV table
+/// metadata - The metadata of the tested input
+/// results - The results of all streams per test
+/// alpha - The significance level the streams were tested with
+///
+/// # Return
+///
+/// Ok(()) - The results were written
+/// Err(err) - Some error occured
+pub fn write_csv(
+    writer: &mut impl std::io::Write,
+    metadata: &customtypes::InputMetadata,
+    results: &[customtypes::MultiStreamResult],
+    alpha: f64,
+) -> Result<()> {
+    log::trace!("export::write_csv()");
+
+    writeln!(writer, "{}", CSV_HEADER.join(","))?;
+
+    for result in results {
+        let params = result
+            .params
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect::<Vec<_>>()
+            .join(";");
+
+        for (stream, stream_result) in result.stream_results.iter().enumerate() {
+            let prefix = [
+                metadata.source.clone(),
+                metadata.sha256.clone(),
+                result.test.id().to_owned(),
+                params.clone(),
+                stream.to_string(),
+            ];
+
+            match stream_result {
+                Ok(test_result) => {
+                    for ((label, p_value), (_, p_value_verdict)) in test_result
+                        .p_values
+                        .iter()
+                        .zip(&test_result.p_value_verdicts)
+                    {
+                        let row = [
+                            label.clone(),
+                            p_value.to_string(),
+                            alpha.to_string(),
+                            p_value_verdict.to_string(),
+                            test_result.verdict.to_string(),
+                            test_result.elapsed_time.to_string(),
+                            String::new(),
+                        ];
+                        write_csv_row(writer, prefix.iter().chain(row.iter()))?;
+                    }
+
+                    if test_result.p_values.is_empty() {
+                        let row = [
+                            String::new(),
+                            String::new(),
+                            alpha.to_string(),
+                            String::new(),
+                            test_result.verdict.to_string(),
+                            test_result.elapsed_time.to_string(),
+                            test_result.reason.clone().unwrap_or_default(),
+                        ];
+                        write_csv_row(writer, prefix.iter().chain(row.iter()))?;
+                    }
+                }
+                Err(err) => {
+                    let row = [
+                        String::new(),
+                        String::new(),
+                        alpha.to_string(),
+                        String::new(),
+                        String::new(),
+                        String::new(),
+                        format!("{:#}", err),
+                    ];
+                    write_csv_row(writer, prefix.iter().chain(row.iter()))?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Convert pairs of name and value into a JSON array, which keeps the order of the pairs.
+///
+/// # Arguments
+///
+/// values - The pairs of name and value
+///
+/// # Return
+///
+/// array - The objects {"name": name, "value": value}
+fn named_values<T: Clone>(values: &[(String, T)]) -> serde_json::Value
+where
+    serde_json::Value: From<T>,
+{
+    values
+        .iter()
+        .map(|(name, value)| {
+            serde_json::json!({
+                "name": name,
+                "value": serde_json::Value::from(value.clone()),
+            })
+        })
+        .collect()
+}
+
+/// Convert the p-values of a test result into a JSON array, which keeps the order of the p-values.
+///
+/// # Arguments
+///
+/// test_result - The result holding the p-values and their verdicts
+///
+/// # Return
+///
+/// array - The objects {"name": label, "value": p_value, "verdict": verdict}
+fn p_values_with_verdicts(test_result: &customtypes::TestResult) -> serde_json::Value {
+    test_result
+        .p_values
+        .iter()
+        .zip(&test_result.p_value_verdicts)
+        .map(|((label, p_value), (_, verdict))| {
+            serde_json::json!({
+                "name": label,
+                "value": p_value,
+                "verdict": verdict.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Write a single CSV row. Fields containing a separator, a quote or a line break are quoted.
+///
+/// # Arguments
+///
+/// writer - The destination of the CSV table
+/// fields - The fields of the row
+///
+/// # Return
+///
+/// Ok(()) - The row was written
+/// Err(err) - Some error occured
+fn write_csv_row<'a>(
+    writer: &mut impl std::io::Write,
+    fields: impl Iterator<Item = &'a String>,
+) -> Result<()> {
+    let row = fields
+        .map(|field| {
+            if field.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.clone()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+    writeln!(writer, "{}", row)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::constants;
+    use crate::customtypes;
+    use crate::export;
+    use crate::logger;
+
+    const LOGLEVEL: &str = "Debug";
+    const REASON_SKIPPED: &str = "'Frequency Monobit Test' failed with p-value 0.000042";
+    const SHA256_ABC: &str = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+
+    /// Create the results of two streams: one with two p-values and one with an error
+    fn create_results() -> Vec<customtypes::MultiStreamResult> {
+        let statistics = customtypes::TestStatistics::create(
+            vec![("chi_square".to_owned(), 1.5)],
+            vec![("p-value1".to_owned(), 0.5), ("p-value2".to_owned(), 0.008)],
+        );
+        let stream_results = vec![
+            Ok(customtypes::TestResult::create(
+                customtypes::Test::Serial,
+                vec![("m".to_owned(), "3".to_owned())],
+                statistics,
+                0.25,
+                constants::P_VALUE_THRESHOLD,
+            )),
+            Err(anyhow::anyhow!("Bit sequence too short, \"m\" too large")),
+            Ok(customtypes::TestResult::skipped(
+                customtypes::Test::Serial,
+                vec![("m".to_owned(), "3".to_owned())],
+                REASON_SKIPPED.to_owned(),
+                constants::P_VALUE_THRESHOLD,
+            )),
+        ];
+
+        vec![customtypes::MultiStreamResult::create(
+            customtypes::Test::Serial,
+            vec![("m".to_owned(), "3".to_owned())],
+            stream_results,
+        )]
+    }
+
+    fn create_metadata() -> customtypes::InputMetadata {
+        customtypes::InputMetadata::create(
+            "data.e",
+            customtypes::InputFormat::Ascii,
+            customtypes::BitOrder::MsbFirst,
+            24,
+            SHA256_ABC.to_owned(),
+        )
+    }
+
+    #[test]
+    fn test_write_json() {
+        logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
+
+        let mut json = Vec::new();
+        export::write_json(
+            &mut json,
+            &create_metadata(),
+            &create_results(),
+            constants::P_VALUE_THRESHOLD,
+        )
+        .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&json).unwrap();
+
+        assert_eq!(json["input"]["source"], "data.e");
+        assert_eq!(json["input"]["format"], "ascii");
+        assert_eq!(json["input"]["length"], 24);
+        assert_eq!(json["input"]["sha256"], SHA256_ABC);
+        assert_eq!(json["alpha"], constants::P_VALUE_THRESHOLD);
+        assert_eq!(json["number_of_streams"], 3);
+
+        let serial = &json["results"][0];
+        assert_eq!(serial["test"], "serial");
+        assert_eq!(serial["name"], "Serial Test");
+        assert_eq!(
+            serial["params"],
+            serde_json::json!([{"name": "m", "value": "3"}])
+        );
+
+        let stream = &serial["streams"][0];
+        assert_eq!(stream["stream"], 0);
+        assert_eq!(stream["statistics"][0]["name"], "chi_square");
+        assert_eq!(stream["statistics"][0]["value"], 1.5);
+        assert_eq!(stream["p_values"][1]["name"], "p-value2");
+        assert_eq!(stream["p_values"][1]["value"], 0.008);
+
+        // a single p-value less than alpha does not fail a test with two p-values
+        assert_eq!(stream["p_values"][1]["verdict"], "FAIL");
+        assert_eq!(stream["verdict"], "PASS");
+        assert_eq!(stream["elapsed_time"], 0.25);
+        assert_eq!(
+            serial["streams"][1]["error"],
+            "Bit sequence too short, \"m\" too large"
+        );
+        assert_eq!(serial["streams"][2]["verdict"], "SKIPPED");
+        assert_eq!(serial["streams"][2]["reason"], REASON_SKIPPED);
+        assert!(serial["streams"][0].get("reason").is_none());
+    }
+
+    #[test]
+    fn test_write_csv() {
+        logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
+
+        let mut csv = Vec::new();
+        export::write_csv(
+            &mut csv,
+            &create_metadata(),
+            &create_results(),
+            constants::P_VALUE_THRESHOLD,
+        )
+        .unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+
+        assert_eq!(
+            csv.lines().collect::<Vec<_>>(),
+            [
+                "source,sha256,test,params,stream,label,p_value,alpha,result,verdict,elapsed_time,error",
+                &format!("data.e,{},serial,m=3,0,p-value1,0.5,0.01,PASS,PASS,0.25,", SHA256_ABC),
+                &format!("data.e,{},serial,m=3,0,p-value2,0.008,0.01,FAIL,PASS,0.25,", SHA256_ABC),
+                &format!(
+                    "data.e,{},serial,m=3,1,,,0.01,,,,\"Bit sequence too short, \"\"m\"\" too large\"",
+                    SHA256_ABC
+                ),
+                &format!(
+                    "data.e,{},serial,m=3,2,,,0.01,,SKIPPED,0,{}",
+                    SHA256_ABC, REASON_SKIPPED
+                ),
+            ]
+        );
+    }
+}
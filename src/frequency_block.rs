@@ -7,8 +7,10 @@
 //! assumption of randomness. For block size M=1, this test degenerates to test 1, the Frequency (Monobit)
 //! test."
 
+use crate::bit_sequence::BitSequence;
 use crate::constants;
 use crate::customtypes;
+use crate::customtypes::StatisticalTest;
 use crate::utils;
 use anyhow::{bail, Context, Result};
 
@@ -18,51 +20,80 @@ const TEST_NAME: customtypes::Test = customtypes::Test::FrequencyBlock;
 ///
 /// # Arguments
 ///
-/// bit_string - The bit string to be tested for randomness
+/// bit_sequence - The bit sequence to be tested for randomness
 /// block_size - Divide the bit string into equal blocks of size M
 ///
 /// # Return
 ///
 /// Ok(p-value) - The p-value which indicates whether randomness is given or not
 /// Err(err) - Some error occured
-pub fn perform_test(bit_string: &str, block_size: usize) -> Result<f64> {
+pub fn perform_test(bit_sequence: &BitSequence, block_size: usize) -> Result<f64> {
     log::trace!("frequency_block::perform_test()");
 
-    // capture the current time before executing the actual test
-    let start_time = std::time::Instant::now();
-
-    // check if bit string contains invalid characters
-    let length = utils::evaluate_bit_string(TEST_NAME, bit_string, constants::RECOMMENDED_SIZE)
-        .with_context(|| "Invalid character(s) in passed bit string detected")?;
-
-    // check block size M for validity and get number of blocks N
-    let number_of_blocks = evaluate_block_size(length, block_size).with_context(|| {
-        format!(
-            "{TEST_NAME}: Either block size M or number of blocks N does not fit to defined requirements")
-    })?;
-
-    // Calculate pi_i = #ones_per_block/block_size
-    let pi_i = compute_pi_i(bit_string, number_of_blocks, block_size);
-
-    // now compute the chi_square statistics: chi_square = 4 * M * sum(p_i - 0.5)^2
-    let chi_square = compute_chi_square(block_size, pi_i);
-
-    // finally, compute the p-value using the incomplete gamma function: igamc(N/2, chi_square/2)
-    // Note: If we do have a perfect distribution (M/2 ones in each block), chi_square is zero
-    // which is an invalid input for igamc. Return p-value of 1 then
-    let p_value = if chi_square == 0.0 {
-        1.0
-    } else {
-        statrs::function::gamma::gamma_ur((number_of_blocks as f64) * 0.5, chi_square * 0.5)
-    };
-    log::info!("{TEST_NAME}: p-value = {p_value}");
-
-    // capture the current time after the test got executed and calculate elapsed time
-    let end_time = std::time::Instant::now();
-    let elapsed_time = end_time.duration_since(start_time).as_secs_f64();
-    log::info!("{TEST_NAME} took {:.6} seconds", elapsed_time);
-
-    Ok(p_value)
+    let result =
+        FrequencyBlock::create(block_size).run(bit_sequence, constants::P_VALUE_THRESHOLD)?;
+
+    Ok(result.p_values[0].1)
+}
+
+/// Parameters of the "Frequency within a block" test
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrequencyBlock {
+    pub block_size: usize,
+}
+
+impl FrequencyBlock {
+    pub fn create(block_size: usize) -> Self {
+        FrequencyBlock { block_size }
+    }
+}
+
+impl customtypes::StatisticalTest for FrequencyBlock {
+    fn test(&self) -> customtypes::Test {
+        TEST_NAME
+    }
+
+    fn params(&self) -> Vec<(String, String)> {
+        vec![("block_size".to_owned(), self.block_size.to_string())]
+    }
+
+    fn compute(&self, bit_sequence: &BitSequence) -> Result<customtypes::TestStatistics> {
+        log::trace!("frequency_block::compute()");
+
+        let block_size = self.block_size;
+
+        // check if bit sequence is valid
+        let length =
+            utils::evaluate_bit_sequence(TEST_NAME, bit_sequence, constants::RECOMMENDED_SIZE)
+                .with_context(|| "Invalid bit sequence passed")?;
+
+        // check block size M for validity and get number of blocks N
+        let number_of_blocks = evaluate_block_size(length, block_size).with_context(|| {
+            format!(
+                "{TEST_NAME}: Either block size M or number of blocks N does not fit to defined requirements")
+        })?;
+
+        // Calculate pi_i = #ones_per_block/block_size
+        let pi_i = compute_pi_i(bit_sequence, number_of_blocks, block_size);
+
+        // now compute the chi_square statistics: chi_square = 4 * M * sum(p_i - 0.5)^2
+        let chi_square = compute_chi_square(block_size, pi_i);
+
+        // finally, compute the p-value using the incomplete gamma function: igamc(N/2, chi_square/2)
+        // Note: If we do have a perfect distribution (M/2 ones in each block), chi_square is zero
+        // which is an invalid input for igamc. Return p-value of 1 then
+        let p_value = if chi_square == 0.0 {
+            1.0
+        } else {
+            statrs::function::gamma::gamma_ur((number_of_blocks as f64) * 0.5, chi_square * 0.5)
+        };
+        log::info!("{TEST_NAME}: p-value = {p_value}");
+
+        Ok(customtypes::TestStatistics::create(
+            vec![("chi_square".to_owned(), chi_square)],
+            vec![("p-value".to_owned(), p_value)],
+        ))
+    }
 }
 
 fn evaluate_block_size(length: usize, block_size: usize) -> Result<usize> {
@@ -96,7 +127,11 @@ fn evaluate_block_size(length: usize, block_size: usize) -> Result<usize> {
     Ok(number_of_blocks)
 }
 
-fn compute_pi_i(bit_string: &str, number_of_blocks: usize, block_size: usize) -> Vec<f64> {
+fn compute_pi_i(
+    bit_sequence: &BitSequence,
+    number_of_blocks: usize,
+    block_size: usize,
+) -> Vec<f64> {
     log::trace!("frequncy_block::compute_pi_i()");
 
     let mut pi_i = Vec::<f64>::new();
@@ -105,13 +140,11 @@ fn compute_pi_i(bit_string: &str, number_of_blocks: usize, block_size: usize) ->
     let mut index = 0;
 
     for current_block in 0..number_of_blocks {
-        let block = &bit_string[index..(index + block_size)];
-        let count_ones = block.chars().filter(|&c| c == '1').count() as f64;
+        let count_ones = bit_sequence.count_ones_in(index..(index + block_size)) as f64;
         log::trace!(
-            "{TEST_NAME}: Block {}/{}: '{}' consists of {} ones",
+            "{TEST_NAME}: Block {}/{} consists of {} ones",
             current_block + 1,
             number_of_blocks,
-            block,
             count_ones
         );
 
@@ -142,6 +175,7 @@ fn compute_chi_square(block_size: usize, pi_i: Vec<f64>) -> f64 {
 
 #[cfg(test)]
 mod tests {
+    use crate::bit_sequence::BitSequence;
     use crate::constants;
     use crate::frequency_block;
     use crate::logger;
@@ -157,29 +191,42 @@ mod tests {
     const BIT_STRING_RANDOM: &str = "11101000100101110100010110100101111100000101010101000101110101010101011101101010010101000001011101110101";
     const BIT_STRING_NON_RANDOM: &str = "0000000000100000000000000000000000000100000000000000000000000000000010000000000000000000000000001000";
     const BIT_STRING_PERFECT: &str = "1111100000111110000011111000001111100000111110000011111000001111100000111110000011111000001111100000";
-    const INVALID_BIT_STRING: &str = "010101111010101010101010101010a0101010101010100101010101";
 
     #[test]
     fn test_frequency_block() {
         logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
 
         assert_eq!(
-            frequency_block::perform_test(BIT_STRING_NIST_1, 3).unwrap(),
+            frequency_block::perform_test(&BIT_STRING_NIST_1.parse().unwrap(), 3).unwrap(),
             P_VALUE_NIST_1
         );
         assert_eq!(
-            frequency_block::perform_test(BIT_STRING_NIST_2, 10).unwrap(),
+            frequency_block::perform_test(&BIT_STRING_NIST_2.parse().unwrap(), 10).unwrap(),
             P_VALUE_NIST_2
         );
-        assert!(frequency_block::perform_test(BIT_STRING_ONLY_ZEROS, 10).unwrap() < 0.01);
-        assert!(frequency_block::perform_test(BIT_STRING_ONLY_ONES, 10).unwrap() < 0.01);
+        assert!(
+            frequency_block::perform_test(&BIT_STRING_ONLY_ZEROS.parse().unwrap(), 10).unwrap()
+                < 0.01
+        );
+        assert!(
+            frequency_block::perform_test(&BIT_STRING_ONLY_ONES.parse().unwrap(), 10).unwrap()
+                < 0.01
+        );
         assert_eq!(
-            frequency_block::perform_test(BIT_STRING_ONLY_ZEROS, 10).unwrap(),
-            frequency_block::perform_test(BIT_STRING_ONLY_ONES, 10).unwrap()
+            frequency_block::perform_test(&BIT_STRING_ONLY_ZEROS.parse().unwrap(), 10).unwrap(),
+            frequency_block::perform_test(&BIT_STRING_ONLY_ONES.parse().unwrap(), 10).unwrap()
+        );
+        assert!(
+            frequency_block::perform_test(&BIT_STRING_RANDOM.parse().unwrap(), 10).unwrap() >= 0.01
+        );
+        assert!(
+            frequency_block::perform_test(&BIT_STRING_NON_RANDOM.parse().unwrap(), 20).unwrap()
+                < 0.01
+        );
+        assert!(
+            frequency_block::perform_test(&BIT_STRING_PERFECT.parse().unwrap(), 10).unwrap()
+                == 1.00
         );
-        assert!(frequency_block::perform_test(BIT_STRING_RANDOM, 10).unwrap() >= 0.01);
-        assert!(frequency_block::perform_test(BIT_STRING_NON_RANDOM, 20).unwrap() < 0.01);
-        assert!(frequency_block::perform_test(BIT_STRING_PERFECT, 10).unwrap() == 1.00);
 
         // test pi, e, sqrt(2) and sqrt(3) in their respective binary representations
         let pi_file = std::env::current_dir()
@@ -232,17 +279,15 @@ mod tests {
     fn test_frequency_block_error_cases() {
         logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
 
-        // pass empty string
-        assert!(frequency_block::perform_test("", 10).is_err());
-
-        // pass invalid bit string
-        assert!(frequency_block::perform_test(INVALID_BIT_STRING, 10).is_err());
+        // pass empty sequence
+        assert!(frequency_block::perform_test(&BitSequence::new(), 10).is_err());
 
         // pass wrong sizes of M
-        assert!(
-            frequency_block::perform_test(BIT_STRING_NON_RANDOM, BIT_STRING_NON_RANDOM.len())
-                .is_err()
-        );
-        assert!(frequency_block::perform_test(BIT_STRING_NON_RANDOM, 0).is_err());
+        assert!(frequency_block::perform_test(
+            &BIT_STRING_NON_RANDOM.parse().unwrap(),
+            BIT_STRING_NON_RANDOM.len()
+        )
+        .is_err());
+        assert!(frequency_block::perform_test(&BIT_STRING_NON_RANDOM.parse().unwrap(), 0).is_err());
     }
 }
@@ -1,5 +1,5 @@
 //! This module performs the Frequency Monobit Test.
-//! If this test does not pass, the remaining tests are NOT executed (makes sense, right?)
+//! If this test does not pass on a bit sequence, the suite runner skips the remaining tests on it.
 //!
 //! Description of test from NIST SP 800-22:
 //!
@@ -9,8 +9,10 @@
 //! is, the number of ones and zeroes in a sequence should be about the same. All subsequent tests depend on
 //! the passing of this test."
 
+use crate::bit_sequence::BitSequence;
 use crate::constants;
 use crate::customtypes;
+use crate::customtypes::StatisticalTest;
 use crate::utils;
 use anyhow::{Context, Result};
 
@@ -20,55 +22,68 @@ const TEST_NAME: customtypes::Test = customtypes::Test::FrequencyMonobit;
 ///
 /// # Arguments
 ///
-/// bit_string - The bit string to be tested for randomness
+/// bit_sequence - The bit sequence to be tested for randomness
 ///
 /// # Return
 ///
 /// Ok(p-value) - The p-value which indicates whether randomness is given or not
 /// Err(err) - Some error occured
-pub fn perform_test(bit_string: &str) -> Result<f64> {
+pub fn perform_test(bit_sequence: &BitSequence) -> Result<f64> {
     log::trace!("frequency_monobit::perform_test()");
 
-    // capture the current time before executing the actual test
-    let start_time = std::time::Instant::now();
+    let result = FrequencyMonobit.run(bit_sequence, constants::P_VALUE_THRESHOLD)?;
 
-    // check if bit string contains invalid characters
-    let length = utils::evaluate_bit_string(TEST_NAME, bit_string, constants::RECOMMENDED_SIZE)
-        .with_context(|| "Invalid character(s) in passed bit string detected")?
-        as f64;
+    Ok(result.p_values[0].1)
+}
 
-    // first of all, we need to compute the partial sum S_n. '1' is a +1 and '0' is a -1.
-    let partial_sum = compute_partial_sum(bit_string);
+/// The "Frequency Monobit" test. The test has no parameters
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrequencyMonobit;
 
-    // now calculate observed value S_obs = |S_n| / sqrt(length)
-    let observed = (partial_sum.abs() as f64) / length.sqrt();
-    log::debug!("{TEST_NAME}: Observed value S_obs: {observed}");
+impl customtypes::StatisticalTest for FrequencyMonobit {
+    fn test(&self) -> customtypes::Test {
+        TEST_NAME
+    }
 
-    // finally, compute p-value to decide whether given bit string is random or not
-    // Therefore we need the complementary error function: erfc(observed / sqrt(2))
-    let p_value = statrs::function::erf::erfc(observed / std::f64::consts::SQRT_2);
-    log::info!("{TEST_NAME}: p-value = {p_value}");
+    fn params(&self) -> Vec<(String, String)> {
+        Vec::new()
+    }
 
-    // capture the current time after the test got executed and calculate elapsed time
-    let end_time = std::time::Instant::now();
-    let elapsed_time = end_time.duration_since(start_time).as_secs_f64();
-    log::info!("{TEST_NAME} took {:.6} seconds", elapsed_time);
+    fn compute(&self, bit_sequence: &BitSequence) -> Result<customtypes::TestStatistics> {
+        log::trace!("frequency_monobit::compute()");
 
-    Ok(p_value)
-}
+        // check if bit sequence is valid
+        let length =
+            utils::evaluate_bit_sequence(TEST_NAME, bit_sequence, constants::RECOMMENDED_SIZE)
+                .with_context(|| "Invalid bit sequence passed")? as f64;
 
-fn compute_partial_sum(bit_string: &str) -> i64 {
-    log::trace!("frequency_monobit::compute_partial_sum()");
+        // first of all, we need to compute the partial sum S_n. '1' is a +1 and '0' is a -1.
+        let partial_sum = compute_partial_sum(bit_sequence);
+
+        // now calculate observed value S_obs = |S_n| / sqrt(length)
+        let observed = (partial_sum.abs() as f64) / length.sqrt();
+        log::debug!("{TEST_NAME}: Observed value S_obs: {observed}");
 
-    let mut partial_sum: i64 = 0;
+        // finally, compute p-value to decide whether given bit string is random or not
+        // Therefore we need the complementary error function: erfc(observed / sqrt(2))
+        let p_value = statrs::function::erf::erfc(observed / std::f64::consts::SQRT_2);
+        log::info!("{TEST_NAME}: p-value = {p_value}");
 
-    for bit in bit_string.chars() {
-        if bit == '1' {
-            partial_sum += 1;
-        } else {
-            partial_sum -= 1;
-        }
+        Ok(customtypes::TestStatistics::create(
+            vec![
+                ("S_n".to_owned(), partial_sum as f64),
+                ("S_obs".to_owned(), observed),
+            ],
+            vec![("p-value".to_owned(), p_value)],
+        ))
     }
+}
+
+fn compute_partial_sum(bit_sequence: &BitSequence) -> i64 {
+    log::trace!("frequency_monobit::compute_partial_sum()");
+
+    // every one adds +1 and every zero adds -1, so S_n = #ones - #zeros = 2 * #ones - n
+    let partial_sum = 2 * (bit_sequence.count_ones() as i64) - (bit_sequence.len() as i64);
 
     log::debug!("{TEST_NAME}: Partial Sum S_n: {partial_sum}");
 
@@ -77,7 +92,10 @@ fn compute_partial_sum(bit_string: &str) -> i64 {
 
 #[cfg(test)]
 mod tests {
+    use crate::bit_sequence::BitSequence;
     use crate::constants;
+    use crate::customtypes;
+    use crate::customtypes::StatisticalTest;
     use crate::frequency_monobit;
     use crate::logger;
     use crate::utils;
@@ -97,30 +115,29 @@ mod tests {
     const BIT_STRING_RANDOM: &str = "11001001000011111101101010100010001000010110100011000010001101001100010011000110011000101000101110001100100100001111110110101010001000100001011010001100001000110100110001001100011001100010100010111000";
     const BIT_STRING_PERFECT: &str = "0000000000000000000000000000000000000000000000000011111111111111111111111111111111111111111111111111";
     const PARTIAL_SUM_PERFECT: i64 = 0;
-    const INVALID_BIT_STRING: &str = "010101111010101010101010101010a0101010101010100101010101";
 
     #[test]
     fn test_partial_sum_computation() {
         logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
 
         assert_eq!(
-            frequency_monobit::compute_partial_sum(BIT_STRING_NIST_1),
+            frequency_monobit::compute_partial_sum(&BIT_STRING_NIST_1.parse().unwrap()),
             PARTIAL_SUM_1
         );
         assert_eq!(
-            frequency_monobit::compute_partial_sum(BIT_STRING_NIST_2),
+            frequency_monobit::compute_partial_sum(&BIT_STRING_NIST_2.parse().unwrap()),
             PARTIAL_SUM_2
         );
         assert_eq!(
-            frequency_monobit::compute_partial_sum(BIT_STRING_ONLY_ZEROS),
+            frequency_monobit::compute_partial_sum(&BIT_STRING_ONLY_ZEROS.parse().unwrap()),
             PARTIAL_SUM_ONLY_ZEROS
         );
         assert_eq!(
-            frequency_monobit::compute_partial_sum(BIT_STRING_ONLY_ONES),
+            frequency_monobit::compute_partial_sum(&BIT_STRING_ONLY_ONES.parse().unwrap()),
             PARTIAL_SUM_ONLY_ONES
         );
         assert_eq!(
-            frequency_monobit::compute_partial_sum(BIT_STRING_PERFECT),
+            frequency_monobit::compute_partial_sum(&BIT_STRING_PERFECT.parse().unwrap()),
             PARTIAL_SUM_PERFECT
         );
     }
@@ -130,22 +147,34 @@ mod tests {
         logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
 
         assert_eq!(
-            frequency_monobit::perform_test(BIT_STRING_NIST_1).unwrap(),
+            frequency_monobit::perform_test(&BIT_STRING_NIST_1.parse().unwrap()).unwrap(),
             P_VALUE_NIST_1
         );
         assert_eq!(
-            frequency_monobit::perform_test(BIT_STRING_NIST_2).unwrap(),
+            frequency_monobit::perform_test(&BIT_STRING_NIST_2.parse().unwrap()).unwrap(),
             P_VALUE_NIST_2
         );
-        assert!(frequency_monobit::perform_test(BIT_STRING_ONLY_ZEROS).unwrap() < 0.01);
-        assert!(frequency_monobit::perform_test(BIT_STRING_ONLY_ONES).unwrap() < 0.01);
+        assert!(
+            frequency_monobit::perform_test(&BIT_STRING_ONLY_ZEROS.parse().unwrap()).unwrap()
+                < 0.01
+        );
+        assert!(
+            frequency_monobit::perform_test(&BIT_STRING_ONLY_ONES.parse().unwrap()).unwrap() < 0.01
+        );
         assert_eq!(
-            frequency_monobit::perform_test(BIT_STRING_ONLY_ZEROS).unwrap(),
-            frequency_monobit::perform_test(BIT_STRING_ONLY_ONES).unwrap()
+            frequency_monobit::perform_test(&BIT_STRING_ONLY_ZEROS.parse().unwrap()).unwrap(),
+            frequency_monobit::perform_test(&BIT_STRING_ONLY_ONES.parse().unwrap()).unwrap()
+        );
+        assert!(
+            frequency_monobit::perform_test(&BIT_STRING_NON_RANDOM.parse().unwrap()).unwrap()
+                < 0.01
+        );
+        assert!(
+            frequency_monobit::perform_test(&BIT_STRING_RANDOM.parse().unwrap()).unwrap() >= 0.01
+        );
+        assert!(
+            frequency_monobit::perform_test(&BIT_STRING_PERFECT.parse().unwrap()).unwrap() == 1.00
         );
-        assert!(frequency_monobit::perform_test(BIT_STRING_NON_RANDOM).unwrap() < 0.01);
-        assert!(frequency_monobit::perform_test(BIT_STRING_RANDOM).unwrap() >= 0.01);
-        assert!(frequency_monobit::perform_test(BIT_STRING_PERFECT).unwrap() == 1.00);
 
         // test pi, e, sqrt(2) and sqrt(3) in their respective binary representations
         let pi_file = std::env::current_dir()
@@ -195,13 +224,38 @@ mod tests {
     }
 
     #[test]
-    fn test_frequency_monobit_error_cases() {
+    fn test_frequency_monobit_run() {
         logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
 
-        // pass empty string
-        assert!(frequency_monobit::perform_test("").is_err());
+        // the result carries statistics and p-value, the verdict depends on alpha
+        let test = frequency_monobit::FrequencyMonobit;
+        assert_eq!(test.name(), "'Frequency Monobit Test'");
+
+        let result = test.run(&BIT_STRING_NIST_1.parse().unwrap(), 0.01).unwrap();
+        assert_eq!(result.test, customtypes::Test::FrequencyMonobit);
+        assert!(result.params.is_empty());
+        assert_eq!(
+            result.statistics[0],
+            ("S_n".to_owned(), PARTIAL_SUM_1 as f64)
+        );
+        assert_eq!(result.p_value(), Some(P_VALUE_NIST_1));
+        assert_eq!(result.alpha, 0.01);
+        assert_eq!(result.verdict, customtypes::Verdict::Pass);
+        assert!(result.elapsed_time >= 0.0);
+
+        let result = test.run(&BIT_STRING_NIST_1.parse().unwrap(), 0.6).unwrap();
+        assert_eq!(result.verdict, customtypes::Verdict::Fail);
+
+        // alpha has to be a probability
+        assert!(test.run(&BIT_STRING_NIST_1.parse().unwrap(), 0.0).is_err());
+        assert!(test.run(&BIT_STRING_NIST_1.parse().unwrap(), 1.0).is_err());
+    }
+
+    #[test]
+    fn test_frequency_monobit_error_cases() {
+        logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
 
-        // pass invalid bit string
-        assert!(frequency_monobit::perform_test(INVALID_BIT_STRING).is_err());
+        // pass empty sequence
+        assert!(frequency_monobit::perform_test(&BitSequence::new()).is_err());
     }
 }
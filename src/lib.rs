@@ -0,0 +1,34 @@
+//! Implementation of the statistical tests of NIST SP 800-22 to verify the randomness of given
+//! bit sequences.
+//!
+//! The bits are loaded once into a [`bit_sequence::BitSequence`]. Each test module exposes a
+//! `perform_test` function and a struct holding the parameters of the test, which implements
+//! [`customtypes::StatisticalTest`].
+
+pub mod approximate_entropy;
+pub mod binary_matrix_rank;
+pub mod bit_sequence;
+pub mod constants;
+pub mod cumulative_sums;
+pub mod customtypes;
+pub mod dft_spectral;
+pub mod export;
+pub mod frequency_block;
+pub mod frequency_monobit;
+pub mod linear_complexity;
+pub mod logger;
+pub mod longest_run;
+pub mod maurers_universal;
+pub mod multi_stream;
+pub mod non_overlapping_template;
+pub mod overlapping_template;
+pub mod random_excursions;
+pub mod random_excursions_variant;
+pub mod report;
+pub mod runs;
+pub mod second_level;
+pub mod serial;
+pub mod suite;
+#[cfg(test)]
+mod test_helper;
+pub mod utils;
@@ -0,0 +1,373 @@
+//! This module performs the Linear Complexity Test.
+//!
+//! Description of test from NIST SP 800-22:
+//!
+//! "The focus of this test is the length of a linear feedback shift register (LFSR). The purpose of this test is
+//! to determine whether or not the sequence is complex enough to be considered random. Random sequences
+//! are characterized by longer LFSRs. An LFSR that is too short implies non-randomness."
+
+use crate::bit_sequence::BitSequence;
+use crate::constants;
+use crate::customtypes;
+use crate::customtypes::StatisticalTest;
+use crate::utils;
+use anyhow::{Context, Result};
+
+const TEST_NAME: customtypes::Test = customtypes::Test::LinearComplexity;
+
+/// Perform the Linear Complexity Test by determining the p-value.
+///
+/// # Arguments
+///
+/// bit_sequence - The bit sequence to be tested for randomness
+/// block_size_m - The length M of each block the bit string is divided into
+///
+/// # Return
+///
+/// Ok(p-value) - The p-value which indicates whether randomness is given or not
+/// Err(err) - Some error occured
+pub fn perform_test(bit_sequence: &BitSequence, block_size_m: usize) -> Result<f64> {
+    log::trace!("linear_complexity::perform_test()");
+
+    let result =
+        LinearComplexity::create(block_size_m).run(bit_sequence, constants::P_VALUE_THRESHOLD)?;
+
+    Ok(result.p_values[0].1)
+}
+
+/// Parameters of the "Linear Complexity" test
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LinearComplexity {
+    pub block_size_m: usize,
+}
+
+impl LinearComplexity {
+    pub fn create(block_size_m: usize) -> Self {
+        LinearComplexity { block_size_m }
+    }
+}
+
+impl customtypes::StatisticalTest for LinearComplexity {
+    fn test(&self) -> customtypes::Test {
+        TEST_NAME
+    }
+
+    fn params(&self) -> Vec<(String, String)> {
+        vec![("block_size_m".to_owned(), self.block_size_m.to_string())]
+    }
+
+    fn compute(&self, bit_sequence: &BitSequence) -> Result<customtypes::TestStatistics> {
+        log::trace!("linear_complexity::compute()");
+
+        let block_size_m = self.block_size_m;
+
+        // check if bit sequence is valid
+        let length = utils::evaluate_bit_sequence(
+            TEST_NAME,
+            bit_sequence,
+            constants::RECOMMENDED_SIZE_LINEAR_COMPLEXITY,
+        )
+        .with_context(|| "Invalid bit sequence passed")?;
+
+        // evaluate block size M and get the number of blocks N
+        let number_of_blocks = evaluate_test_params(length, block_size_m)
+            .with_context(|| "Block size M does not match defined requirements")?;
+
+        // compute the theoretical mean
+        // mu = M/2 + (9 + (-1)^(M+1)) / 36 - (M/3 + 2/9) / 2^M
+        let m = block_size_m as f64;
+        let sign = if block_size_m.is_multiple_of(2) {
+            1.0
+        } else {
+            -1.0
+        };
+        let mean = m * 0.5 + (9.0 - sign) / 36.0 - (m / 3.0 + 2.0 / 9.0) / 2.0_f64.powf(m);
+        log::debug!("{}: Theoretical mean mu = {}", TEST_NAME, mean);
+
+        // determine the linear complexity L_i of each block, compute T_i = (-1)^M * (L_i - mu) + 2/9
+        // and sort it into one of the categories v_0..v_6
+        let mut nu = [0_usize; 7];
+        for block in bit_sequence.blocks(block_size_m).take(number_of_blocks) {
+            let bits: Vec<u8> = block.iter().map(u8::from).collect();
+            let linear_complexity = berlekamp_massey(&bits);
+            let t_i = sign * (linear_complexity as f64 - mean) + 2.0 / 9.0;
+
+            nu[categorize(t_i)] += 1;
+        }
+        log::debug!("{}: Categories v_0..v_6: {:?}", TEST_NAME, nu);
+
+        // compute chi_square statistics against the theoretical probabilities pi_0..pi_6
+        let n = number_of_blocks as f64;
+        let chi_square = nu
+            .iter()
+            .zip(constants::PI_VALUES_LINEAR_COMPLEXITY.iter())
+            .map(|(&nu_i, &pi_i)| ((nu_i as f64) - n * pi_i).powf(2.0) / (n * pi_i))
+            .sum::<f64>();
+        log::debug!("{}: Chi_square value: {}", TEST_NAME, chi_square);
+
+        // finally, compute p-value with the incomplete gamma function: igamc(K/2, chi_square/2)
+        // with K = 6 degrees of freedom
+        let degrees_of_freedom = (constants::PI_VALUES_LINEAR_COMPLEXITY.len() - 1) as f64;
+        let p_value = statrs::function::gamma::gamma_ur(degrees_of_freedom * 0.5, chi_square * 0.5);
+        log::info!("{}: p-value = {}", TEST_NAME, p_value);
+
+        Ok(customtypes::TestStatistics::create(
+            vec![("chi_square".to_owned(), chi_square)],
+            vec![("p-value".to_owned(), p_value)],
+        ))
+    }
+}
+
+/// Evaluate passed block size and return the resulting number of blocks N.
+///
+/// # Arguments
+///
+/// bit_string_length - Length of bit string
+/// block_size_m - The length M of each block
+///
+/// # Return
+///
+/// Ok(number_of_blocks) - The number of blocks N to be processed
+/// Err(err) - Some error occured
+fn evaluate_test_params(bit_string_length: usize, block_size_m: usize) -> Result<usize> {
+    log::trace!("linear_complexity::evaluate_test_params()");
+
+    // at least one complete block is needed
+    if block_size_m == 0 || block_size_m > bit_string_length {
+        anyhow::bail!(
+            "{}: Block size M ({}) must be between 1 and {}",
+            TEST_NAME,
+            block_size_m,
+            bit_string_length
+        );
+    }
+
+    // recommended sizes for M: 500 <= M <= 5000. Log a warning if they do not match
+    if !(constants::RECOMMENDED_BLOCK_SIZE_M.0..=constants::RECOMMENDED_BLOCK_SIZE_M.1)
+        .contains(&block_size_m)
+    {
+        log::warn!(
+            "{}: Recommended block size M is between {} and {}, passed: {}",
+            TEST_NAME,
+            constants::RECOMMENDED_BLOCK_SIZE_M.0,
+            constants::RECOMMENDED_BLOCK_SIZE_M.1,
+            block_size_m
+        );
+    }
+
+    // the number of blocks N should be at least 200 for the chi_square approximation to hold
+    let number_of_blocks = bit_string_length / block_size_m;
+    if number_of_blocks < constants::MIN_BLOCKS_N_LINEAR_COMPLEXITY {
+        log::warn!(
+            "{}: Recommended number of blocks N is at least {}, actual: {}",
+            TEST_NAME,
+            constants::MIN_BLOCKS_N_LINEAR_COMPLEXITY,
+            number_of_blocks
+        );
+    }
+
+    log::info!(
+        "{}: Block size M = {}, Number of blocks N = {}",
+        TEST_NAME,
+        block_size_m,
+        number_of_blocks
+    );
+
+    Ok(number_of_blocks)
+}
+
+/// Determine the linear complexity of a block with the Berlekamp-Massey algorithm over GF(2).
+///
+/// # Arguments
+///
+/// block - The bits of the block, each either 0 or 1
+///
+/// # Return
+///
+/// linear_complexity - The length L of the shortest LFSR generating the block
+fn berlekamp_massey(block: &[u8]) -> usize {
+    let len = block.len();
+
+    // connection polynomial C(D) and its copy B(D) from before the last length change
+    let mut c = vec![0_u8; len + 1];
+    let mut b = vec![0_u8; len + 1];
+    c[0] = 1;
+    b[0] = 1;
+
+    let mut linear_complexity = 0;
+    let mut last_change: isize = -1;
+
+    for n in 0..len {
+        // compute the discrepancy d = s_n + sum(c_i * s_(n-i))
+        let mut discrepancy = block[n];
+        for i in 1..=linear_complexity {
+            discrepancy ^= c[i] & block[n - i];
+        }
+
+        if discrepancy == 1 {
+            let t = c.clone();
+            let shift = (n as isize - last_change) as usize;
+            for j in 0..=(len - shift) {
+                c[j + shift] ^= b[j];
+            }
+
+            if linear_complexity <= n / 2 {
+                linear_complexity = n + 1 - linear_complexity;
+                last_change = n as isize;
+                b = t;
+            }
+        }
+    }
+
+    linear_complexity
+}
+
+/// Sort a test statistic T_i into one of the seven categories v_0..v_6.
+///
+/// # Arguments
+///
+/// t_i - The test statistic of a block
+///
+/// # Return
+///
+/// index - The index of the category
+fn categorize(t_i: f64) -> usize {
+    if t_i <= -2.5 {
+        0
+    } else if t_i <= -1.5 {
+        1
+    } else if t_i <= -0.5 {
+        2
+    } else if t_i <= 0.5 {
+        3
+    } else if t_i <= 1.5 {
+        4
+    } else if t_i <= 2.5 {
+        5
+    } else {
+        6
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bit_sequence::BitSequence;
+    use crate::constants;
+    use crate::linear_complexity;
+    use crate::logger;
+    use crate::utils;
+
+    const LOGLEVEL: &str = "Debug";
+    const BIT_STRING_NIST_1: &str = "1101011110001";
+    const LINEAR_COMPLEXITY_NIST_1: usize = 4;
+    const P_VALUE_NIST_E_1000: f64 = 0.845406;
+    const NIST_LENGTH: usize = 1000000;
+    const BIT_STRING_PERIODIC: &str = "1101101101101101101101101101101101101101101101101101101101101101101101101101101101101101101101101101";
+
+    #[test]
+    fn test_berlekamp_massey() {
+        logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
+
+        let to_bits =
+            |bit_string: &str| -> Vec<u8> { bit_string.bytes().map(|bit| bit - b'0').collect() };
+
+        assert_eq!(
+            linear_complexity::berlekamp_massey(&to_bits(BIT_STRING_NIST_1)),
+            LINEAR_COMPLEXITY_NIST_1
+        );
+        assert_eq!(
+            linear_complexity::berlekamp_massey(&to_bits("0000000000")),
+            0
+        );
+        assert_eq!(
+            linear_complexity::berlekamp_massey(&to_bits("0000000001")),
+            10
+        );
+        assert_eq!(
+            linear_complexity::berlekamp_massey(&to_bits("1111111111")),
+            1
+        );
+        assert_eq!(
+            linear_complexity::berlekamp_massey(&to_bits(BIT_STRING_PERIODIC)),
+            2
+        );
+    }
+
+    #[test]
+    fn test_linear_complexity() {
+        logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
+
+        // a periodic sequence is generated by a very short LFSR
+        assert!(
+            linear_complexity::perform_test(&BIT_STRING_PERIODIC.parse().unwrap(), 10).unwrap()
+                < 0.01
+        );
+
+        // the first 10^6 bits of e with M = 1000 must match the example of NIST SP 800-22
+        let e_file = std::env::current_dir()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_owned()
+            + constants::E_FILE;
+        let e_bit_string = utils::read_random_numbers(&e_file).unwrap();
+        assert!(
+            linear_complexity::perform_test(&e_bit_string.slice(0..NIST_LENGTH), 500).unwrap()
+                >= 0.01
+        );
+        let p_value =
+            linear_complexity::perform_test(&e_bit_string.slice(0..NIST_LENGTH), 1000).unwrap();
+        assert!((p_value - P_VALUE_NIST_E_1000).abs() < 1e-6);
+
+        // test pi, sqrt(2), sqrt(3) and SHA-3 in their respective binary representations
+        let pi_file = std::env::current_dir()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_owned()
+            + constants::PI_FILE;
+        let pi_bit_string = utils::read_random_numbers(&pi_file).unwrap();
+        assert!(linear_complexity::perform_test(&pi_bit_string, 500).unwrap() >= 0.01);
+
+        let sqrt_2_file = std::env::current_dir()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_owned()
+            + constants::SQRT_2_FILE;
+        let sqrt_2_bit_string = utils::read_random_numbers(&sqrt_2_file).unwrap();
+        assert!(linear_complexity::perform_test(&sqrt_2_bit_string, 500).unwrap() >= 0.01);
+
+        let sqrt_3_file = std::env::current_dir()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_owned()
+            + constants::SQRT_3_FILE;
+        let sqrt_3_bit_string = utils::read_random_numbers(&sqrt_3_file).unwrap();
+        assert!(linear_complexity::perform_test(&sqrt_3_bit_string, 500).unwrap() >= 0.01);
+
+        let sha_3_file = std::env::current_dir()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_owned()
+            + constants::SHA_3_FILE;
+        let sha_3_bit_string = utils::read_random_numbers(&sha_3_file).unwrap();
+        assert!(linear_complexity::perform_test(&sha_3_bit_string, 500).unwrap() >= 0.01);
+    }
+
+    #[test]
+    fn test_linear_complexity_error_cases() {
+        logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
+
+        // pass empty sequence
+        assert!(linear_complexity::perform_test(&BitSequence::new(), 500).is_err());
+
+        // pass invalid block sizes
+        assert!(linear_complexity::perform_test(&BIT_STRING_PERIODIC.parse().unwrap(), 0).is_err());
+        assert!(
+            linear_complexity::perform_test(&BIT_STRING_PERIODIC.parse().unwrap(), 101).is_err()
+        );
+    }
+}
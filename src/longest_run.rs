@@ -9,8 +9,10 @@
 //! the expected length of the longest run of ones implies that there is also an irregularity in the expected
 //! length of the longest run of zeroes. Therefore, only a test for ones is necessary."
 
+use crate::bit_sequence::BitSequence;
 use crate::constants;
 use crate::customtypes;
+use crate::customtypes::StatisticalTest;
 use crate::utils;
 use anyhow::{Context, Result};
 use std::collections::BTreeMap;
@@ -21,72 +23,91 @@ const TEST_NAME: customtypes::Test = customtypes::Test::LongestRun;
 ///
 /// # Arguments
 ///
-/// bit_string -  The bit string to be tested for randomness
+/// bit_sequence -  The bit string to be tested for randomness
 ///
 /// # Return
 ///
 /// Ok(p-value) - The p-value which indicates whether randomness is given or not
 /// Err(err) - Some error occured
-pub fn perform_test(bit_string: &str) -> Result<f64> {
+pub fn perform_test(bit_sequence: &BitSequence) -> Result<f64> {
     log::trace!("longest_run::perform_test()");
 
-    // capture the current time before executing the actual test
-    let start_time = std::time::Instant::now();
+    let result = LongestRun.run(bit_sequence, constants::P_VALUE_THRESHOLD)?;
 
-    // check if bit string contains invalid characters
-    let length = utils::evaluate_bit_string(TEST_NAME, bit_string, constants::MIN_LENGTH)
-        .with_context(|| "Invalid character(s) in passed bit string detected")?;
-
-    // evaluate bit string length and determine longest run configuration
-    let config = get_longest_run_config(length)
-        .with_context(|| format!("{TEST_NAME}: Failed to retrieve longest run configuration"))?;
+    Ok(result.p_values[0].1)
+}
 
-    // determine the number of runs per block and calculate v_i. A "longest" run is defined as the
-    // maximum number of consecutive ones in a block, e.g., "110010111" has the longest run as of 3
-    let mut counts: BTreeMap<i32, i32> = BTreeMap::new();
+/// The "Longest Run of Ones in a Block" test. Block size M and number of blocks N are derived
+/// from the length of the bit string
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LongestRun;
 
-    for block_num in 0..config.number_of_blocks {
-        let start_index = block_num * config.block_size;
-        let end_index = (block_num + 1) * config.block_size;
-        let block = &bit_string[start_index..end_index];
-        let max_consecutive_ones = count_max_consecutive_ones(block);
+impl customtypes::StatisticalTest for LongestRun {
+    fn test(&self) -> customtypes::Test {
+        TEST_NAME
+    }
 
-        *counts.entry(max_consecutive_ones).or_insert(0) += 1;
+    fn params(&self) -> Vec<(String, String)> {
+        Vec::new()
     }
 
-    log::debug!("{TEST_NAME}: Number of runs before merge: {:?}", counts);
-    let vi_counts = calculate_vi_values(counts, config.thresholds);
-    log::debug!("{TEST_NAME}: Number of runs after merge: {:?}", vi_counts);
+    fn compute(&self, bit_sequence: &BitSequence) -> Result<customtypes::TestStatistics> {
+        log::trace!("longest_run::compute()");
 
-    // Now we need to compute chi_square value
-    let mut chi_square = 0.0;
+        // check if bit sequence is valid
+        let length = utils::evaluate_bit_sequence(TEST_NAME, bit_sequence, constants::MIN_LENGTH)
+            .with_context(|| "Invalid bit sequence passed")?;
 
-    // iterate over vi_values and pi_values at the same time because both have same size
-    for ((_, vi_value), &pi_value) in vi_counts.iter().zip(config.pi_values.iter()) {
-        log::trace!(
-            "{TEST_NAME}: Current vi_value: {}, current pi_value: {}",
-            *vi_value,
-            pi_value
-        );
+        // evaluate bit string length and determine longest run configuration
+        let config = get_longest_run_config(length).with_context(|| {
+            format!("{TEST_NAME}: Failed to retrieve longest run configuration")
+        })?;
 
-        let constant = (config.number_of_blocks as f64) * pi_value;
-        chi_square += ((*vi_value as f64) - constant).powf(2.0) / constant;
-    }
-    log::debug!("{TEST_NAME}: Value of chi_square: {chi_square}");
+        // determine the number of runs per block and calculate v_i. A "longest" run is defined as the
+        // maximum number of consecutive ones in a block, e.g., "110010111" has the longest run as of 3
+        let mut counts: BTreeMap<i32, i32> = BTreeMap::new();
+
+        for block_num in 0..config.number_of_blocks {
+            let start_index = block_num * config.block_size;
+            let end_index = (block_num + 1) * config.block_size;
+            let block = bit_sequence.slice(start_index..end_index);
+            let max_consecutive_ones = count_max_consecutive_ones(&block);
+
+            *counts.entry(max_consecutive_ones).or_insert(0) += 1;
+        }
 
-    // finally compute p-value with the incomplete gamma function: igamc(K/2, chi_square/2)
-    let p_value = statrs::function::gamma::gamma_ur(
-        ((config.pi_values.len() as f64) - 1.0) * 0.5,
-        chi_square * 0.5,
-    );
-    log::info!("{TEST_NAME}: p-value = {p_value}");
+        log::debug!("{TEST_NAME}: Number of runs before merge: {:?}", counts);
+        let vi_counts = calculate_vi_values(counts, config.thresholds);
+        log::debug!("{TEST_NAME}: Number of runs after merge: {:?}", vi_counts);
 
-    // capture the current time after the test got executed and calculate elapsed time
-    let end_time = std::time::Instant::now();
-    let elapsed_time = end_time.duration_since(start_time).as_secs_f64();
-    log::info!("{TEST_NAME} took {:.6} seconds", elapsed_time);
+        // Now we need to compute chi_square value
+        let mut chi_square = 0.0;
 
-    Ok(p_value)
+        // iterate over vi_values and pi_values at the same time because both have same size
+        for ((_, vi_value), &pi_value) in vi_counts.iter().zip(config.pi_values.iter()) {
+            log::trace!(
+                "{TEST_NAME}: Current vi_value: {}, current pi_value: {}",
+                *vi_value,
+                pi_value
+            );
+
+            let constant = (config.number_of_blocks as f64) * pi_value;
+            chi_square += ((*vi_value as f64) - constant).powf(2.0) / constant;
+        }
+        log::debug!("{TEST_NAME}: Value of chi_square: {chi_square}");
+
+        // finally compute p-value with the incomplete gamma function: igamc(K/2, chi_square/2)
+        let p_value = statrs::function::gamma::gamma_ur(
+            ((config.pi_values.len() as f64) - 1.0) * 0.5,
+            chi_square * 0.5,
+        );
+        log::info!("{TEST_NAME}: p-value = {p_value}");
+
+        Ok(customtypes::TestStatistics::create(
+            vec![("chi_square".to_owned(), chi_square)],
+            vec![("p-value".to_owned(), p_value)],
+        ))
+    }
 }
 
 fn get_longest_run_config(length: usize) -> Result<customtypes::LongestRunConfig<'static>> {
@@ -131,14 +152,14 @@ fn get_longest_run_config(length: usize) -> Result<customtypes::LongestRunConfig
     Ok(config)
 }
 
-fn count_max_consecutive_ones(block: &str) -> i32 {
+fn count_max_consecutive_ones(block: &BitSequence) -> i32 {
     log::trace!("longest_run::count_max_consecutive_ones()");
 
     let mut max_count = 0;
     let mut current_count = 0;
 
-    for bit in block.chars() {
-        if bit == '1' {
+    for bit in block {
+        if bit {
             current_count += 1;
             max_count = max_count.max(current_count);
         } else {
@@ -180,6 +201,7 @@ fn calculate_vi_values(
 
 #[cfg(test)]
 mod tests {
+    use crate::bit_sequence::BitSequence;
     use crate::constants;
     use crate::logger;
     use crate::longest_run;
@@ -192,20 +214,19 @@ mod tests {
     const BIT_STRING_ONLY_ONES: &str = "11111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111";
     const BIT_STRING_RANDOM: &str = "10000000100000001000000010000000110000001100000011000000110000001110000011100000111000001110000011110000111100001111000011110000";
     const BIT_STRING_NON_RANDOM: &str = "10000000010000000000010000000000001000000000001000000000001000000000000100000000000000000010000000000010000000000111111111111111";
-    const INVALID_BIT_STRING: &str = "1100110000010101011011000100110011100000000000100100110101010001000100a111010110100000001101011111001100111001101101100010110010";
 
     #[test]
     fn test_longest_run() {
         logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
 
         assert_eq!(
-            longest_run::perform_test(BIT_STRING_NIST_1).unwrap(),
+            longest_run::perform_test(&BIT_STRING_NIST_1.parse().unwrap()).unwrap(),
             P_VALUE_NIST_1
         );
-        assert!(longest_run::perform_test(BIT_STRING_ONLY_ZEROS).unwrap() < 0.01);
-        assert!(longest_run::perform_test(BIT_STRING_ONLY_ONES).unwrap() < 0.01);
-        assert!(longest_run::perform_test(BIT_STRING_RANDOM).unwrap() >= 0.01);
-        assert!(longest_run::perform_test(BIT_STRING_NON_RANDOM).unwrap() < 0.01);
+        assert!(longest_run::perform_test(&BIT_STRING_ONLY_ZEROS.parse().unwrap()).unwrap() < 0.01);
+        assert!(longest_run::perform_test(&BIT_STRING_ONLY_ONES.parse().unwrap()).unwrap() < 0.01);
+        assert!(longest_run::perform_test(&BIT_STRING_RANDOM.parse().unwrap()).unwrap() >= 0.01);
+        assert!(longest_run::perform_test(&BIT_STRING_NON_RANDOM.parse().unwrap()).unwrap() < 0.01);
 
         // test pi, e, sqrt(2) and sqrt(3) in their respective binary representations
         let pi_file = std::env::current_dir()
@@ -258,10 +279,7 @@ mod tests {
     fn test_longest_run_error_cases() {
         logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
 
-        // pass empty string
-        assert!(longest_run::perform_test("").is_err());
-
-        // pass invalid bit string
-        assert!(longest_run::perform_test(INVALID_BIT_STRING).is_err());
+        // pass empty sequence
+        assert!(longest_run::perform_test(&BitSequence::new()).is_err());
     }
 }
@@ -1,21 +1,953 @@
-mod binary_matrix_rank;
-mod constants;
-mod cumulative_sums;
-mod customtypes;
-mod dft_spectral;
-mod frequency_block;
-mod frequency_monobit;
-mod logger;
-mod longest_run;
-mod non_overlapping_template;
-mod overlapping_template;
-mod runs;
-mod test_helper;
-mod utils;
-
-use anyhow::Result;
-
-fn main() -> Result<()> {
-    logger::init_logger("Trace")?;
+//! Command line interface of the NIST test suite. The bit string is read from the given file and
+//! the selected tests are performed on it, either as a whole or split into N streams of n bits.
+//! Every p-value is reported with its own verdict against alpha. The process exits with a non-zero
+//! code if any of the selected tests fails, i.e. one of its k p-values is less than alpha / k, or
+//! could not be performed.
+
+use anyhow::{Context, Result};
+use rust_nist_suite::bit_sequence::BitSequence;
+use rust_nist_suite::customtypes::{self, StatisticalTest};
+use rust_nist_suite::{
+    approximate_entropy, binary_matrix_rank, constants, cumulative_sums, dft_spectral, export,
+    frequency_block, frequency_monobit, linear_complexity, logger, longest_run, maurers_universal,
+    multi_stream, non_overlapping_template, overlapping_template, random_excursions,
+    random_excursions_variant, report, runs, second_level, serial, suite, utils,
+};
+use std::process::ExitCode;
+
+const USAGE: &str = "Usage: rust_nist_suite [OPTIONS] <FILE>
+
+Perform the statistical tests of NIST SP 800-22 on the bit string read from FILE, which may be a
+named pipe or '-' to read raw bytes from the standard input. If a stream
+length or a number of streams is passed, the tests are performed on each stream separately and
+the proportion of passing streams and the uniformity of their p-values are reported.
+
+Options:
+  -t, --tests <LIST>             Comma separated test names or numbers (1-15) [default: all]
+  -a, --alpha <ALPHA>            Significance level [default: 0.01]
+  -l, --log-level <LEVEL>        off, error, warn, info, debug or trace [default: warn]
+  -j, --jobs <JOBS>              Number of threads to perform the tests on [default: CPUs]
+      --prerequisites            Skip the tests if frequency_monobit fails first [default: without
+                                 streams]
+      --no-prerequisites         Perform every test also if frequency_monobit fails first
+                                 [default: with streams]
+  -f, --format <FORMAT>          auto, binary, ascii, hex or base64 [default: auto]
+      --bit-order <ORDER>        msb or lsb first within each byte [default: msb]
+  -b, --bits <COUNT>             Read at most COUNT bits of the input [default: n * N or all]
+  -n, --stream-len <n>           Split the input into streams of n bits [default: FILE / N]
+  -N, --streams <N>              Number of streams N to test [default: FILE / n]
+  -r, --report <REPORT>          Write a finalAnalysisReport.txt of the streams to REPORT
+      --json <JSON>              Write all results with the input metadata as JSON to JSON
+      --csv <CSV>                Write one row per p-value as CSV to CSV
+      --block-size <M>           Block size M of frequency_block [default: n / 100 + 1]
+      --dft-legacy-threshold     Use the log10 threshold of earlier versions in dft_spectral
+      --template-len <m>         Template length m of the template tests [default: 9]
+      --template-blocks <N>      Number of blocks N of non_overlapping_template [default: 8]
+      --matrix-rows <M>          Rows M of binary_matrix_rank [default: 32]
+      --matrix-columns <Q>       Columns Q of binary_matrix_rank [default: 32]
+      --universal-l <L>          Block length L of maurers_universal [default: by length]
+      --universal-q <Q>          Initialization blocks Q of maurers_universal [default: 10 * 2^L]
+      --linear-complexity-m <M>  Block size M of linear_complexity [default: 500]
+      --serial-m <m>             Pattern length m of serial [default: 16]
+      --apen-m <m>               Block length m of approximate_entropy [default: 10]
+      --cusum-mode <MODE>        forward, backward or both [default: both]
+  -h, --help                     Print this help
+
+Tests:
+  1 frequency_monobit, 2 frequency_block, 3 runs, 4 longest_run, 5 binary_matrix_rank,
+  6 dft_spectral, 7 non_overlapping_template, 8 overlapping_template, 9 maurers_universal,
+  10 linear_complexity, 11 serial, 12 approximate_entropy, 13 cumulative_sums,
+  14 random_excursions, 15 random_excursions_variant
+
+Exit status:
+  Each p-value is reported as PASS or FAIL against ALPHA. A test with k p-values only fails if
+  one of them is less than ALPHA / k, so a single one of the 148 templates of
+  non_overlapping_template below ALPHA does not fail the run. A test skipped because its
+  prerequisite failed counts as failed, since the prerequisite already rejects the input. The
+  exit status is 0 if no test failed, 1 if a test failed, was skipped or could not be performed
+  and 2 on invalid arguments. With streams, the exit status is 1 if the proportion or the
+  uniformity of a p-value fails, if a test was skipped on all streams or if a test or the
+  analysis of its p-values could not be performed.";
+
+/// Options passed on the command line
+#[derive(Debug, PartialEq)]
+struct Options {
+    input_file: String,
+    tests: Vec<customtypes::Test>,
+    alpha: f64,
+    log_level: String,
+    jobs: Option<usize>,
+    /// Perform the tests as a suite with prerequisites, by default only without streams
+    prerequisites: Option<bool>,
+    input_format: customtypes::InputFormat,
+    bit_order: customtypes::BitOrder,
+    max_bits: Option<usize>,
+    stream_len: Option<usize>,
+    number_of_streams: Option<usize>,
+    report_file: Option<String>,
+    json_file: Option<String>,
+    csv_file: Option<String>,
+    block_size: Option<usize>,
+    dft_legacy_threshold: bool,
+    template_len: usize,
+    template_blocks: usize,
+    matrix_rows_m: usize,
+    matrix_columns_q: usize,
+    universal_l: Option<usize>,
+    universal_q: Option<usize>,
+    linear_complexity_m: usize,
+    serial_m: usize,
+    apen_m: usize,
+    cusum_modes: Vec<customtypes::Mode>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            input_file: String::new(),
+            tests: customtypes::Test::ALL.to_vec(),
+            alpha: constants::P_VALUE_THRESHOLD,
+            log_level: "warn".to_owned(),
+            jobs: None,
+            prerequisites: None,
+            input_format: customtypes::InputFormat::Auto,
+            bit_order: customtypes::BitOrder::MsbFirst,
+            max_bits: None,
+            stream_len: None,
+            number_of_streams: None,
+            report_file: None,
+            json_file: None,
+            csv_file: None,
+            block_size: None,
+            dft_legacy_threshold: false,
+            template_len: constants::DEFAULT_TEMPLATE_LEN,
+            template_blocks: constants::DEFAULT_TEMPLATE_BLOCKS,
+            matrix_rows_m: constants::MATRIX_ROWS_M,
+            matrix_columns_q: constants::MATRIX_COLUMNS_Q,
+            universal_l: None,
+            universal_q: None,
+            linear_complexity_m: constants::DEFAULT_BLOCK_SIZE_LINEAR_COMPLEXITY,
+            serial_m: constants::DEFAULT_BLOCK_LEN_SERIAL,
+            apen_m: constants::DEFAULT_BLOCK_LEN_APPROXIMATE_ENTROPY,
+            cusum_modes: vec![customtypes::Mode::Forward, customtypes::Mode::Backward],
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(err) => {
+            eprintln!("Error: {:#}\n\n{}", err, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    match run(&options) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(err) => {
+            eprintln!("Error: {:#}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Parse the command line arguments.
+///
+/// # Arguments
+///
+/// args - The arguments without the name of the binary
+///
+/// # Return
+///
+/// Ok(Some(options)) - The parsed options
+/// Ok(None) - The help was requested
+/// Err(err) - Some error occured
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Option<Options>> {
+    let mut options = Options::default();
+    let mut input_file = None;
+
+    while let Some(arg) = args.next() {
+        // options take their value either from the next argument or after '='
+        let (name, inline_value) = match arg.split_once('=') {
+            Some((name, value)) if name.starts_with("--") => (name.to_owned(), Some(value)),
+            _ => (arg.clone(), None),
+        };
+        let mut value = || -> Result<String> {
+            match inline_value {
+                Some(value) => Ok(value.to_owned()),
+                None => args
+                    .next()
+                    .with_context(|| format!("Missing value for option '{}'", name)),
+            }
+        };
+
+        match name.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "-t" | "--tests" => options.tests = parse_tests(&value()?)?,
+            "-a" | "--alpha" => options.alpha = parse_value(&name, &value()?)?,
+            "-l" | "--log-level" => options.log_level = parse_log_level(&value()?)?,
+            "-j" | "--jobs" => options.jobs = Some(parse_jobs(&name, &value()?)?),
+            "--prerequisites" => options.prerequisites = Some(true),
+            "--no-prerequisites" => options.prerequisites = Some(false),
+            "-f" | "--format" => options.input_format = parse_value(&name, &value()?)?,
+            "--bit-order" => options.bit_order = parse_value(&name, &value()?)?,
+            "-b" | "--bits" => options.max_bits = Some(parse_value(&name, &value()?)?),
+            "-n" | "--stream-len" => options.stream_len = Some(parse_value(&name, &value()?)?),
+            "-N" | "--streams" => options.number_of_streams = Some(parse_value(&name, &value()?)?),
+            "-r" | "--report" => options.report_file = Some(value()?),
+            "--json" => options.json_file = Some(value()?),
+            "--csv" => options.csv_file = Some(value()?),
+            "--block-size" => options.block_size = Some(parse_value(&name, &value()?)?),
+            "--dft-legacy-threshold" => options.dft_legacy_threshold = true,
+            "--template-len" => options.template_len = parse_value(&name, &value()?)?,
+            "--template-blocks" => {
+                options.template_blocks = parse_template_blocks(&name, &value()?)?
+            }
+            "--matrix-rows" => options.matrix_rows_m = parse_value(&name, &value()?)?,
+            "--matrix-columns" => options.matrix_columns_q = parse_value(&name, &value()?)?,
+            "--universal-l" => options.universal_l = Some(parse_value(&name, &value()?)?),
+            "--universal-q" => options.universal_q = Some(parse_value(&name, &value()?)?),
+            "--linear-complexity-m" => options.linear_complexity_m = parse_value(&name, &value()?)?,
+            "--serial-m" => options.serial_m = parse_value(&name, &value()?)?,
+            "--apen-m" => options.apen_m = parse_value(&name, &value()?)?,
+            "--cusum-mode" => options.cusum_modes = parse_cusum_mode(&value()?)?,
+            _ if name.starts_with('-') && name.len() > 1 => {
+                anyhow::bail!("Unknown option '{}'", name)
+            }
+            _ => {
+                if input_file.replace(arg).is_some() {
+                    anyhow::bail!("Only one input file can be passed");
+                }
+            }
+        }
+    }
+
+    options.input_file = input_file.context("No input file passed")?;
+
+    Ok(Some(options))
+}
+
+/// Parse a value of an option.
+///
+/// # Arguments
+///
+/// name - The name of the option
+/// value - The value to be parsed
+///
+/// # Return
+///
+/// Ok(value) - The parsed value
+/// Err(err) - Some error occured
+fn parse_value<T: std::str::FromStr>(name: &str, value: &str) -> Result<T> {
+    match value.parse::<T>() {
+        Ok(value) => Ok(value),
+        Err(_) => anyhow::bail!("Invalid value '{}' for option '{}'", value, name),
+    }
+}
+
+/// Parse a comma separated list of test names or numbers. Duplicates are removed and the tests
+/// are sorted in the order of NIST SP 800-22.
+///
+/// # Arguments
+///
+/// value - The list of tests
+///
+/// # Return
+///
+/// Ok(tests) - The selected tests
+/// Err(err) - Some error occured
+fn parse_tests(value: &str) -> Result<Vec<customtypes::Test>> {
+    let mut tests = value
+        .split(',')
+        .filter(|test| !test.trim().is_empty())
+        .map(|test| test.parse::<customtypes::Test>())
+        .collect::<Result<Vec<_>>>()?;
+
+    if tests.is_empty() {
+        anyhow::bail!("No tests selected");
+    }
+
+    tests.sort_by_key(|test| test.number());
+    tests.dedup();
+
+    Ok(tests)
+}
+
+/// Parse the log level.
+///
+/// # Arguments
+///
+/// value - The log level
+///
+/// # Return
+///
+/// Ok(log_level) - The log level in lower case
+/// Err(err) - Some error occured
+fn parse_log_level(value: &str) -> Result<String> {
+    let log_level = value.to_lowercase();
+
+    match log_level.as_str() {
+        "off" | "error" | "warn" | "info" | "debug" | "trace" => Ok(log_level),
+        _ => anyhow::bail!("Invalid log level '{}'", value),
+    }
+}
+
+/// Parse the number of threads to perform the tests on.
+///
+/// # Arguments
+///
+/// name - The name of the option
+/// value - The number of threads
+///
+/// # Return
+///
+/// Ok(jobs) - The number of threads, at least 1
+/// Err(err) - Some error occured
+fn parse_jobs(name: &str, value: &str) -> Result<usize> {
+    match parse_value(name, value)? {
+        0 => anyhow::bail!("Option '{}' requires at least 1 thread", name),
+        jobs => Ok(jobs),
+    }
+}
+
+/// Parse the number of blocks N of the Non-overlapping Template Matching Test.
+///
+/// # Arguments
+///
+/// name - The name of the option
+/// value - The number of blocks
+///
+/// # Return
+///
+/// Ok(template_blocks) - The number of blocks, at least 1
+/// Err(err) - Some error occured
+fn parse_template_blocks(name: &str, value: &str) -> Result<usize> {
+    match parse_value(name, value)? {
+        0 => anyhow::bail!("Option '{}' requires at least 1 block", name),
+        template_blocks => Ok(template_blocks),
+    }
+}
+
+/// Parse the mode(s) of the Cumulative Sums Test.
+///
+/// # Arguments
+///
+/// value - forward, backward or both
+///
+/// # Return
+///
+/// Ok(modes) - The modes to perform the test with
+/// Err(err) - Some error occured
+fn parse_cusum_mode(value: &str) -> Result<Vec<customtypes::Mode>> {
+    match value.to_lowercase().as_str() {
+        "forward" => Ok(vec![customtypes::Mode::Forward]),
+        "backward" => Ok(vec![customtypes::Mode::Backward]),
+        "both" => Ok(vec![
+            customtypes::Mode::Forward,
+            customtypes::Mode::Backward,
+        ]),
+        _ => anyhow::bail!("Invalid cusum mode '{}'", value),
+    }
+}
+
+/// Create the selected tests with their parameters. Parameters which depend on the length of the
+/// bit string are derived here.
+///
+/// # Arguments
+///
+/// options - The parsed options
+/// length - The length of the bit string
+///
+/// # Return
+///
+/// Ok(tests) - The tests to be performed
+/// Err(err) - Some error occured
+fn create_tests(options: &Options, length: usize) -> Result<Vec<Box<dyn StatisticalTest>>> {
+    let mut tests = Vec::<Box<dyn StatisticalTest>>::new();
+
+    for test in &options.tests {
+        match test {
+            customtypes::Test::FrequencyMonobit => {
+                tests.push(Box::new(frequency_monobit::FrequencyMonobit))
+            }
+            customtypes::Test::FrequencyBlock => {
+                // the smallest block size M > n / 100, which results in N < 100 blocks
+                let block_size = options
+                    .block_size
+                    .unwrap_or(length / constants::RECOMMENDED_SIZE + 1);
+                tests.push(Box::new(frequency_block::FrequencyBlock::create(
+                    block_size,
+                )))
+            }
+            customtypes::Test::Runs => tests.push(Box::new(runs::Runs)),
+            customtypes::Test::LongestRun => tests.push(Box::new(longest_run::LongestRun)),
+            customtypes::Test::BinaryMatrixRank => {
+                tests.push(Box::new(binary_matrix_rank::BinaryMatrixRank::create(
+                    options.matrix_rows_m,
+                    options.matrix_columns_q,
+                )))
+            }
+            customtypes::Test::DFTSpectral => tests.push(Box::new(
+                dft_spectral::DFTSpectral::create(options.dft_legacy_threshold),
+            )),
+            customtypes::Test::NonOverlappingTemplate => tests.push(Box::new(
+                non_overlapping_template::NonOverlappingTemplate::create(
+                    options.template_len,
+                    options.template_blocks,
+                ),
+            )),
+            customtypes::Test::OverlappingTemplate => tests.push(Box::new(
+                overlapping_template::OverlappingTemplate::create(options.template_len),
+            )),
+            customtypes::Test::MaurersUniversalStatistical => {
+                // Q = 10 * 2^L is recommended. If the bit string is too short for any L, the
+                // test itself reports the error. A given Q is used with the selected L and
+                // checked against it by the test
+                let min_block_len_l = constants::RECOMMENDED_BLOCK_LEN_L.0;
+                let (block_len_l, init_blocks_q) = match (options.universal_l, options.universal_q)
+                {
+                    (Some(block_len_l), Some(init_blocks_q)) => (block_len_l, init_blocks_q),
+                    (Some(block_len_l), None) => {
+                        (block_len_l, 10 << block_len_l.min(constants::BLOCK_LEN_L.1))
+                    }
+                    (None, init_blocks_q) => {
+                        let (block_len_l, recommended_q) =
+                            maurers_universal::select_test_params(length)
+                                .unwrap_or((min_block_len_l, 10 << min_block_len_l));
+                        (block_len_l, init_blocks_q.unwrap_or(recommended_q))
+                    }
+                };
+                tests.push(Box::new(
+                    maurers_universal::MaurersUniversalStatistical::create(
+                        block_len_l,
+                        init_blocks_q,
+                    ),
+                ))
+            }
+            customtypes::Test::LinearComplexity => tests.push(Box::new(
+                linear_complexity::LinearComplexity::create(options.linear_complexity_m),
+            )),
+            customtypes::Test::Serial => {
+                tests.push(Box::new(serial::Serial::create(options.serial_m)))
+            }
+            customtypes::Test::ApproximateEntropy => tests.push(Box::new(
+                approximate_entropy::ApproximateEntropy::create(options.apen_m),
+            )),
+            customtypes::Test::CumulativeSums => {
+                for mode in &options.cusum_modes {
+                    tests.push(Box::new(cumulative_sums::CumulativeSums::create(*mode)))
+                }
+            }
+            customtypes::Test::RandomExcursions => {
+                tests.push(Box::new(random_excursions::RandomExcursions))
+            }
+            customtypes::Test::RandomExcursionsVariant => {
+                tests.push(Box::new(random_excursions_variant::RandomExcursionsVariant))
+            }
+        }
+    }
+
+    Ok(tests)
+}
+
+/// Read the bit string, perform the selected tests and print their results.
+///
+/// # Arguments
+///
+/// options - The parsed options
+///
+/// # Return
+///
+/// Ok(true) - All selected tests passed or were not applicable
+/// Ok(false) - At least one test failed or could not be performed. A test with k p-values
+/// fails if one of them is less than alpha / k, not already if one is less than alpha
+/// Err(err) - Some error occured
+fn run(options: &Options) -> Result<bool> {
+    logger::init_logger(&options.log_level)?;
+
+    // the tests, streams and templates are distributed over the threads of the global pool
+    if let Some(jobs) = options.jobs {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(jobs)
+            .build_global()
+            .context("Failed to create thread pool")?;
+    }
+
+    // if both the stream length and the number of streams are known, no more bits are needed
+    let max_bits = options.max_bits.or_else(|| {
+        options
+            .stream_len
+            .zip(options.number_of_streams)
+            .and_then(|(stream_len, number_of_streams)| stream_len.checked_mul(number_of_streams))
+    });
+    let (bit_sequence, sha256) = utils::read_random_numbers_from_source(
+        &options.input_file,
+        options.input_format,
+        options.bit_order,
+        max_bits,
+    )
+    .with_context(|| format!("Failed to read input file '{}'", options.input_file))?;
+    let metadata = customtypes::InputMetadata::create(
+        &options.input_file,
+        options.input_format,
+        options.bit_order,
+        bit_sequence.len(),
+        sha256,
+    );
+    println!(
+        "Input file '{}' contains {} bits",
+        options.input_file,
+        bit_sequence.len()
+    );
+
+    // the missing one of stream length n and number of streams N is derived from the input length.
+    // A report without any of them covers the whole input as a single stream
+    let length = bit_sequence.len();
+    let (success, results) = match (options.stream_len, options.number_of_streams) {
+        (None, None) if options.report_file.is_none() => run_single(options, &bit_sequence),
+        (None, None) => run_streams(options, &bit_sequence, length, 1),
+        (Some(stream_len), None) => run_streams(
+            options,
+            &bit_sequence,
+            stream_len,
+            length / stream_len.max(1),
+        ),
+        (None, Some(number_of_streams)) => run_streams(
+            options,
+            &bit_sequence,
+            length / number_of_streams.max(1),
+            number_of_streams,
+        ),
+        (Some(stream_len), Some(number_of_streams)) => {
+            run_streams(options, &bit_sequence, stream_len, number_of_streams)
+        }
+    }?;
+
+    write_outputs(options, &metadata, &results)?;
+
+    Ok(success)
+}
+
+/// Perform the selected tests on the whole bit sequence and print their results.
+///
+/// # Arguments
+///
+/// options - The parsed options
+/// bit_sequence - The bit sequence to be tested
+///
+/// # Return
+///
+/// Ok((true, results)) - All selected tests passed or were not applicable
+/// Ok((false, results)) - At least one test failed, was skipped or could not be performed. A test
+/// with k p-values fails if one of them is less than alpha / k, not already if one is less than
+/// alpha
+/// Err(err) - Some error occured
+fn run_single(
+    options: &Options,
+    bit_sequence: &BitSequence,
+) -> Result<(bool, Vec<customtypes::MultiStreamResult>)> {
+    let tests = create_tests(options, bit_sequence.len())?;
+    let mut success = true;
+
+    // the whole bit sequence is performed and exported as a single stream
+    let prerequisites = options.prerequisites.unwrap_or(true);
+    let results = perform_tests(
+        options,
+        prerequisites,
+        &tests,
+        std::slice::from_ref(bit_sequence),
+    );
+
+    for result in &results {
+        match &result.stream_results[0] {
+            Ok(test_result) => {
+                println!(
+                    "{}{}: {}",
+                    test_result.test,
+                    format_params(&test_result.params),
+                    test_result.verdict
+                );
+                for ((label, p_value), (_, verdict)) in test_result
+                    .p_values
+                    .iter()
+                    .zip(&test_result.p_value_verdicts)
+                {
+                    println!("    {:<12} {:.6} {}", label, p_value, verdict);
+                }
+                if let Some(reason) = &test_result.reason {
+                    println!("    {}", reason);
+                }
+
+                // a test skipped because its prerequisite failed counts as failed
+                success &= !matches!(
+                    test_result.verdict,
+                    customtypes::Verdict::Fail | customtypes::Verdict::Skipped
+                );
+            }
+            Err(err) => {
+                println!("{}: ERROR ({:#})", result.test, err);
+                success = false;
+            }
+        }
+    }
+
+    Ok((success, results))
+}
+
+/// Split the bit sequence into N streams of n bits, perform the selected tests on each stream
+/// and print the second-level analysis of the p-values: the proportion of passing streams and the
+/// uniformity of the p-values.
+///
+/// # Arguments
+///
+/// options - The parsed options
+/// bit_sequence - The bit sequence to be tested
+/// stream_len - The length n of each stream
+/// number_of_streams - The number N of streams
+///
+/// # Return
+///
+/// Ok((true, results)) - The proportion and the uniformity passed for all p-values of the
+/// selected tests
+/// Ok((false, results)) - At least one proportion or uniformity failed or a test or the analysis
+/// of a p-value could not be performed
+/// Err(err) - Some error occured
+fn run_streams(
+    options: &Options,
+    bit_sequence: &BitSequence,
+    stream_len: usize,
+    number_of_streams: usize,
+) -> Result<(bool, Vec<customtypes::MultiStreamResult>)> {
+    let streams = multi_stream::split_into_streams(bit_sequence, stream_len, number_of_streams)?;
+    println!(
+        "Testing {} streams of {} bits",
+        number_of_streams, stream_len
+    );
+
+    let tests = create_tests(options, stream_len)?;
+    let mut success = true;
+
+    // like the NIST reference implementation, every test is performed on every stream by default,
+    // so that all rows of the second-level analysis have the same sample size
+    let prerequisites = options.prerequisites.unwrap_or(false);
+    let results = perform_tests(options, prerequisites, &tests, &streams);
+
+    for result in &results {
+        println!("{}{}:", result.test, format_params(&result.params));
+        for (label, p_values) in result.p_values() {
+            // a p-value which cannot be analyzed is reported like a test which failed on a stream
+            let analysis = match second_level::analyze(&p_values, options.alpha) {
+                Ok(analysis) => analysis,
+                Err(err) => {
+                    println!("    {:<12} ERROR ({:#})", label, err);
+                    success = false;
+                    continue;
+                }
+            };
+            println!(
+                "    {:<12} {}/{} passed (min. {:.4}) {}, P-value_T = {:.6} {}",
+                label,
+                analysis.passed,
+                analysis.sample_size,
+                analysis.confidence_interval.0,
+                if analysis.proportion_passed() {
+                    "PASS"
+                } else {
+                    "FAIL"
+                },
+                analysis.uniformity_p_value,
+                if analysis.uniformity_passed() {
+                    "PASS"
+                } else {
+                    "FAIL"
+                }
+            );
+
+            success &= analysis.passed();
+        }
+
+        // streams a test was skipped on do not contribute p-values. Only if the test was skipped on
+        // all streams, there is nothing left to analyze
+        let skipped = result.number_of_skipped();
+        if skipped > 0 {
+            if let Some(reason) = result
+                .stream_results
+                .iter()
+                .flatten()
+                .find_map(|result| result.reason.as_ref())
+            {
+                println!("    SKIPPED on {} streams ({})", skipped, reason);
+            }
+            success &= skipped < number_of_streams;
+        }
+
+        let errors = result.number_of_errors();
+        if errors > 0 {
+            if let Some(Err(err)) = result.stream_results.iter().find(|result| result.is_err()) {
+                println!("    ERROR on {} streams ({:#})", errors, err);
+            }
+            success = false;
+        }
+    }
+
+    Ok((success, results))
+}
+
+/// Perform the tests on the streams, either as a suite with prerequisites between the tests or
+/// every test on every stream.
+///
+/// # Arguments
+///
+/// options - The parsed options
+/// prerequisites - Whether tests are skipped on streams their prerequisites fail on
+/// tests - The tests to be performed
+/// streams - The streams to perform the tests on
+///
+/// # Return
+///
+/// results - The results of all streams for each test in the order of the passed tests
+fn perform_tests(
+    options: &Options,
+    prerequisites: bool,
+    tests: &[Box<dyn StatisticalTest>],
+    streams: &[BitSequence],
+) -> Vec<customtypes::MultiStreamResult> {
+    if prerequisites {
+        suite::perform_tests(tests, streams, options.alpha)
+    } else {
+        multi_stream::perform_tests(tests, streams, options.alpha)
+    }
+}
+
+/// Write the requested report and exports of the results.
+///
+/// # Arguments
+///
+/// options - The parsed options
+/// metadata - The metadata of the tested input
+/// results - The results of all streams per test
+///
+/// # Return
+///
+/// Ok(()) - All requested files were written
+/// Err(err) - Some error occured
+fn write_outputs(
+    options: &Options,
+    metadata: &customtypes::InputMetadata,
+    results: &[customtypes::MultiStreamResult],
+) -> Result<()> {
+    if let Some(report_file) = &options.report_file {
+        write_file(report_file, |writer| {
+            report::write_final_analysis_report(writer, &metadata.source, results, options.alpha)
+        })?;
+    }
+    if let Some(json_file) = &options.json_file {
+        write_file(json_file, |writer| {
+            export::write_json(writer, metadata, results, options.alpha)
+        })?;
+    }
+    if let Some(csv_file) = &options.csv_file {
+        write_file(csv_file, |writer| {
+            export::write_csv(writer, metadata, results, options.alpha)
+        })?;
+    }
+
     Ok(())
 }
+
+/// Create a file and write its contents.
+///
+/// # Arguments
+///
+/// path - The path of the file
+/// write - Writes the contents to the buffered file
+///
+/// # Return
+///
+/// Ok(()) - The file was written
+/// Err(err) - Some error occured
+fn write_file(
+    path: &str,
+    write: impl FnOnce(&mut std::io::BufWriter<std::fs::File>) -> Result<()>,
+) -> Result<()> {
+    let file =
+        std::fs::File::create(path).with_context(|| format!("Failed to create '{}'", path))?;
+    let mut writer = std::io::BufWriter::new(file);
+
+    write(&mut writer)
+        .and_then(|()| Ok(std::io::Write::flush(&mut writer)?))
+        .with_context(|| format!("Failed to write '{}'", path))?;
+    println!("Results written to '{}'", path);
+
+    Ok(())
+}
+
+/// Format the parameters of a test for printing.
+///
+/// # Arguments
+///
+/// params - The parameters as pairs of name and value
+///
+/// # Return
+///
+/// params - The parameters, each preceded by a space
+fn format_params(params: &[(String, String)]) -> String {
+    params
+        .iter()
+        .map(|(name, value)| format!(" {} = {}", name, value))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> impl Iterator<Item = String> {
+        args.iter()
+            .map(|arg| arg.to_string())
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    #[test]
+    fn test_parse_args() {
+        // defaults
+        let options = parse_args(args(&["data.e"])).unwrap().unwrap();
+        assert_eq!(options.input_file, "data.e");
+        assert_eq!(options.tests, customtypes::Test::ALL);
+        assert_eq!(options.alpha, constants::P_VALUE_THRESHOLD);
+        assert_eq!(options.max_bits, None);
+        assert_eq!(options.jobs, None);
+        assert!(!options.dft_legacy_threshold);
+        assert_eq!(options.prerequisites, None);
+
+        // the suite with prerequisites can be requested for streams as well
+        let options = parse_args(args(&["--prerequisites", "-N", "10", "data.e"]))
+            .unwrap()
+            .unwrap();
+        assert_eq!(options.prerequisites, Some(true));
+
+        // the standard input is read as "-"
+        let options = parse_args(args(&["-b", "1000000", "-"])).unwrap().unwrap();
+        assert_eq!(options.input_file, constants::STDIN_SOURCE);
+        assert_eq!(options.max_bits, Some(1000000));
+
+        // tests are selected by name or number
+        let options = parse_args(args(&[
+            "-t",
+            "serial,1,frequency_block,2",
+            "--alpha=0.001",
+            "--log-level",
+            "Debug",
+            "--block-size",
+            "20000",
+            "--dft-legacy-threshold",
+            "--no-prerequisites",
+            "--template-len",
+            "10",
+            "--cusum-mode",
+            "backward",
+            "-f",
+            "binary",
+            "--bit-order=LSB",
+            "-N",
+            "100",
+            "--report=finalAnalysisReport.txt",
+            "--json",
+            "results.json",
+            "--csv=results.csv",
+            "-j",
+            "4",
+            "data.e",
+        ]))
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            options.tests,
+            [
+                customtypes::Test::FrequencyMonobit,
+                customtypes::Test::FrequencyBlock,
+                customtypes::Test::Serial
+            ]
+        );
+        assert_eq!(options.alpha, 0.001);
+        assert_eq!(options.log_level, "debug");
+        assert_eq!(options.block_size, Some(20000));
+        assert!(options.dft_legacy_threshold);
+        assert_eq!(options.prerequisites, Some(false));
+        assert_eq!(options.template_len, 10);
+        assert_eq!(options.cusum_modes, [customtypes::Mode::Backward]);
+        assert_eq!(options.input_format, customtypes::InputFormat::Binary);
+        assert_eq!(options.bit_order, customtypes::BitOrder::LsbFirst);
+        assert_eq!(options.stream_len, None);
+        assert_eq!(options.number_of_streams, Some(100));
+        assert_eq!(
+            options.report_file.as_deref(),
+            Some("finalAnalysisReport.txt")
+        );
+        assert_eq!(options.json_file.as_deref(), Some("results.json"));
+        assert_eq!(options.csv_file.as_deref(), Some("results.csv"));
+        assert_eq!(options.jobs, Some(4));
+
+        // help
+        assert!(parse_args(args(&["--help"])).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_parse_args_error_cases() {
+        assert!(parse_args(args(&[])).is_err());
+        assert!(parse_args(args(&["data.e", "data.pi"])).is_err());
+        assert!(parse_args(args(&["--unknown", "data.e"])).is_err());
+        assert!(parse_args(args(&["-t", "16", "data.e"])).is_err());
+        assert!(parse_args(args(&["-t", "monobit", "data.e"])).is_err());
+        assert!(parse_args(args(&["-a", "abc", "data.e"])).is_err());
+        assert!(parse_args(args(&["-l", "verbose", "data.e"])).is_err());
+        assert!(parse_args(args(&["--cusum-mode", "sideways", "data.e"])).is_err());
+        assert!(parse_args(args(&["--template-blocks", "0", "data.e"])).is_err());
+        assert!(parse_args(args(&["--format", "octal", "data.e"])).is_err());
+        assert!(parse_args(args(&["--bit-order", "middle", "data.e"])).is_err());
+        assert!(parse_args(args(&["-n", "-1", "data.e"])).is_err());
+        assert!(parse_args(args(&["--bits", "all", "-"])).is_err());
+        assert!(parse_args(args(&["--jobs", "0", "data.e"])).is_err());
+        assert!(parse_args(args(&["data.e", "--block-size"])).is_err());
+    }
+
+    #[test]
+    fn test_create_tests() {
+        let options = parse_args(args(&["data.e"])).unwrap().unwrap();
+        let tests = create_tests(&options, 1000000).unwrap();
+
+        // the cumulative sums test is performed forward and backward
+        assert_eq!(tests.len(), customtypes::Test::ALL.len() + 1);
+        assert_eq!(
+            tests[1].params(),
+            [("block_size".to_owned(), "10001".to_owned())]
+        );
+        assert_eq!(
+            tests[8].params(),
+            [
+                ("block_len_l".to_owned(), "7".to_owned()),
+                ("init_blocks_q".to_owned(), "1280".to_owned())
+            ]
+        );
+
+        // a given Q is used with the selected L
+        let options = parse_args(args(&["--universal-q", "0", "-t", "9", "data.e"]))
+            .unwrap()
+            .unwrap();
+        let tests = create_tests(&options, 1000000).unwrap();
+        assert_eq!(
+            tests[0].params(),
+            [
+                ("block_len_l".to_owned(), "7".to_owned()),
+                ("init_blocks_q".to_owned(), "0".to_owned())
+            ]
+        );
+    }
+}
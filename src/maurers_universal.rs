@@ -0,0 +1,403 @@
+//! This module performs Maurer's "Universal Statistical" Test.
+//!
+//! Description of test from NIST SP 800-22:
+//!
+//! "The focus of this test is the number of bits between matching patterns (a measure that is related to the
+//! length of a compressed sequence). The purpose of the test is to detect whether or not the sequence can be
+//! significantly compressed without loss of information. A significantly compressible sequence is
+//! considered to be non-random."
+
+use crate::bit_sequence::BitSequence;
+use crate::constants;
+use crate::customtypes;
+use crate::customtypes::StatisticalTest;
+use crate::utils;
+use anyhow::{Context, Result};
+
+const TEST_NAME: customtypes::Test = customtypes::Test::MaurersUniversalStatistical;
+
+/// Perform Maurer's "Universal Statistical" Test by determining the p-value.
+///
+/// # Arguments
+///
+/// bit_sequence - The bit sequence to be tested for randomness
+/// block_len_l - The length L of each block
+/// init_blocks_q - The number Q of blocks in the initialization segment
+///
+/// # Return
+///
+/// Ok(p-value) - The p-value which indicates whether randomness is given or not
+/// Err(err) - Some error occured
+pub fn perform_test(
+    bit_sequence: &BitSequence,
+    block_len_l: usize,
+    init_blocks_q: usize,
+) -> Result<f64> {
+    log::trace!("maurers_universal::perform_test()");
+
+    let result = MaurersUniversalStatistical::create(block_len_l, init_blocks_q)
+        .run(bit_sequence, constants::P_VALUE_THRESHOLD)?;
+
+    Ok(result.p_values[0].1)
+}
+
+/// Parameters of the "Maurer's Universal Statistical" test
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MaurersUniversalStatistical {
+    pub block_len_l: usize,
+    pub init_blocks_q: usize,
+}
+
+impl MaurersUniversalStatistical {
+    pub fn create(block_len_l: usize, init_blocks_q: usize) -> Self {
+        MaurersUniversalStatistical {
+            block_len_l,
+            init_blocks_q,
+        }
+    }
+}
+
+impl customtypes::StatisticalTest for MaurersUniversalStatistical {
+    fn test(&self) -> customtypes::Test {
+        TEST_NAME
+    }
+
+    fn params(&self) -> Vec<(String, String)> {
+        vec![
+            ("block_len_l".to_owned(), self.block_len_l.to_string()),
+            ("init_blocks_q".to_owned(), self.init_blocks_q.to_string()),
+        ]
+    }
+
+    fn compute(&self, bit_sequence: &BitSequence) -> Result<customtypes::TestStatistics> {
+        log::trace!("maurers_universal::compute()");
+
+        let block_len_l = self.block_len_l;
+        let init_blocks_q = self.init_blocks_q;
+
+        // check if bit sequence is valid
+        let length = utils::evaluate_bit_sequence(
+            TEST_NAME,
+            bit_sequence,
+            constants::RECOMMENDED_SIZE_UNIVERSAL,
+        )
+        .with_context(|| "Invalid bit sequence passed")?;
+
+        // evaluate L and Q and get the number of blocks K in the test segment
+        let test_blocks_k = evaluate_test_params(length, block_len_l, init_blocks_q)
+            .with_context(|| "Block length L or number of initialization blocks Q is invalid")?;
+
+        // compute the test statistic f_n = sum(log2(i - T_j)) / K over the test segment
+        let f_n = compute_test_statistic(bit_sequence, block_len_l, init_blocks_q, test_blocks_k);
+
+        // compute the theoretical standard deviation sigma = c * sqrt(variance(L) / K) with
+        // c = 0.7 - 0.8 / L + (4 + 32 / L) * K^(-3 / L) / 15
+        let l = block_len_l as f64;
+        let k = test_blocks_k as f64;
+        let c = 0.7 - 0.8 / l + (4.0 + 32.0 / l) * k.powf(-3.0 / l) / 15.0;
+        let sigma = c * (constants::UNIVERSAL_VARIANCES[block_len_l] / k).sqrt();
+        let expected_value = constants::UNIVERSAL_EXPECTED_VALUES[block_len_l];
+        log::debug!(
+            "{}: f_n = {}, expected value = {}, sigma = {}",
+            TEST_NAME,
+            f_n,
+            expected_value,
+            sigma
+        );
+
+        // finally, compute p-value with the complementary error function:
+        // erfc(|f_n - expected_value| / (sqrt(2) * sigma))
+        let p_value = statrs::function::erf::erfc(
+            (f_n - expected_value).abs() / (std::f64::consts::SQRT_2 * sigma),
+        );
+        log::info!("{}: p-value = {}", TEST_NAME, p_value);
+
+        Ok(customtypes::TestStatistics::create(
+            vec![("f_n".to_owned(), f_n)],
+            vec![("p-value".to_owned(), p_value)],
+        ))
+    }
+}
+
+/// Select the block length L and the number of initialization blocks Q recommended by NIST
+/// SP 800-22 for the given bit string length.
+///
+/// # Arguments
+///
+/// length - The length of the bit string to be tested
+///
+/// # Return
+///
+/// Ok((L, Q)) - The recommended block length and number of initialization blocks
+/// Err(err) - Some error occured
+pub fn select_test_params(length: usize) -> Result<(usize, usize)> {
+    log::trace!("maurers_universal::select_test_params()");
+
+    // take the largest L whose minimum length is still covered by the bit string
+    let block_len_l = match constants::UNIVERSAL_MIN_LENGTHS
+        .iter()
+        .rev()
+        .find(|(_, min_length)| length >= *min_length)
+    {
+        Some(&(block_len_l, _)) => block_len_l,
+        None => anyhow::bail!(
+            "{}: Bit string needs at least {} bits! Actual length: {}",
+            TEST_NAME,
+            constants::RECOMMENDED_SIZE_UNIVERSAL,
+            length
+        ),
+    };
+
+    let init_blocks_q = 10 * (1 << block_len_l);
+    log::debug!(
+        "{}: Selected L = {}, Q = {} for length {}",
+        TEST_NAME,
+        block_len_l,
+        init_blocks_q,
+        length
+    );
+
+    Ok((block_len_l, init_blocks_q))
+}
+
+/// Evaluate passed test parameters and return the resulting number of test blocks K.
+///
+/// # Arguments
+///
+/// bit_string_length - Length of bit string
+/// block_len_l - The length L of each block
+/// init_blocks_q - The number Q of blocks in the initialization segment
+///
+/// # Return
+///
+/// Ok(test_blocks_k) - The number of blocks K in the test segment
+/// Err(err) - Some error occured
+fn evaluate_test_params(
+    bit_string_length: usize,
+    block_len_l: usize,
+    init_blocks_q: usize,
+) -> Result<usize> {
+    log::trace!("maurers_universal::evaluate_test_params()");
+
+    // check whether block length is covered by the table of expected values and variances
+    if !(constants::BLOCK_LEN_L.0..=constants::BLOCK_LEN_L.1).contains(&block_len_l) {
+        anyhow::bail!(
+            "{}: Passed block length L '{}' must be between {} and {}",
+            TEST_NAME,
+            block_len_l,
+            constants::BLOCK_LEN_L.0,
+            constants::BLOCK_LEN_L.1
+        );
+    }
+
+    // recommended block lengths: 6..16. Log a warning if they do not match
+    if !(constants::RECOMMENDED_BLOCK_LEN_L.0..=constants::RECOMMENDED_BLOCK_LEN_L.1)
+        .contains(&block_len_l)
+    {
+        log::warn!(
+            "{}: Recommended block length L is between {} and {}, passed: {}",
+            TEST_NAME,
+            constants::RECOMMENDED_BLOCK_LEN_L.0,
+            constants::RECOMMENDED_BLOCK_LEN_L.1,
+            block_len_l
+        );
+    }
+
+    // Q should be chosen as 10 * 2^L so that each L-bit template is likely to occur
+    if init_blocks_q < 10 * (1 << block_len_l) {
+        log::warn!(
+            "{}: Recommended number of initialization blocks Q is at least {}, passed: {}",
+            TEST_NAME,
+            10 * (1 << block_len_l),
+            init_blocks_q
+        );
+    }
+
+    // the remaining blocks K form the test segment
+    let number_of_blocks = bit_string_length / block_len_l;
+    if init_blocks_q == 0 || number_of_blocks <= init_blocks_q {
+        anyhow::bail!(
+            "{}: Number of initialization blocks Q ({}) must be between 1 and {}",
+            TEST_NAME,
+            init_blocks_q,
+            number_of_blocks.saturating_sub(1)
+        );
+    }
+    let test_blocks_k = number_of_blocks - init_blocks_q;
+
+    log::info!(
+        "{}: Block length L = {}, initialization blocks Q = {}, test blocks K = {}",
+        TEST_NAME,
+        block_len_l,
+        init_blocks_q,
+        test_blocks_k
+    );
+
+    Ok(test_blocks_k)
+}
+
+/// Compute the test statistic f_n.
+///
+/// # Arguments
+///
+/// bit_sequence - The bit sequence to compute the statistic from
+/// block_len_l - The length L of each block
+/// init_blocks_q - The number Q of blocks in the initialization segment
+/// test_blocks_k - The number K of blocks in the test segment
+///
+/// # Return
+///
+/// f_n - The averaged sum of log2 distances between matching blocks
+fn compute_test_statistic(
+    bit_sequence: &BitSequence,
+    block_len_l: usize,
+    init_blocks_q: usize,
+    test_blocks_k: usize,
+) -> f64 {
+    log::trace!("maurers_universal::compute_test_statistic()");
+
+    // the table stores for every possible L-bit value the (1-based) index of the block it was
+    // last seen in
+    let mut table = vec![0_usize; 1 << block_len_l];
+    let mut sum = 0.0;
+
+    for index in 0..(init_blocks_q + test_blocks_k) {
+        let value = bit_sequence.bits(index * block_len_l, block_len_l) as usize;
+
+        if index >= init_blocks_q {
+            sum += ((index + 1 - table[value]) as f64).log2();
+        }
+        table[value] = index + 1;
+    }
+    log::debug!("{}: Sum of log2 distances: {}", TEST_NAME, sum);
+
+    sum / (test_blocks_k as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bit_sequence::BitSequence;
+    use crate::constants;
+    use crate::logger;
+    use crate::maurers_universal;
+    use crate::utils;
+
+    const LOGLEVEL: &str = "Debug";
+    const BIT_STRING_NIST_1: &str = "01011010011101010111";
+    const F_N_NIST_1: f64 = 1.1949875;
+    const P_VALUE_NIST_E: f64 = 0.282568;
+    const NIST_LENGTH: usize = 1000000;
+
+    #[test]
+    fn test_select_test_params() {
+        logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
+
+        assert_eq!(
+            maurers_universal::select_test_params(387840).unwrap(),
+            (6, 640)
+        );
+        assert_eq!(
+            maurers_universal::select_test_params(NIST_LENGTH).unwrap(),
+            (7, 1280)
+        );
+        assert_eq!(
+            maurers_universal::select_test_params(2068480).unwrap(),
+            (8, 2560)
+        );
+        assert_eq!(
+            maurers_universal::select_test_params(usize::MAX).unwrap(),
+            (16, 655360)
+        );
+        assert!(maurers_universal::select_test_params(387839).is_err());
+    }
+
+    #[test]
+    fn test_maurers_universal() {
+        logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
+
+        // worked example from NIST SP 800-22 with L = 2, Q = 4 and K = 6
+        let f_n =
+            maurers_universal::compute_test_statistic(&BIT_STRING_NIST_1.parse().unwrap(), 2, 4, 6);
+        assert!((f_n - F_N_NIST_1).abs() < 1e-6);
+        assert!(maurers_universal::perform_test(&BIT_STRING_NIST_1.parse().unwrap(), 2, 4).is_ok());
+
+        // the first 10^6 bits of e must match the result of the NIST reference implementation
+        let e_file = std::env::current_dir()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_owned()
+            + constants::E_FILE;
+        let e_bit_string = utils::read_random_numbers(&e_file).unwrap();
+        let (block_len_l, init_blocks_q) =
+            maurers_universal::select_test_params(NIST_LENGTH).unwrap();
+        let p_value = maurers_universal::perform_test(
+            &e_bit_string.slice(0..NIST_LENGTH),
+            block_len_l,
+            init_blocks_q,
+        )
+        .unwrap();
+        assert!((p_value - P_VALUE_NIST_E).abs() < 1e-6);
+
+        // test pi, sqrt(2), sqrt(3) and SHA-3 in their respective binary representations
+        let pi_file = std::env::current_dir()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_owned()
+            + constants::PI_FILE;
+        let pi_bit_string = utils::read_random_numbers(&pi_file).unwrap();
+        assert!(maurers_universal::perform_test(&pi_bit_string, 7, 1280).unwrap() >= 0.01);
+
+        let sqrt_2_file = std::env::current_dir()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_owned()
+            + constants::SQRT_2_FILE;
+        let sqrt_2_bit_string = utils::read_random_numbers(&sqrt_2_file).unwrap();
+        assert!(maurers_universal::perform_test(&sqrt_2_bit_string, 7, 1280).unwrap() >= 0.01);
+
+        let sqrt_3_file = std::env::current_dir()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_owned()
+            + constants::SQRT_3_FILE;
+        let sqrt_3_bit_string = utils::read_random_numbers(&sqrt_3_file).unwrap();
+        assert!(maurers_universal::perform_test(&sqrt_3_bit_string, 7, 1280).unwrap() >= 0.01);
+
+        let sha_3_file = std::env::current_dir()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_owned()
+            + constants::SHA_3_FILE;
+        let sha_3_bit_string = utils::read_random_numbers(&sha_3_file).unwrap();
+        assert!(maurers_universal::perform_test(&sha_3_bit_string, 7, 1280).unwrap() >= 0.01);
+    }
+
+    #[test]
+    fn test_maurers_universal_error_cases() {
+        logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
+
+        // pass empty sequence
+        assert!(maurers_universal::perform_test(&BitSequence::new(), 6, 640).is_err());
+
+        // pass invalid block lengths
+        assert!(
+            maurers_universal::perform_test(&BIT_STRING_NIST_1.parse().unwrap(), 0, 4).is_err()
+        );
+        assert!(
+            maurers_universal::perform_test(&BIT_STRING_NIST_1.parse().unwrap(), 17, 4).is_err()
+        );
+
+        // pass invalid number of initialization blocks
+        assert!(
+            maurers_universal::perform_test(&BIT_STRING_NIST_1.parse().unwrap(), 2, 0).is_err()
+        );
+        assert!(
+            maurers_universal::perform_test(&BIT_STRING_NIST_1.parse().unwrap(), 2, 10).is_err()
+        );
+    }
+}
@@ -0,0 +1,364 @@
+//! This module performs the tests on multiple bit streams like the NIST reference implementation.
+//!
+//! The input is split into N streams of n bits each and every selected test is performed on each
+//! stream. The p-values of all streams are collected per test, so the proportion of passing
+//! streams and the distribution of the p-values can be evaluated afterwards.
+//!
+//! The tests and streams are independent of each other and are performed in parallel on the
+//! current rayon thread pool. The results keep the order of the tests and streams. The random walk
+//! of a stream is computed once and shared by all tests which use it.
+
+use crate::bit_sequence::BitSequence;
+use crate::cumulative_sums;
+use crate::customtypes;
+use anyhow::Result;
+use rayon::prelude::*;
+use std::sync::OnceLock;
+
+/// Split the bit sequence into N streams of length n. Remaining bits are discarded.
+///
+/// # Arguments
+///
+/// bit_sequence - The bit sequence to be split
+/// stream_len - The length n of each stream
+/// number_of_streams - The number N of streams
+///
+/// # Return
+///
+/// Ok(streams) - The N streams
+/// Err(err) - Some error occured
+pub fn split_into_streams(
+    bit_sequence: &BitSequence,
+    stream_len: usize,
+    number_of_streams: usize,
+) -> Result<Vec<BitSequence>> {
+    log::trace!("multi_stream::split_into_streams()");
+
+    if stream_len == 0 || number_of_streams == 0 {
+        anyhow::bail!(
+            "Stream length n ({}) and number of streams N ({}) must be greater than 0",
+            stream_len,
+            number_of_streams
+        );
+    }
+
+    // the input has to contain N * n bits
+    let required_len = stream_len
+        .checked_mul(number_of_streams)
+        .filter(|&required_len| required_len <= bit_sequence.len());
+    let required_len = match required_len {
+        Some(required_len) => required_len,
+        None => anyhow::bail!(
+            "Bit sequence of length {} is too short for {} streams of {} bits",
+            bit_sequence.len(),
+            number_of_streams,
+            stream_len
+        ),
+    };
+
+    if required_len < bit_sequence.len() {
+        log::warn!(
+            "Discarded {} bits which do not fill the {} streams of {} bits",
+            bit_sequence.len() - required_len,
+            number_of_streams,
+            stream_len
+        );
+    }
+
+    let streams = bit_sequence
+        .blocks(stream_len)
+        .take(number_of_streams)
+        .collect::<Vec<_>>();
+    log::info!(
+        "Split bit sequence into {} streams of {} bits",
+        streams.len(),
+        stream_len
+    );
+
+    Ok(streams)
+}
+
+/// Perform the tests on each stream in parallel and collect the results per test.
+///
+/// # Arguments
+///
+/// tests - The tests to be performed
+/// streams - The streams to perform the tests on
+/// alpha - The significance level to decide about randomness of each stream
+///
+/// # Return
+///
+/// results - The results of all streams for each test in the order of the passed tests
+pub fn perform_tests(
+    tests: &[Box<dyn customtypes::StatisticalTest>],
+    streams: &[BitSequence],
+    alpha: f64,
+) -> Vec<customtypes::MultiStreamResult> {
+    log::trace!("multi_stream::perform_tests()");
+
+    // the streams are performed one after another per thread, so the random walk of a stream
+    // shared by the tests only lives as long as the stream is tested
+    let stream_results = streams
+        .par_iter()
+        .enumerate()
+        .map(|(index, stream)| {
+            log::debug!("Stream {}/{}", index + 1, streams.len());
+
+            let random_walk = OnceLock::new();
+            tests
+                .par_iter()
+                .map(|test| {
+                    let random_walk = shared_random_walk(test.as_ref(), stream, &random_walk);
+                    test.run_on_walk(stream, random_walk, alpha)
+                })
+                .collect()
+        })
+        .collect();
+
+    collect_per_test(tests, stream_results)
+}
+
+/// Get the random walk of a stream if the test uses it. The random walk is computed by the first
+/// test which needs it and shared with all other tests on the stream.
+///
+/// # Arguments
+///
+/// test - The test to be performed
+/// stream - The stream to perform the test on
+/// random_walk - The random walk of the stream, once it is computed
+///
+/// # Return
+///
+/// Some(random_walk) - The random walk of the stream
+/// None - The test does not use the random walk
+pub(crate) fn shared_random_walk<'a>(
+    test: &dyn customtypes::StatisticalTest,
+    stream: &BitSequence,
+    random_walk: &'a OnceLock<customtypes::RandomWalk>,
+) -> Option<&'a customtypes::RandomWalk> {
+    test.uses_random_walk()
+        .then(|| random_walk.get_or_init(|| cumulative_sums::compute_random_walk(stream)))
+}
+
+/// Regroup the results of all tests per stream into the results of all streams per test.
+///
+/// # Arguments
+///
+/// tests - The performed tests
+/// stream_results - The results of the tests for each stream in the order of the tests
+///
+/// # Return
+///
+/// results - The results of all streams for each test in the order of the passed tests
+pub(crate) fn collect_per_test(
+    tests: &[Box<dyn customtypes::StatisticalTest>],
+    stream_results: Vec<Vec<Result<customtypes::TestResult>>>,
+) -> Vec<customtypes::MultiStreamResult> {
+    let mut test_results = tests
+        .iter()
+        .map(|_| Vec::with_capacity(stream_results.len()))
+        .collect::<Vec<_>>();
+    for results in stream_results {
+        for (test_result, result) in test_results.iter_mut().zip(results) {
+            test_result.push(result);
+        }
+    }
+
+    tests
+        .iter()
+        .zip(test_results)
+        .map(|(test, stream_results)| {
+            customtypes::MultiStreamResult::create(test.test(), test.params(), stream_results)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bit_sequence::BitSequence;
+    use crate::constants;
+    use crate::cumulative_sums;
+    use crate::customtypes;
+    use crate::frequency_monobit;
+    use crate::logger;
+    use crate::multi_stream;
+    use crate::non_overlapping_template;
+    use crate::random_excursions;
+    use crate::random_excursions_variant;
+    use crate::serial;
+    use crate::utils;
+
+    const LOGLEVEL: &str = "Info";
+    const STREAM_LEN: usize = 100000;
+    const NUMBER_OF_STREAMS: usize = 10;
+
+    #[test]
+    fn test_split_into_streams() {
+        logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
+
+        let bit_sequence: BitSequence = "1100100100001111110110101".parse().unwrap();
+        let streams = multi_stream::split_into_streams(&bit_sequence, 8, 3).unwrap();
+        assert_eq!(
+            streams
+                .iter()
+                .map(|stream| stream.to_string())
+                .collect::<Vec<_>>(),
+            ["11001001", "00001111", "11011010"]
+        );
+        assert_eq!(
+            multi_stream::split_into_streams(&bit_sequence, 5, 2)
+                .unwrap()
+                .len(),
+            2
+        );
+
+        // invalid stream length or number of streams
+        assert!(multi_stream::split_into_streams(&bit_sequence, 0, 3).is_err());
+        assert!(multi_stream::split_into_streams(&bit_sequence, 8, 0).is_err());
+        assert!(multi_stream::split_into_streams(&bit_sequence, 8, 4).is_err());
+        assert!(multi_stream::split_into_streams(&bit_sequence, usize::MAX, 2).is_err());
+    }
+
+    #[test]
+    fn test_perform_tests() {
+        logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
+
+        let e_file = std::env::current_dir()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_owned()
+            + constants::E_FILE;
+        let e_bit_string = utils::read_random_numbers(&e_file).unwrap();
+        let streams =
+            multi_stream::split_into_streams(&e_bit_string, STREAM_LEN, NUMBER_OF_STREAMS).unwrap();
+
+        let tests: Vec<Box<dyn customtypes::StatisticalTest>> = vec![
+            Box::new(frequency_monobit::FrequencyMonobit),
+            Box::new(serial::Serial::create(2)),
+            Box::new(random_excursions::RandomExcursions),
+            Box::new(cumulative_sums::CumulativeSums::create(
+                customtypes::Mode::Backward,
+            )),
+            Box::new(random_excursions_variant::RandomExcursionsVariant),
+        ];
+        let results = multi_stream::perform_tests(&tests, &streams, constants::P_VALUE_THRESHOLD);
+        assert_eq!(results.len(), tests.len());
+
+        // each stream is tested on its own
+        let monobit = &results[0];
+        assert_eq!(monobit.test, customtypes::Test::FrequencyMonobit);
+        assert_eq!(monobit.stream_results.len(), NUMBER_OF_STREAMS);
+        assert_eq!(monobit.number_of_errors(), 0);
+        let p_values = monobit.p_values();
+        assert_eq!(p_values.len(), 1);
+        assert_eq!(p_values[0].1.len(), NUMBER_OF_STREAMS);
+        for (stream, p_value) in streams.iter().zip(p_values[0].1.iter()) {
+            assert_eq!(frequency_monobit::perform_test(stream).unwrap(), *p_value);
+        }
+
+        // p-values of tests with multiple p-values are grouped by label
+        let p_values = results[1].p_values();
+        assert_eq!(
+            p_values
+                .iter()
+                .map(|(label, values)| (label.as_str(), values.len()))
+                .collect::<Vec<_>>(),
+            [
+                ("p-value1", NUMBER_OF_STREAMS),
+                ("p-value2", NUMBER_OF_STREAMS)
+            ]
+        );
+
+        // streams the test is not applicable to do not contribute any p-values
+        let excursions = &results[2];
+        let applicable = excursions
+            .stream_results
+            .iter()
+            .flatten()
+            .filter(|result| result.verdict != customtypes::Verdict::NotApplicable)
+            .count();
+        assert!(applicable < NUMBER_OF_STREAMS);
+        assert!(excursions
+            .p_values()
+            .iter()
+            .all(|(_, values)| values.len() == applicable));
+
+        // the tests sharing the random walk of a stream compute the same p-values on their own
+        for (stream, result) in streams.iter().zip(results[3].stream_results.iter()) {
+            assert_eq!(
+                cumulative_sums::perform_test(stream, customtypes::Mode::Backward).unwrap(),
+                result.as_ref().unwrap().p_values[0].1
+            );
+        }
+        for (stream, result) in streams.iter().zip(results[4].stream_results.iter()) {
+            let p_values = match random_excursions_variant::perform_test(stream).unwrap() {
+                customtypes::ExcursionsResult::Applicable(p_values) => p_values
+                    .into_iter()
+                    .map(|(_, p_value)| p_value)
+                    .collect::<Vec<_>>(),
+                customtypes::ExcursionsResult::NotApplicable { .. } => Vec::new(),
+            };
+            assert_eq!(
+                result
+                    .as_ref()
+                    .unwrap()
+                    .p_values
+                    .iter()
+                    .map(|(_, p_value)| *p_value)
+                    .collect::<Vec<_>>(),
+                p_values
+            );
+        }
+
+        // streams which are too short produce an error per stream
+        let short_streams = multi_stream::split_into_streams(&e_bit_string, 10, 2).unwrap();
+        let results = multi_stream::perform_tests(
+            &[Box::new(serial::Serial::create(16))],
+            &short_streams,
+            constants::P_VALUE_THRESHOLD,
+        );
+        assert_eq!(results[0].number_of_errors(), 2);
+        assert!(results[0].p_values().is_empty());
+    }
+
+    #[test]
+    fn test_perform_tests_deterministic() {
+        logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
+
+        let e_file = std::env::current_dir()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_owned()
+            + constants::E_FILE;
+        let e_bit_string = utils::read_random_numbers(&e_file).unwrap();
+        let streams =
+            multi_stream::split_into_streams(&e_bit_string, STREAM_LEN, NUMBER_OF_STREAMS).unwrap();
+        let tests: Vec<Box<dyn customtypes::StatisticalTest>> = vec![
+            Box::new(frequency_monobit::FrequencyMonobit),
+            Box::new(serial::Serial::create(2)),
+            Box::new(non_overlapping_template::NonOverlappingTemplate::create(
+                9, 8,
+            )),
+        ];
+
+        // the results on a single thread and on multiple threads are in the same order
+        let perform_tests = |jobs: usize| {
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(jobs)
+                .build()
+                .unwrap()
+                .install(|| {
+                    multi_stream::perform_tests(&tests, &streams, constants::P_VALUE_THRESHOLD)
+                })
+        };
+        let sequential = perform_tests(1);
+        let parallel = perform_tests(4);
+        assert_eq!(sequential.len(), parallel.len());
+        for (sequential, parallel) in sequential.iter().zip(parallel.iter()) {
+            assert_eq!(sequential.test, parallel.test);
+            assert_eq!(sequential.p_values(), parallel.p_values());
+        }
+    }
+}
@@ -8,38 +8,42 @@
 //! search for a specific m-bit pattern. If the pattern is not found, the window slides one bit position. If the
 //! pattern is found, the window is reset to the bit after the found pattern, and the search resumes."
 
+use crate::bit_sequence::BitSequence;
 use crate::constants;
 use crate::customtypes;
 use crate::utils;
 use anyhow::{Context, Result};
-use std::io::{BufRead, BufReader};
+use rayon::prelude::*;
+use std::ops::Range;
 
 const TEST_NAME: customtypes::Test = customtypes::Test::NonOverlappingTemplate;
 
-/// Perform the Non-overlapping Template Matching Test by determining the p-value.
+/// Perform the Non-overlapping Template Matching Test by determining the p-value of each template.
 ///
 /// # Arguments
 ///
-/// bit_string - The bit string to be tested for randomness
+/// bit_sequence - The bit sequence to be tested for randomness
 /// template_len - Length of templates to be used for test
 /// number_of_blocks - The number of blocks the bit string has to be divided into
 ///
 /// # Return
 ///
-/// Ok(p-value) - The p-value which indicates whether randomness is given or not
+/// Ok(result) - The counts W_j, chi_square and p-value of each template
 /// Err(err) - Some error occured
-pub fn perform_test(bit_string: &str, template_len: usize, number_of_blocks: usize) -> Result<f64> {
+pub fn perform_test(
+    bit_sequence: &BitSequence,
+    template_len: usize,
+    number_of_blocks: usize,
+) -> Result<customtypes::NonOverlappingTemplateResult> {
     log::trace!("non_overlapping_template::perform_test()");
 
-    // capture the current time before executing the actual test
-    let start_time = std::time::Instant::now();
-
-    // check if bit string contains invalid characters
-    let length = utils::evaluate_bit_string(TEST_NAME, bit_string, constants::RECOMMENDED_SIZE)
-        .with_context(|| "Invalid character(s) in passed bit string detected")?;
+    // check if bit sequence is valid
+    let length = utils::evaluate_bit_sequence(TEST_NAME, bit_sequence, constants::RECOMMENDED_SIZE)
+        .with_context(|| "Invalid bit sequence passed")?;
 
     // check if we got bit string only containing zeros or ones
-    if bit_string.chars().all(|c| c == '0') || bit_string.chars().all(|c| c == '1') {
+    let count_ones = bit_sequence.count_ones();
+    if count_ones == 0 || count_ones == length {
         anyhow::bail!("Given bit string either contains only zeros or only ones");
     }
 
@@ -55,7 +59,7 @@ pub fn perform_test(bit_string: &str, template_len: usize, number_of_blocks: usi
     let second_fraction =
         (2.0 * (template_len as f64) - 1.0) / 2.0_f64.powf(2.0 * (template_len as f64));
 
-    let mean = ((block_size.wrapping_sub(template_len) + 1) as f64) / number_of_templates;
+    let mean = ((block_size - template_len + 1) as f64) / number_of_templates;
     let variance = (block_size as f64) * (first_fraction - second_fraction);
     log::debug!(
         "{}: Theoretical mean = {}, Variance = {}",
@@ -64,86 +68,147 @@ pub fn perform_test(bit_string: &str, template_len: usize, number_of_blocks: usi
         variance
     );
 
-    // now iterate over each template and search for it in each substring
-    let mut p_values = Vec::<f64>::new();
-    let templates = get_templates(template_len).with_context(|| "Failed to get templates")?;
-    p_values.reserve_exact(templates.len());
-
-    for template in templates {
-        let mut template_counters = Vec::<usize>::new();
-
-        // now iterate over blocks 1...N and count occurences of respective aperiodic template in substring
-        for block in 0..number_of_blocks {
-            let start_index = block * block_size;
-            let end_index = (block + 1) * block_size;
-            let substring = &bit_string[start_index..end_index];
-
-            let mut counter = 0;
-            let mut index = 0;
+    // count the occurences of all templates in a single pass per block, the blocks in parallel
+    let templates = generate_templates(template_len);
+    let mut template_indices = vec![usize::MAX; 1 << template_len];
+    for (template_index, &template) in templates.iter().enumerate() {
+        template_indices[template as usize] = template_index;
+    }
 
-            while let Some(start) = substring[index..].find(&template) {
-                counter += 1;
+    let block_counts = (0..number_of_blocks)
+        .into_par_iter()
+        .map(|block| {
+            count_templates(
+                bit_sequence,
+                block * block_size..(block + 1) * block_size,
+                template_len,
+                &template_indices,
+                templates.len(),
+            )
+        })
+        .collect::<Vec<_>>();
+    let counts = (0..templates.len())
+        .map(|template_index| {
+            block_counts
+                .iter()
+                .map(|counts| counts[template_index])
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    // now compute the p-value of each template from its counts W_1, ..., W_N in parallel
+    let template_results = templates
+        .into_par_iter()
+        .zip(counts)
+        .map(|(template_value, template_counters)| {
+            let template = format!("{:0width$b}", template_value, width = template_len);
+            log::trace!(
+                "{}: Template '{}' found {:?} times in the blocks",
+                TEST_NAME,
+                template,
+                template_counters
+            );
 
-                // move the index to the next possible occurence
-                index += start + template_len;
+            // compute chi_square statistics
+            let mut chi_square = 0.0;
+            for counter in &template_counters {
+                chi_square += ((*counter as f64) - mean).powf(2.0) / variance;
             }
-
             log::trace!(
-                "{}: Template '{}' in substring '{}' found {} times",
+                "{}: Chi_square = {} for template '{}'",
                 TEST_NAME,
-                template,
-                substring,
-                counter
+                chi_square,
+                template
             );
-            template_counters.push(counter);
-        }
-        // compute chi_square statistics
-        let mut chi_square = 0.0;
-        for counter in &template_counters {
-            chi_square += ((*counter as f64) - mean).powf(2.0) / variance;
-        }
-        log::trace!(
-            "{}: Chi_square = {} for template '{}'",
-            TEST_NAME,
-            chi_square,
-            template
-        );
 
-        // now compute p-value for current template with incomplete gamma function
-        let p_value = if chi_square == 0.0 {
-            1.0
-        } else {
-            statrs::function::gamma::gamma_ur((number_of_blocks as f64) * 0.5, chi_square * 0.5)
-        };
+            // now compute p-value for current template with incomplete gamma function
+            let p_value = if chi_square == 0.0 {
+                1.0
+            } else {
+                statrs::function::gamma::gamma_ur((number_of_blocks as f64) * 0.5, chi_square * 0.5)
+            };
+
+            if p_value < constants::P_VALUE_THRESHOLD {
+                log::warn!(
+                    "{}: p-value ({}) for template '{}' is below threshold",
+                    TEST_NAME,
+                    p_value,
+                    &template
+                );
+            }
 
-        if p_value < constants::P_VALUE_THRESHOLD {
-            log::warn!(
-                "{}: p-value ({}) for template '{}' is below threshold",
+            log::trace!(
+                "{}: p-value = {} for template '{}'",
                 TEST_NAME,
                 p_value,
-                &template
+                template
             );
+
+            customtypes::TemplateResult::create(template, template_counters, chi_square, p_value)
+        })
+        .collect::<Vec<_>>();
+
+    let result = customtypes::NonOverlappingTemplateResult::create(template_results);
+    log::info!(
+        "{}: {} templates tested, mean of p-values = {}",
+        TEST_NAME,
+        result.template_results.len(),
+        result.p_values_mean()
+    );
+
+    Ok(result)
+}
+
+/// Parameters of the "Non-overlapping Template Matching" test
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NonOverlappingTemplate {
+    pub template_len: usize,
+    pub number_of_blocks: usize,
+}
+
+impl NonOverlappingTemplate {
+    pub fn create(template_len: usize, number_of_blocks: usize) -> Self {
+        NonOverlappingTemplate {
+            template_len,
+            number_of_blocks,
         }
+    }
+}
 
-        log::trace!(
-            "{}: p-value = {} for template '{}'",
-            TEST_NAME,
-            p_value,
-            template
-        );
+impl customtypes::StatisticalTest for NonOverlappingTemplate {
+    fn test(&self) -> customtypes::Test {
+        TEST_NAME
+    }
 
-        p_values.push(p_value);
+    fn params(&self) -> Vec<(String, String)> {
+        vec![
+            ("template_len".to_owned(), self.template_len.to_string()),
+            (
+                "number_of_blocks".to_owned(),
+                self.number_of_blocks.to_string(),
+            ),
+        ]
     }
 
-    let p_values_mean = p_values.iter().sum::<f64>() / (p_values.len() as f64);
-    log::info!("{}: Mean of p-values = {}", TEST_NAME, p_values_mean);
+    fn compute(&self, bit_sequence: &BitSequence) -> Result<customtypes::TestStatistics> {
+        log::trace!("non_overlapping_template::compute()");
 
-    // capture the current time after the test got executed and calculate elapsed time
-    let end_time = std::time::Instant::now();
-    let elapsed_time = end_time.duration_since(start_time).as_secs_f64();
-    log::info!("{} took {:.6} seconds", TEST_NAME, elapsed_time);
+        let result = perform_test(bit_sequence, self.template_len, self.number_of_blocks)?;
 
-    Ok(p_values_mean)
+        // one chi_square statistic and p-value per template, labeled by the template
+        let (statistics, p_values) = result
+            .template_results
+            .into_iter()
+            .map(|template_result| {
+                (
+                    (template_result.template.clone(), template_result.chi_square),
+                    (template_result.template, template_result.p_value),
+                )
+            })
+            .unzip();
+
+        Ok(customtypes::TestStatistics::create(statistics, p_values))
+    }
 }
 
 /// Evaluate passed test parameters and return the resulting block size M.
@@ -189,6 +254,9 @@ fn evaluate_test_params(
     }
 
     // check number of blocks
+    if number_of_blocks == 0 {
+        anyhow::bail!("{}: Number of blocks N must be at least 1", TEST_NAME);
+    }
     if number_of_blocks > constants::RECOMMENDED_SIZE {
         anyhow::bail!(
             "{}: Number of blocks N ({}) is greater than recommended size ({})",
@@ -211,6 +279,16 @@ fn evaluate_test_params(
         );
     }
 
+    // the template has to fit into a block at least once
+    if block_size <= template_len {
+        anyhow::bail!(
+            "{}: Block size M ({}) must be greater than template length ({}). Choose smaller number of blocks",
+            TEST_NAME,
+            block_size,
+            template_len
+        );
+    }
+
     log::info!(
         "{}: Template length = {}, Block size M = {}, Number of blocks N = {}",
         TEST_NAME,
@@ -222,88 +300,168 @@ fn evaluate_test_params(
     Ok(block_size)
 }
 
-/// Get pre-computed templates based on passed template length.
+/// Count the non-overlapping occurences of all templates within a block in a single pass. An
+/// m-bit window slides over the block, and a template is only counted if its previous occurence
+/// ended before the window.
 ///
 /// # Arguments
 ///
-/// template_len - Length of templates to be used for the test
+/// bit_sequence - The bit sequence containing the block
+/// block - The range of the block within the bit sequence
+/// template_len - Length m of the templates
+/// template_indices - The index of each template by its value, usize::MAX for other values
+/// number_of_templates - The number of templates
 ///
 /// # Return
 ///
-/// Ok(templates) - The extracted templates from file
-/// Err(err) - Some error occured
-fn get_templates(template_len: usize) -> Result<Vec<String>> {
-    log::trace!("non_overlapping_template::get_templates()");
-
-    // check whether template file already exists in /tmp (due to previous runs). Therefore no
-    // unpacking needed anymore
-    let template_file_path =
-        constants::TMP_DIR.to_owned() + "/template" + &template_len.to_string();
-    if !std::path::Path::new(&template_file_path).exists() {
-        // create path to templates to use
-        let template_path = match std::env::current_dir() {
-            Ok(path) => path,
-            Err(err) => anyhow::bail!("Failed to retrieve current working directory: {}", err),
-        };
-
-        let template_archive = template_path.to_string_lossy().into_owned()
-            + constants::TEMPLATE_SUB_PATH
-            + &template_len.to_string()
-            + ".tar.gz";
+/// counts - The number of occurences W_j of each template in the block
+fn count_templates(
+    bit_sequence: &BitSequence,
+    block: Range<usize>,
+    template_len: usize,
+    template_indices: &[usize],
+    number_of_templates: usize,
+) -> Vec<usize> {
+    let mask = (1_u64 << template_len) - 1;
+    let mut counts = vec![0_usize; number_of_templates];
+    let mut next_start = vec![block.start; number_of_templates];
+    let mut window = 0_u64;
+
+    for index in block.clone() {
+        window = ((window << 1) | u64::from(bit_sequence.get(index))) & mask;
+
+        // the window covers the bits start..=index once it is completely within the block
+        if index + 1 < block.start + template_len {
+            continue;
+        }
+        let start = index + 1 - template_len;
+
+        let template_index = template_indices[window as usize];
+        if template_index != usize::MAX && next_start[template_index] <= start {
+            counts[template_index] += 1;
 
-        // now unpack the archive to tmp directory and read in the particular templates from file
-        utils::untar_archive(&template_archive, constants::TMP_DIR).with_context(|| {
-            format!("Failed to unpack template archive '{}'", &template_archive)
-        })?;
+            // the next occurence must not overlap with this one
+            next_start[template_index] = start + template_len;
+        }
     }
 
-    // read the file contents line by line
-    let template_file = std::fs::File::open(&template_file_path)
-        .with_context(|| format!("Failed to open template file '{}'", &template_file_path))?;
+    counts
+}
 
-    let reader = BufReader::new(template_file);
-    let mut templates = Vec::<String>::new();
+/// Generate the aperiodic templates of the passed length in ascending order.
+///
+/// A template B is aperiodic if no proper prefix of B equals the suffix of the same length, i.e. B
+/// cannot overlap with a shifted copy of itself. The generated set equals the templates shipped
+/// with the NIST reference implementation.
+///
+/// # Arguments
+///
+/// template_len - Length of templates to be used for the test
+///
+/// # Return
+///
+/// templates - The aperiodic templates, each packed into the lowest template_len bits
+pub fn generate_templates(template_len: usize) -> Vec<u64> {
+    log::trace!("non_overlapping_template::generate_templates()");
 
-    for line in reader.lines() {
-        if let Ok(line) = line {
-            templates.push(line);
-        }
-    }
+    let templates: Vec<u64> = (0..1_u64 << template_len)
+        .filter(|&template| {
+            (1..template_len).all(|overlap| {
+                template >> (template_len - overlap) != template & ((1 << overlap) - 1)
+            })
+        })
+        .collect();
 
-    log::info!("Extracted {} templates to test with", templates.len());
+    log::info!("Generated {} templates to test with", templates.len());
 
-    Ok(templates)
+    templates
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::bit_sequence::BitSequence;
+    use crate::constants;
+    use crate::customtypes;
+    use crate::customtypes::StatisticalTest;
     use crate::logger;
     use crate::non_overlapping_template;
     use crate::utils;
+    use std::io::Read;
 
     const LOGLEVEL: &str = "Debug";
     const BIT_STRING_NIST_1: &str = "10100100101110010110";
+    const TEMPLATE_NIST_1: &str = "001";
+    const COUNTS_NIST_1: [usize; 2] = [2, 1];
+    const CHI_SQUARE_NIST_1: f64 = 2.133333;
+    const P_VALUE_NIST_1: f64 = 0.344154;
+    const NUMBER_OF_TEMPLATES_9: usize = 148;
+    const TEMPLATE_E_FAIL: &str = "111110000";
+    const BIT_STRING_PERIODIC: &str = "0111110111";
+    const COUNTS_PERIODIC: [usize; 2] = [3, 2];
     const BIT_STRING_ONLY_ZEROS: &str = "0000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000";
     const BIT_STRING_ONLY_ONES: &str = "1111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111111";
     const BIT_STRING_RANDOM_PATTERN: &str = "01011010001010110101101000101111010111100010101100101010101010101010000101010101101010101011101010";
     const BIT_STRING_SAME_PATTERN: &str = "1101101101101101101101101101101101101101101101101101101101101101101101101101101101101101101101101101";
-    const INVALID_BIT_STRING: &str = "010101111010101010101010101010a0101010101010100101010101";
     const PI_FILE: &str = "/src/testdata/data.pi";
     const E_FILE: &str = "/src/testdata/data.e";
     const SQRT_2_FILE: &str = "/src/testdata/data.sqrt2";
     const SQRT_3_FILE: &str = "/src/testdata/data.sqrt3";
     const SHA_3_FILE: &str = "/src/testdata/data.sha3";
+    const TEMPLATE_ARCHIVE: &str = "/templates/template";
+
+    /// Read the templates from the archive shipped with the NIST reference implementation
+    fn read_template_archive(template_len: usize) -> Vec<String> {
+        let archive_name = std::env::current_dir()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_owned()
+            + TEMPLATE_ARCHIVE
+            + &template_len.to_string()
+            + ".tar.gz";
+        let file = std::fs::File::open(archive_name).expect("Failed to open template archive");
+        let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(file));
+
+        let mut contents = String::new();
+        for entry in archive.entries().unwrap() {
+            entry.unwrap().read_to_string(&mut contents).unwrap();
+        }
+        contents.lines().map(str::to_owned).collect()
+    }
 
     #[test]
     fn test_non_overlapping_template() {
         logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
 
-        assert!(non_overlapping_template::perform_test(BIT_STRING_NIST_1, 3, 2).unwrap() > 0.01);
+        // example from NIST SP 800-22: template B = 001, N = 2 and M = 10
+        let result =
+            non_overlapping_template::perform_test(&BIT_STRING_NIST_1.parse().unwrap(), 3, 2)
+                .unwrap();
+        let template_result = result
+            .template_results
+            .iter()
+            .find(|template_result| template_result.template == TEMPLATE_NIST_1)
+            .unwrap();
+        assert_eq!(template_result.counts, COUNTS_NIST_1);
+        assert!((template_result.chi_square - CHI_SQUARE_NIST_1).abs() < 1e-6);
+        assert!((template_result.p_value - P_VALUE_NIST_1).abs() < 1e-6);
+        assert!(result.p_values_mean() > 0.01);
+
         assert!(
-            non_overlapping_template::perform_test(BIT_STRING_RANDOM_PATTERN, 4, 3).unwrap() > 0.01
+            non_overlapping_template::perform_test(
+                &BIT_STRING_RANDOM_PATTERN.parse().unwrap(),
+                4,
+                3
+            )
+            .unwrap()
+            .p_values_mean()
+                > 0.01
         );
         assert!(
-            non_overlapping_template::perform_test(BIT_STRING_SAME_PATTERN, 3, 2).unwrap() <= 0.01
+            non_overlapping_template::perform_test(&BIT_STRING_SAME_PATTERN.parse().unwrap(), 3, 2)
+                .unwrap()
+                .p_values_mean()
+                <= 0.01
         );
         // test pi, e, sqrt(2) and sqrt(3) in their respective binary representations
         let pi_file = std::env::current_dir()
@@ -313,7 +471,12 @@ mod tests {
             .to_owned()
             + PI_FILE;
         let pi_bit_string = utils::read_random_numbers(&pi_file).unwrap();
-        assert!(non_overlapping_template::perform_test(&pi_bit_string, 10, 8).unwrap() >= 0.01);
+        assert!(
+            non_overlapping_template::perform_test(&pi_bit_string, 10, 8)
+                .unwrap()
+                .p_values_mean()
+                >= 0.01
+        );
 
         let e_file = std::env::current_dir()
             .unwrap()
@@ -322,7 +485,38 @@ mod tests {
             .to_owned()
             + E_FILE;
         let e_bit_string = utils::read_random_numbers(&e_file).unwrap();
-        assert!(non_overlapping_template::perform_test(&e_bit_string, 10, 8).unwrap() >= 0.01);
+
+        // there is one result per aperiodic template of length 9
+        let result = non_overlapping_template::perform_test(&e_bit_string, 9, 8).unwrap();
+        assert_eq!(result.template_results.len(), NUMBER_OF_TEMPLATES_9);
+        assert_eq!(result.p_values().len(), NUMBER_OF_TEMPLATES_9);
+        assert!(result
+            .template_results
+            .iter()
+            .all(|template_result| template_result.counts.len() == 8));
+        let run_result = non_overlapping_template::NonOverlappingTemplate::create(9, 8)
+            .run(&e_bit_string, 0.01)
+            .unwrap();
+        assert_eq!(run_result.p_values.len(), NUMBER_OF_TEMPLATES_9);
+        assert_eq!(
+            run_result.p_values[0].0,
+            result.template_results[0].template
+        );
+        assert_eq!(run_result.p_values[0].1, result.template_results[0].p_value);
+
+        // a single template failing at alpha does not fail the test with 148 p-values
+        assert_eq!(
+            run_result.p_value_verdict(TEMPLATE_E_FAIL),
+            Some(customtypes::Verdict::Fail)
+        );
+        assert_eq!(run_result.verdict, customtypes::Verdict::Pass);
+
+        assert!(
+            non_overlapping_template::perform_test(&e_bit_string, 10, 8)
+                .unwrap()
+                .p_values_mean()
+                >= 0.01
+        );
 
         let sqrt_2_file = std::env::current_dir()
             .unwrap()
@@ -331,7 +525,12 @@ mod tests {
             .to_owned()
             + SQRT_2_FILE;
         let sqrt_2_bit_string = utils::read_random_numbers(&sqrt_2_file).unwrap();
-        assert!(non_overlapping_template::perform_test(&sqrt_2_bit_string, 10, 8).unwrap() >= 0.01);
+        assert!(
+            non_overlapping_template::perform_test(&sqrt_2_bit_string, 10, 8)
+                .unwrap()
+                .p_values_mean()
+                >= 0.01
+        );
 
         let sqrt_3_file = std::env::current_dir()
             .unwrap()
@@ -340,7 +539,12 @@ mod tests {
             .to_owned()
             + SQRT_3_FILE;
         let sqrt_3_bit_string = utils::read_random_numbers(&sqrt_3_file).unwrap();
-        assert!(non_overlapping_template::perform_test(&sqrt_3_bit_string, 10, 8).unwrap() >= 0.01);
+        assert!(
+            non_overlapping_template::perform_test(&sqrt_3_bit_string, 10, 8)
+                .unwrap()
+                .p_values_mean()
+                >= 0.01
+        );
 
         let sha_3_file = std::env::current_dir()
             .unwrap()
@@ -349,28 +553,139 @@ mod tests {
             .to_owned()
             + SHA_3_FILE;
         let sha_3_bit_string = utils::read_random_numbers(&sha_3_file).unwrap();
-        assert!(non_overlapping_template::perform_test(&sha_3_bit_string, 10, 8).unwrap() >= 0.01);
+        assert!(
+            non_overlapping_template::perform_test(&sha_3_bit_string, 10, 8)
+                .unwrap()
+                .p_values_mean()
+                >= 0.01
+        );
     }
 
     #[test]
-    fn test_non_overlapping_template_error_cases() {
+    fn test_count_templates() {
+        logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
+
+        // a periodic template is not counted again before its occurence has ended
+        let bit_sequence: BitSequence = BIT_STRING_PERIODIC.parse().unwrap();
+        let mut template_indices = vec![usize::MAX; 4];
+        template_indices[0b11] = 0;
+        template_indices[0b01] = 1;
+        assert_eq!(
+            non_overlapping_template::count_templates(
+                &bit_sequence,
+                0..bit_sequence.len(),
+                2,
+                &template_indices,
+                2
+            ),
+            COUNTS_PERIODIC
+        );
+
+        // all templates counted at once match the occurences counted one by one per block
+        let e_file = std::env::current_dir()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_owned()
+            + E_FILE;
+        let e_bit_string = utils::read_random_numbers(&e_file).unwrap();
+        let templates = non_overlapping_template::generate_templates(9);
+        let mut template_indices = vec![usize::MAX; 1 << 9];
+        for (template_index, &template) in templates.iter().enumerate() {
+            template_indices[template as usize] = template_index;
+        }
+        let block = 200000..325000;
+        let counts = non_overlapping_template::count_templates(
+            &e_bit_string,
+            block.clone(),
+            9,
+            &template_indices,
+            templates.len(),
+        );
+        for (template, count) in templates.into_iter().zip(counts) {
+            let mut expected = 0;
+            let mut index = block.start;
+            while index + 9 <= block.end {
+                if e_bit_string.bits(index, 9) == template {
+                    expected += 1;
+                    index += 9;
+                } else {
+                    index += 1;
+                }
+            }
+            assert_eq!(count, expected);
+        }
+    }
+
+    #[test]
+    fn test_generate_templates() {
         logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
 
-        // pass empty string
-        assert!(non_overlapping_template::perform_test("", 3, 2).is_err());
+        assert_eq!(
+            non_overlapping_template::generate_templates(2),
+            [0b01, 0b10]
+        );
+        assert_eq!(
+            non_overlapping_template::generate_templates(9).len(),
+            NUMBER_OF_TEMPLATES_9
+        );
+
+        // the generated templates match the archives of the NIST reference implementation
+        for template_len in constants::TEMPLATE_LEN.0..=constants::TEMPLATE_LEN.1 {
+            let templates = non_overlapping_template::generate_templates(template_len)
+                .iter()
+                .map(|template| format!("{:0width$b}", template, width = template_len))
+                .collect::<Vec<_>>();
+            assert_eq!(templates, read_template_archive(template_len));
+        }
+    }
 
-        // pass invalid bit string
-        assert!(non_overlapping_template::perform_test(INVALID_BIT_STRING, 6, 2).is_err());
+    #[test]
+    fn test_non_overlapping_template_error_cases() {
+        logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
+
+        // pass empty sequence
+        assert!(non_overlapping_template::perform_test(&BitSequence::new(), 3, 2).is_err());
 
         // pass only zeros or only ones
-        assert!(non_overlapping_template::perform_test(BIT_STRING_ONLY_ZEROS, 4, 2).is_err());
-        assert!(non_overlapping_template::perform_test(BIT_STRING_ONLY_ONES, 4, 2).is_err());
+        assert!(non_overlapping_template::perform_test(
+            &BIT_STRING_ONLY_ZEROS.parse().unwrap(),
+            4,
+            2
+        )
+        .is_err());
+        assert!(non_overlapping_template::perform_test(
+            &BIT_STRING_ONLY_ONES.parse().unwrap(),
+            4,
+            2
+        )
+        .is_err());
 
         // pass invalid template length sizes
-        assert!(non_overlapping_template::perform_test(BIT_STRING_NIST_1, 0, 4).is_err());
-        assert!(non_overlapping_template::perform_test(BIT_STRING_NIST_1, 22, 3).is_err());
+        assert!(
+            non_overlapping_template::perform_test(&BIT_STRING_NIST_1.parse().unwrap(), 0, 4)
+                .is_err()
+        );
+        assert!(
+            non_overlapping_template::perform_test(&BIT_STRING_NIST_1.parse().unwrap(), 22, 3)
+                .is_err()
+        );
 
         // pass invalid number of blocks size
-        assert!(non_overlapping_template::perform_test(BIT_STRING_NIST_1, 3, 120).is_err());
+        assert!(
+            non_overlapping_template::perform_test(&BIT_STRING_NIST_1.parse().unwrap(), 3, 0)
+                .is_err()
+        );
+        assert!(non_overlapping_template::perform_test(
+            &BIT_STRING_NIST_1.parse().unwrap(),
+            3,
+            120
+        )
+        .is_err());
+
+        // pass a number of blocks resulting in a block size M not greater than the template length
+        let alternating_bit_sequence: BitSequence = "01".repeat(100).parse().unwrap();
+        assert!(non_overlapping_template::perform_test(&alternating_bit_sequence, 9, 40).is_err());
+        assert!(non_overlapping_template::perform_test(&alternating_bit_sequence, 5, 40).is_err());
     }
 }
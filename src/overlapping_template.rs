@@ -8,149 +8,148 @@
 //! the window slides one bit position. The difference between this test and the test in Section 2.7 is that
 //! when the pattern is found, the window slides only one bit before resuming the search."
 
+use crate::bit_sequence::BitSequence;
 use crate::constants;
 use crate::customtypes;
+use crate::customtypes::StatisticalTest;
 use crate::utils;
 use anyhow::{Context, Result};
 
 const TEST_NAME: customtypes::Test = customtypes::Test::OverlappingTemplate;
 
-/// Perform the Overlapping Template Matching Test by determining the p-value.
+/// Perform the Overlapping Template Matching Test by determining the p-value. The template is the
+/// m-bit run of ones, the bit string is divided into blocks of M = 1032 bits.
 ///
 /// # Arguments
 ///
-/// bit_string - The bit string to be tested for randomness
-/// template_len - Length of templates to be used for test
-/// number_of_blocks - The number of blocks the bit string has to be divided into
+/// bit_sequence - The bit sequence to be tested for randomness
+/// template_len - Length m of the template to be used for test
 ///
 /// # Return
 ///
 /// Ok(p-value) - The p-value which indicates whether randomness is given or not
 /// Err(err) - Some error occured
-pub fn perform_test(bit_string: &str, template_len: usize, number_of_blocks: usize) -> Result<f64> {
+pub fn perform_test(bit_sequence: &BitSequence, template_len: usize) -> Result<f64> {
     log::trace!("overlapping_template::perform_test()");
 
-    // capture the current time before executing the actual test
-    let start_time = std::time::Instant::now();
+    let result = OverlappingTemplate::create(template_len)
+        .run(bit_sequence, constants::P_VALUE_THRESHOLD)?;
 
-    // check if bit string contains invalid characters
-    let length = utils::evaluate_bit_string(
-        TEST_NAME,
-        bit_string,
-        constants::RECOMMENDED_SIZE_OVERLAPPING_TEMPLATE,
-    )
-    .with_context(|| "Invalid character(s) in passed bit string detected")?;
-
-    // evaluate the other input and get the block size m
-    let block_size = evaluate_test_params(length, template_len, number_of_blocks)
-        .with_context(|| "Template length does not match defined requirements")?;
-
-    // calculate number of templates to be searched
-    let number_of_templates = 2_usize.pow(template_len.try_into().unwrap());
-
-    // calculate theoretical mean and variance
-    let first_fraction = 1.0 / (number_of_templates as f64);
-    let second_fraction =
-        (2.0 * (template_len as f64) - 1.0) / 2.0_f64.powf(2.0 * (template_len as f64));
-
-    let mean = ((block_size - template_len + 1) as f64) / (number_of_templates as f64);
-    let variance = (block_size as f64) * (first_fraction - second_fraction);
-    log::debug!(
-        "{}: Theoretical mean = {}, Variance = {}",
-        TEST_NAME,
-        mean,
-        variance
-    );
+    Ok(result.p_values[0].1)
+}
 
-    // now iterate over each template and search for it in each substring
-    let mut p_values = Vec::<f64>::new();
+/// Parameters of the "Overlapping Template Matching" test
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OverlappingTemplate {
+    pub template_len: usize,
+}
 
-    for num in 0..number_of_templates {
-        let template = format!("{:0width$b}", num, width = template_len);
-        let mut template_counters = Vec::<usize>::new();
+impl OverlappingTemplate {
+    pub fn create(template_len: usize) -> Self {
+        OverlappingTemplate { template_len }
+    }
+}
 
-        // now iterate over blocks 1...N and count occurences of respective template in substring
-        for block in 0..number_of_blocks {
-            let start_index = block * block_size;
-            let end_index = (block + 1) * block_size;
-            let substring = &bit_string[start_index..end_index];
+impl customtypes::StatisticalTest for OverlappingTemplate {
+    fn test(&self) -> customtypes::Test {
+        TEST_NAME
+    }
 
-            let mut counter = 0;
-            let mut index = 0;
+    fn params(&self) -> Vec<(String, String)> {
+        vec![("template_len".to_owned(), self.template_len.to_string())]
+    }
 
-            while let Some(start) = substring[index..].find(&template) {
-                counter += 1;
+    fn compute(&self, bit_sequence: &BitSequence) -> Result<customtypes::TestStatistics> {
+        log::trace!("overlapping_template::compute()");
 
-                // move the index to the next possible occurence
-                index += start + template_len;
-            }
+        let template_len = self.template_len;
+
+        // check if bit sequence is valid
+        let length = utils::evaluate_bit_sequence(
+            TEST_NAME,
+            bit_sequence,
+            constants::RECOMMENDED_SIZE_OVERLAPPING_TEMPLATE,
+        )
+        .with_context(|| "Invalid bit sequence passed")?;
 
-            log::trace!(
-                "{}: Template '{}' in substring '{}' found {} times",
+        // evaluate the other input and get the number of blocks N
+        let block_size = constants::BLOCK_SIZE_OVERLAPPING_TEMPLATE;
+        let number_of_blocks = evaluate_test_params(length, template_len)
+            .with_context(|| "Template length does not match defined requirements")?;
+
+        // compute the probabilities pi_0, ..., pi_K of the template occuring 0, ..., K times per block
+        let probabilities = compute_probabilities(template_len, block_size);
+        log::debug!("{}: Probabilities pi_i = {:?}", TEST_NAME, probabilities);
+
+        // NIST recommends N * min(pi_i) > 5. Log a warning if this does not hold
+        let min_expected = probabilities
+            .iter()
+            .map(|pi| (number_of_blocks as f64) * pi)
+            .fold(f64::INFINITY, f64::min);
+        if min_expected <= constants::MIN_EXPECTED_OVERLAPPING_TEMPLATE {
+            log::warn!(
+                "{}: Expected number of blocks per category ({}) should be greater than {}",
                 TEST_NAME,
-                template,
-                substring,
-                counter
+                min_expected,
+                constants::MIN_EXPECTED_OVERLAPPING_TEMPLATE
             );
-            template_counters.push(counter);
         }
-        // compute chi_square statistics
-        let mut chi_square = 0.0;
-        for counter in &template_counters {
-            chi_square += ((*counter as f64) - mean).powf(2.0) / variance;
+
+        // count the overlapping occurences of the template in each block and categorize the blocks
+        // by their number of occurences v_0, ..., v_K
+        let occurences = bit_sequence
+            .blocks(block_size)
+            .map(|block| count_template(&block, template_len));
+
+        let mut categories = [0_usize; constants::MAX_N_OVERLAPPING_TEMPLATE + 1];
+        for counter in occurences {
+            categories[counter.min(constants::MAX_N_OVERLAPPING_TEMPLATE)] += 1;
         }
-        log::trace!(
-            "{}: Chi_square = {} for template '{}'",
-            TEST_NAME,
-            chi_square,
-            template
-        );
+        log::debug!("{}: Categories v_i = {:?}", TEST_NAME, categories);
 
-        // now compute p-value for current template with incomplete gamma function
+        // compute chi_square statistics: sum((v_i - N * pi_i)^2 / (N * pi_i))
+        let chi_square = categories
+            .iter()
+            .zip(probabilities.iter())
+            .map(|(&v_i, &pi_i)| {
+                let expected = (number_of_blocks as f64) * pi_i;
+                ((v_i as f64) - expected).powi(2) / expected
+            })
+            .sum::<f64>();
+        log::debug!("{}: Chi_square value: {}", TEST_NAME, chi_square);
+
+        // finally, compute p-value with the incomplete gamma function: igamc(K/2, chi_square/2)
+        // Note: If chi_square is zero, which is an invalid input for igamc, return p-value of 1
         let p_value = if chi_square == 0.0 {
             1.0
         } else {
-            statrs::function::gamma::gamma_ur((number_of_blocks as f64) * 0.5, chi_square * 0.5)
+            statrs::function::gamma::gamma_ur(
+                (constants::MAX_N_OVERLAPPING_TEMPLATE as f64) * 0.5,
+                chi_square * 0.5,
+            )
         };
-        log::trace!(
-            "{}: p-value = {} for template '{}'",
-            TEST_NAME,
-            p_value,
-            template
-        );
+        log::info!("{}: p-value = {}", TEST_NAME, p_value);
 
-        p_values.push(p_value);
+        Ok(customtypes::TestStatistics::create(
+            vec![("chi_square".to_owned(), chi_square)],
+            vec![("p-value".to_owned(), p_value)],
+        ))
     }
-
-    let p_values_mean = p_values.iter().sum::<f64>() / (p_values.len() as f64);
-    log::info!("{}: Mean of p-values = {}", TEST_NAME, p_values_mean);
-
-    // capture the current time after the test got executed and calculate elapsed time
-    let end_time = std::time::Instant::now();
-    let elapsed_time = end_time.duration_since(start_time).as_secs_f64();
-    log::info!("{} took {:.6} seconds", TEST_NAME, elapsed_time);
-
-    Ok(p_values_mean)
 }
 
-/// Evaluate passed test parameters and return the resulting block size M.
+/// Evaluate passed test parameters and return the resulting number of blocks N.
 ///
 /// # Arguments
 ///
 /// bit_string_length - Length of bit string
-/// template_len - Length of template to be searched later in substrings
-/// number_of_blocks - The number of blocks the bitstring has to be divided into
+/// template_len - Length of template to be searched later in blocks
 ///
 /// # Return
 ///
-/// Ok(block_size) - The resulting block size if template length is okay
+/// Ok(number_of_blocks) - The resulting number of blocks if template length is okay
 /// Err(err) - Some error occured
-fn evaluate_test_params(
-    bit_string_length: usize,
-    template_len: usize,
-    number_of_blocks: usize,
-) -> Result<usize> {
-    log::trace!("non_overlapping_template::evaluate_test_params()");
+fn evaluate_test_params(bit_string_length: usize, template_len: usize) -> Result<usize> {
+    log::trace!("overlapping_template::evaluate_test_params()");
 
     // check whether template length is between thresholds for meaningful results
     if !(constants::TEMPLATE_LEN.0..constants::TEMPLATE_LEN.1 + 1).contains(&template_len) {
@@ -164,35 +163,25 @@ fn evaluate_test_params(
     }
 
     // recommended sizes for template lengths: 9, 10. Log a warning if they do not match
-    if template_len < constants::TEMPLATE_LEN.1 - 1 {
+    if !(constants::RECOMMENDED_TEMPLATE_LEN.0..constants::RECOMMENDED_TEMPLATE_LEN.1 + 1)
+        .contains(&template_len)
+    {
         log::warn!(
             "{}: Recommended size for template length: {}, {}",
             TEST_NAME,
-            constants::TEMPLATE_LEN.1 - 1,
-            constants::TEMPLATE_LEN.1
+            constants::RECOMMENDED_TEMPLATE_LEN.0,
+            constants::RECOMMENDED_TEMPLATE_LEN.1
         );
     }
 
-    // check number of blocks
-    if number_of_blocks > constants::RECOMMENDED_SIZE {
+    // the bit string has to contain at least one block of size M. Remaining bits are discarded
+    let number_of_blocks = bit_string_length / constants::BLOCK_SIZE_OVERLAPPING_TEMPLATE;
+    if number_of_blocks == 0 {
         anyhow::bail!(
-            "{}: Number of blocks N ({}) is greater than recommended size ({})",
+            "{}: Length of bit string ({}) is less than block size M ({})",
             TEST_NAME,
-            number_of_blocks,
-            constants::RECOMMENDED_SIZE
-        );
-    }
-
-    // construct block size M to get the substrings to be tested
-    let block_size = bit_string_length / number_of_blocks;
-    let recommended_size = bit_string_length / 100;
-
-    if block_size <= recommended_size {
-        anyhow::bail!(
-            "{}: Block size M ({}) is less than or equal to {}. Choose smaller number of blocks",
-            TEST_NAME,
-            block_size,
-            recommended_size
+            bit_string_length,
+            constants::BLOCK_SIZE_OVERLAPPING_TEMPLATE
         );
     }
 
@@ -200,9 +189,227 @@ fn evaluate_test_params(
         "{}: Template length = {}, Block size M = {}, Number of blocks N = {}",
         TEST_NAME,
         template_len,
-        block_size,
+        constants::BLOCK_SIZE_OVERLAPPING_TEMPLATE,
         number_of_blocks
     );
 
-    Ok(block_size)
+    Ok(number_of_blocks)
+}
+
+/// Count the overlapping occurences of the template of m ones in a block. Each position where the
+/// current run of ones is at least m bits long marks the end of one occurence.
+///
+/// # Arguments
+///
+/// block - The block of bits to search in
+/// template_len - Length m of the template
+///
+/// # Return
+///
+/// counter - The number of occurences of the template
+fn count_template(block: &BitSequence, template_len: usize) -> usize {
+    let mut counter = 0;
+    let mut run = 0;
+
+    for bit in block {
+        if bit {
+            run += 1;
+            if run >= template_len {
+                counter += 1;
+            }
+        } else {
+            run = 0;
+        }
+    }
+
+    counter
+}
+
+/// Compute the probabilities pi_0, ..., pi_K that the template occurs 0, ..., K - 1 or at least K
+/// times in a block as the NIST reference implementation does with
+/// lambda = (M - m + 1) / 2^m and eta = lambda / 2.
+///
+/// # Arguments
+///
+/// template_len - Length m of the template
+/// block_size - Block size M
+///
+/// # Return
+///
+/// probabilities - The probabilities pi_0, ..., pi_K
+fn compute_probabilities(
+    template_len: usize,
+    block_size: usize,
+) -> [f64; constants::MAX_N_OVERLAPPING_TEMPLATE + 1] {
+    log::trace!("overlapping_template::compute_probabilities()");
+
+    let lambda = ((block_size - template_len + 1) as f64) / 2.0_f64.powi(template_len as i32);
+    let eta = lambda * 0.5;
+
+    let mut probabilities = [0.0; constants::MAX_N_OVERLAPPING_TEMPLATE + 1];
+    for (u, pi) in probabilities
+        .iter_mut()
+        .take(constants::MAX_N_OVERLAPPING_TEMPLATE)
+        .enumerate()
+    {
+        *pi = probability(u, eta);
+    }
+    probabilities[constants::MAX_N_OVERLAPPING_TEMPLATE] = 1.0 - probabilities.iter().sum::<f64>();
+
+    probabilities
+}
+
+/// Compute the probability that the template occurs exactly u times in a block:
+/// Pr(u, eta) = e^(-eta) / 2^u * sum_{l=1}^{u} (u-1 choose l-1) * eta^l / l!
+///
+/// # Arguments
+///
+/// u - The number of occurences
+/// eta - The parameter eta = lambda / 2
+///
+/// # Return
+///
+/// probability - The probability Pr(u, eta)
+fn probability(u: usize, eta: f64) -> f64 {
+    if u == 0 {
+        return (-eta).exp();
+    }
+
+    let ln_gamma = statrs::function::gamma::ln_gamma;
+    let u_f = u as f64;
+
+    (1..=u)
+        .map(|l| {
+            let l_f = l as f64;
+            (-eta - u_f * std::f64::consts::LN_2 + l_f * eta.ln() - ln_gamma(l_f + 1.0)
+                + ln_gamma(u_f)
+                - ln_gamma(l_f)
+                - ln_gamma(u_f - l_f + 1.0))
+            .exp()
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bit_sequence::BitSequence;
+    use crate::constants;
+    use crate::logger;
+    use crate::overlapping_template;
+    use crate::utils;
+
+    const LOGLEVEL: &str = "Debug";
+    const P_VALUE_NIST_E: f64 = 0.110434;
+    const NIST_LENGTH: usize = 1000000;
+
+    #[test]
+    fn test_count_template() {
+        logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
+
+        // overlapping occurences are counted, i.e. the window slides only one bit after a match
+        assert_eq!(
+            overlapping_template::count_template(&"1011101111".parse().unwrap(), 2),
+            5
+        );
+        assert_eq!(
+            overlapping_template::count_template(&"1011101111".parse().unwrap(), 3),
+            3
+        );
+        assert_eq!(
+            overlapping_template::count_template(&"0000000000".parse().unwrap(), 2),
+            0
+        );
+    }
+
+    #[test]
+    fn test_probabilities() {
+        logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
+
+        // the approximation of the reference implementation is close to the theoretical values
+        let probabilities = overlapping_template::compute_probabilities(
+            9,
+            constants::BLOCK_SIZE_OVERLAPPING_TEMPLATE,
+        );
+        assert!((probabilities.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        for (pi, expected) in probabilities
+            .iter()
+            .zip(constants::PI_VALUES_OVERLAPPING_TEMPLATE.iter())
+        {
+            assert!((pi - expected).abs() < 0.005);
+        }
+    }
+
+    #[test]
+    fn test_overlapping_template() {
+        logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
+
+        // a bit string of only ones contains the template in every position of every block
+        let only_ones: BitSequence = std::iter::repeat_n(true, NIST_LENGTH).collect();
+        assert!(overlapping_template::perform_test(&only_ones, 9).unwrap() < 0.01);
+
+        // the first 10^6 bits of e must match the result of NIST SP 800-22
+        let e_file = std::env::current_dir()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_owned()
+            + constants::E_FILE;
+        let e_bit_string = utils::read_random_numbers(&e_file).unwrap();
+        let p_value =
+            overlapping_template::perform_test(&e_bit_string.slice(0..NIST_LENGTH), 9).unwrap();
+        assert!((p_value - P_VALUE_NIST_E).abs() < 1e-6);
+
+        // test pi, sqrt(2), sqrt(3) and SHA-3 in their respective binary representations
+        let pi_file = std::env::current_dir()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_owned()
+            + constants::PI_FILE;
+        let pi_bit_string = utils::read_random_numbers(&pi_file).unwrap();
+        assert!(overlapping_template::perform_test(&pi_bit_string, 9).unwrap() >= 0.01);
+
+        let sqrt_2_file = std::env::current_dir()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_owned()
+            + constants::SQRT_2_FILE;
+        let sqrt_2_bit_string = utils::read_random_numbers(&sqrt_2_file).unwrap();
+        assert!(overlapping_template::perform_test(&sqrt_2_bit_string, 9).unwrap() >= 0.01);
+
+        let sqrt_3_file = std::env::current_dir()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_owned()
+            + constants::SQRT_3_FILE;
+        let sqrt_3_bit_string = utils::read_random_numbers(&sqrt_3_file).unwrap();
+        assert!(overlapping_template::perform_test(&sqrt_3_bit_string, 9).unwrap() >= 0.01);
+
+        let sha_3_file = std::env::current_dir()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_owned()
+            + constants::SHA_3_FILE;
+        let sha_3_bit_string = utils::read_random_numbers(&sha_3_file).unwrap();
+        assert!(overlapping_template::perform_test(&sha_3_bit_string, 9).unwrap() >= 0.01);
+    }
+
+    #[test]
+    fn test_overlapping_template_error_cases() {
+        logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
+
+        // pass empty sequence
+        assert!(overlapping_template::perform_test(&BitSequence::new(), 9).is_err());
+
+        // pass bit string shorter than one block
+        assert!(overlapping_template::perform_test(&"01".repeat(500).parse().unwrap(), 9).is_err());
+
+        // pass invalid template lengths
+        let bit_sequence = "01".repeat(1000).parse().unwrap();
+        assert!(overlapping_template::perform_test(&bit_sequence, 1).is_err());
+        assert!(overlapping_template::perform_test(&bit_sequence, 22).is_err());
+    }
 }
@@ -0,0 +1,463 @@
+//! This module performs the Random Excursions Test.
+//!
+//! Description of test from NIST SP 800-22:
+//!
+//! "The focus of this test is the number of cycles having exactly K visits in a cumulative sum random walk.
+//! The cumulative sum random walk is derived from partial sums after the (0,1) sequence is transferred to
+//! the appropriate (-1, +1) sequence. A cycle of a random walk consists of a sequence of steps of unit length
+//! taken at random that begin at and return to the origin. The purpose of this test is to determine if the
+//! number of visits to a particular state within a cycle deviates from what one would expect for a random
+//! sequence. This test is actually a series of eight tests (and conclusions), one test and conclusion for each
+//! of the states: -4, -3, -2, -1 and +1, +2, +3, +4."
+
+use crate::bit_sequence::BitSequence;
+use crate::constants;
+use crate::cumulative_sums;
+use crate::customtypes;
+use crate::customtypes::StatisticalTest;
+use crate::utils;
+use anyhow::{Context, Result};
+
+const TEST_NAME: customtypes::Test = customtypes::Test::RandomExcursions;
+
+/// Perform the Random Excursions Test by determining the p-values of all eight states.
+///
+/// # Arguments
+///
+/// bit_sequence - The bit sequence to be tested for randomness
+///
+/// # Return
+///
+/// Ok(ExcursionsResult::Applicable(p-values)) - The p-values per state x = -4..-1, 1..4
+/// Ok(ExcursionsResult::NotApplicable) - The bit string has not enough cycles J
+/// Err(err) - Some error occured
+pub fn perform_test(bit_sequence: &BitSequence) -> Result<customtypes::ExcursionsResult> {
+    log::trace!("random_excursions::perform_test()");
+
+    let result = RandomExcursions.run(bit_sequence, constants::P_VALUE_THRESHOLD)?;
+
+    Ok(create_excursions_result(
+        &result,
+        &constants::RANDOM_EXCURSIONS_STATES,
+        bit_sequence.len(),
+    ))
+}
+
+/// The "Random Excursions" test. The test has no parameters
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RandomExcursions;
+
+impl customtypes::StatisticalTest for RandomExcursions {
+    fn test(&self) -> customtypes::Test {
+        TEST_NAME
+    }
+
+    fn params(&self) -> Vec<(String, String)> {
+        Vec::new()
+    }
+
+    fn compute(&self, bit_sequence: &BitSequence) -> Result<customtypes::TestStatistics> {
+        log::trace!("random_excursions::compute()");
+
+        self.compute_on_walk(
+            bit_sequence,
+            &cumulative_sums::compute_random_walk(bit_sequence),
+        )
+    }
+
+    fn uses_random_walk(&self) -> bool {
+        true
+    }
+
+    fn compute_on_walk(
+        &self,
+        bit_sequence: &BitSequence,
+        random_walk: &customtypes::RandomWalk,
+    ) -> Result<customtypes::TestStatistics> {
+        log::trace!("random_excursions::compute_on_walk()");
+
+        // check if bit sequence is valid
+        utils::evaluate_bit_sequence(
+            TEST_NAME,
+            bit_sequence,
+            constants::RECOMMENDED_SIZE_RANDOM_EXCURSIONS,
+        )
+        .with_context(|| "Invalid bit sequence passed")?;
+
+        let cycles_j = ("J".to_owned(), random_walk.number_of_cycles() as f64);
+
+        // a test which is not applicable has no p-values
+        let p_values = match perform_test_on_walk(random_walk)? {
+            customtypes::ExcursionsResult::Applicable(p_values) => p_values
+                .into_iter()
+                .map(|(state, p_value)| (format!("x = {:+}", state), p_value))
+                .collect(),
+            customtypes::ExcursionsResult::NotApplicable { .. } => Vec::new(),
+        };
+
+        Ok(customtypes::TestStatistics::create(
+            vec![cycles_j],
+            p_values,
+        ))
+    }
+}
+
+/// Perform the Random Excursions Test on an already computed random walk.
+///
+/// # Arguments
+///
+/// random_walk - The random walk of the bit string to be tested for randomness
+///
+/// # Return
+///
+/// Ok(ExcursionsResult::Applicable(p-values)) - The p-values per state x = -4..-1, 1..4
+/// Ok(ExcursionsResult::NotApplicable) - The bit string has not enough cycles J
+/// Err(err) - Some error occured
+pub fn perform_test_on_walk(
+    random_walk: &customtypes::RandomWalk,
+) -> Result<customtypes::ExcursionsResult> {
+    log::trace!("random_excursions::perform_test_on_walk()");
+
+    let length = random_walk.partial_sums.len();
+    let cycles_j = random_walk.number_of_cycles();
+    log::debug!("{}: Number of cycles J = {}", TEST_NAME, cycles_j);
+
+    // the test is not applicable if J < max(0.005 * sqrt(n), 500)
+    let min_cycles = compute_min_cycles(length);
+    if cycles_j < min_cycles {
+        log::warn!(
+            "{} is not applicable! Number of cycles J ({}) is less than {}",
+            TEST_NAME,
+            cycles_j,
+            min_cycles
+        );
+        return Ok(customtypes::ExcursionsResult::NotApplicable {
+            cycles: cycles_j,
+            min_cycles,
+        });
+    }
+
+    // count the number of cycles v_k(x) in which each state x is visited exactly k times
+    let state_counts = compute_state_counts(random_walk);
+
+    // compute chi_square statistics and p-value for each state with the incomplete gamma
+    // function: igamc(5/2, chi_square/2)
+    let mut p_values = Vec::<(i64, f64)>::new();
+    p_values.reserve_exact(constants::RANDOM_EXCURSIONS_STATES.len());
+
+    for (state, counts) in constants::RANDOM_EXCURSIONS_STATES
+        .iter()
+        .zip(state_counts.iter())
+    {
+        let pi_values =
+            &constants::PI_VALUES_RANDOM_EXCURSIONS[(state.unsigned_abs() - 1) as usize];
+        let chi_square = compute_chi_square(cycles_j, counts, pi_values);
+        let p_value = statrs::function::gamma::gamma_ur(
+            ((pi_values.len() - 1) as f64) * 0.5,
+            chi_square * 0.5,
+        );
+        log::debug!(
+            "{}: State x = {:+}: v_k(x) = {:?}, chi_square = {}, p-value = {}",
+            TEST_NAME,
+            state,
+            counts,
+            chi_square,
+            p_value
+        );
+
+        if p_value < constants::P_VALUE_THRESHOLD {
+            log::warn!(
+                "{}: p-value ({}) for state x = {:+} is below threshold",
+                TEST_NAME,
+                p_value,
+                state
+            );
+        }
+
+        p_values.push((*state, p_value));
+    }
+    log::info!("{}: p-values = {:?}", TEST_NAME, p_values);
+
+    Ok(customtypes::ExcursionsResult::Applicable(p_values))
+}
+
+/// Compute the minimum number of cycles J for which the random excursions tests are applicable.
+///
+/// # Arguments
+///
+/// length - The length n of the random walk
+///
+/// # Return
+///
+/// min_cycles - max(0.005 * sqrt(n), 500)
+pub fn compute_min_cycles(length: usize) -> usize {
+    constants::MIN_CYCLES_J.max((constants::CYCLES_J_FACTOR * (length as f64).sqrt()) as usize)
+}
+
+/// Convert the result of a random excursions test run back into the p-values per state.
+///
+/// # Arguments
+///
+/// result - The result of the test run
+/// states - The states x the p-values of the test belong to, sorted by state
+/// length - The length n of the tested bit string
+///
+/// # Return
+///
+/// ExcursionsResult::Applicable(p-values) - The p-values per state
+/// ExcursionsResult::NotApplicable - The test run has no p-values because of too few cycles J
+pub fn create_excursions_result(
+    result: &customtypes::TestResult,
+    states: &[i64],
+    length: usize,
+) -> customtypes::ExcursionsResult {
+    if result.p_values.is_empty() {
+        let cycles = result
+            .statistics
+            .first()
+            .map_or(0, |(_, cycles_j)| *cycles_j as usize);
+
+        return customtypes::ExcursionsResult::NotApplicable {
+            cycles,
+            min_cycles: compute_min_cycles(length),
+        };
+    }
+
+    customtypes::ExcursionsResult::Applicable(
+        states
+            .iter()
+            .zip(result.p_values.iter())
+            .map(|(state, (_, p_value))| (*state, *p_value))
+            .collect(),
+    )
+}
+
+/// Count for each state x in how many cycles it is visited exactly k times (k = 0..4) or at least
+/// 5 times (k = 5).
+///
+/// # Arguments
+///
+/// random_walk - The random walk already split into cycles
+///
+/// # Return
+///
+/// state_counts - The counts v_k(x) per state x
+fn compute_state_counts(random_walk: &customtypes::RandomWalk) -> [[usize; 6]; 8] {
+    log::trace!("random_excursions::compute_state_counts()");
+
+    let mut state_counts = [[0_usize; 6]; 8];
+
+    for cycle in random_walk.cycles() {
+        let mut visits = [0_usize; 8];
+        for &partial_sum in cycle {
+            if let Some(state_index) = state_index(partial_sum) {
+                visits[state_index] += 1;
+            }
+        }
+
+        for (counts, visit) in state_counts.iter_mut().zip(visits.iter()) {
+            counts[(*visit).min(5)] += 1;
+        }
+    }
+
+    state_counts
+}
+
+/// Map a state x to its position within the states -4..-1, 1..4.
+///
+/// # Arguments
+///
+/// state - The state x of the random walk
+///
+/// # Return
+///
+/// Some(index) - The position of the state
+/// None - The state is not considered by the test
+fn state_index(state: i64) -> Option<usize> {
+    match state {
+        -4..=-1 => Some((state + 4) as usize),
+        1..=4 => Some((state + 3) as usize),
+        _ => None,
+    }
+}
+
+/// Compute the chi_square statistic of a single state.
+///
+/// # Arguments
+///
+/// cycles_j - The number of cycles J
+/// counts - The number of cycles v_k(x) in which the state is visited k times
+/// pi_values - The theoretical probabilities pi_k(x)
+///
+/// # Return
+///
+/// chi_square - sum((v_k(x) - J * pi_k(x))^2 / (J * pi_k(x)))
+fn compute_chi_square(cycles_j: usize, counts: &[usize], pi_values: &[f64]) -> f64 {
+    let j = cycles_j as f64;
+
+    counts
+        .iter()
+        .zip(pi_values.iter())
+        .map(|(&v_k, &pi_k)| ((v_k as f64) - j * pi_k).powf(2.0) / (j * pi_k))
+        .sum::<f64>()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bit_sequence::BitSequence;
+    use crate::constants;
+    use crate::cumulative_sums;
+    use crate::customtypes;
+    use crate::customtypes::StatisticalTest;
+    use crate::logger;
+    use crate::random_excursions;
+    use crate::utils;
+
+    const LOGLEVEL: &str = "Debug";
+    const BIT_STRING_NIST_1: &str = "0110110101";
+    const CYCLES_NIST_1: usize = 3;
+    const CHI_SQUARE_NIST_1: f64 = 13.0 / 3.0;
+    const CYCLES_NIST_E: usize = 1490;
+    const P_VALUE_NIST_E: f64 = 0.786868;
+    const NIST_LENGTH: usize = 1000000;
+    const BIT_STRING_FAST_OSCILLATION: &str = "1010101010101010101010101010101010101010101010101010101010101010101010101010101010101010101010101010";
+
+    #[test]
+    fn test_state_counts() {
+        logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
+
+        // example from NIST SP 800-22: the cycles are {0, -1, 0}, {0, 1, 0} and
+        // {0, 1, 2, 1, 2, 1, 2, 0}
+        let random_walk = cumulative_sums::compute_random_walk(&BIT_STRING_NIST_1.parse().unwrap());
+        let cycles_j = random_walk.number_of_cycles();
+        let state_counts = random_excursions::compute_state_counts(&random_walk);
+        assert_eq!(cycles_j, CYCLES_NIST_1);
+        assert_eq!(state_counts[3], [2, 1, 0, 0, 0, 0]);
+        assert_eq!(state_counts[4], [1, 1, 0, 1, 0, 0]);
+        assert_eq!(state_counts[5], [2, 0, 0, 1, 0, 0]);
+        assert_eq!(state_counts[0], [3, 0, 0, 0, 0, 0]);
+
+        let chi_square = random_excursions::compute_chi_square(
+            cycles_j,
+            &state_counts[4],
+            &constants::PI_VALUES_RANDOM_EXCURSIONS[0],
+        );
+        assert!((chi_square - CHI_SQUARE_NIST_1).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_random_excursions() {
+        logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
+
+        // short bit strings do not have enough cycles
+        assert_eq!(
+            random_excursions::perform_test(&BIT_STRING_NIST_1.parse().unwrap()).unwrap(),
+            customtypes::ExcursionsResult::NotApplicable {
+                cycles: CYCLES_NIST_1,
+                min_cycles: constants::MIN_CYCLES_J
+            }
+        );
+        assert!(matches!(
+            random_excursions::perform_test(&BIT_STRING_FAST_OSCILLATION.parse().unwrap()).unwrap(),
+            customtypes::ExcursionsResult::NotApplicable { .. }
+        ));
+
+        // the first 10^6 bits of e must match the result of NIST SP 800-22
+        let e_file = std::env::current_dir()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_owned()
+            + constants::E_FILE;
+        let e_bit_string = utils::read_random_numbers(&e_file).unwrap();
+        let random_walk = cumulative_sums::compute_random_walk(&e_bit_string.slice(0..NIST_LENGTH));
+        assert_eq!(random_walk.number_of_cycles(), CYCLES_NIST_E);
+        assert_eq!(
+            random_excursions::perform_test_on_walk(&random_walk).unwrap(),
+            random_excursions::perform_test(&e_bit_string.slice(0..NIST_LENGTH)).unwrap()
+        );
+        match random_excursions::perform_test_on_walk(&random_walk).unwrap() {
+            customtypes::ExcursionsResult::Applicable(p_values) => {
+                assert_eq!(p_values.len(), constants::RANDOM_EXCURSIONS_STATES.len());
+                let (_, p_value) = p_values.iter().find(|(state, _)| *state == 1).unwrap();
+                assert!((p_value - P_VALUE_NIST_E).abs() < 1e-6);
+            }
+            result => panic!("Unexpected result {:?}", result),
+        }
+
+        // test sqrt(2) and sqrt(3) in their respective binary representations
+        let sqrt_2_file = std::env::current_dir()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_owned()
+            + constants::SQRT_2_FILE;
+        let sqrt_2_bit_string = utils::read_random_numbers(&sqrt_2_file).unwrap();
+        assert!(random_excursions::perform_test(&sqrt_2_bit_string).is_ok());
+
+        let sqrt_3_file = std::env::current_dir()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_owned()
+            + constants::SQRT_3_FILE;
+        let sqrt_3_bit_string = utils::read_random_numbers(&sqrt_3_file).unwrap();
+        assert!(random_excursions::perform_test(&sqrt_3_bit_string).is_ok());
+    }
+
+    #[test]
+    fn test_random_excursions_run() {
+        logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
+
+        // a test without p-values is not applicable
+        let result = random_excursions::RandomExcursions
+            .run(
+                &BIT_STRING_NIST_1.parse().unwrap(),
+                constants::P_VALUE_THRESHOLD,
+            )
+            .unwrap();
+        assert_eq!(result.statistics, [("J".to_owned(), CYCLES_NIST_1 as f64)]);
+        assert!(result.p_values.is_empty());
+        assert_eq!(result.verdict, customtypes::Verdict::NotApplicable);
+
+        // otherwise there is one p-value per state
+        let e_file = std::env::current_dir()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_owned()
+            + constants::E_FILE;
+        let e_bit_string = utils::read_random_numbers(&e_file).unwrap();
+        let result = random_excursions::RandomExcursions
+            .run(
+                &e_bit_string.slice(0..NIST_LENGTH),
+                constants::P_VALUE_THRESHOLD,
+            )
+            .unwrap();
+        assert_eq!(result.statistics, [("J".to_owned(), CYCLES_NIST_E as f64)]);
+        assert_eq!(
+            result.p_values.len(),
+            constants::RANDOM_EXCURSIONS_STATES.len()
+        );
+        let (_, p_value) = result
+            .p_values
+            .iter()
+            .find(|(state, _)| state == "x = +1")
+            .unwrap();
+        assert!((p_value - P_VALUE_NIST_E).abs() < 1e-6);
+        assert_eq!(
+            result.p_value_verdict("x = +1"),
+            Some(customtypes::Verdict::Pass)
+        );
+        assert_eq!(result.p_value_verdicts.len(), result.p_values.len());
+        assert_ne!(result.verdict, customtypes::Verdict::NotApplicable);
+    }
+
+    #[test]
+    fn test_random_excursions_error_cases() {
+        logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
+
+        // pass empty sequence
+        assert!(random_excursions::perform_test(&BitSequence::new()).is_err());
+    }
+}
@@ -0,0 +1,306 @@
+//! This module performs the Random Excursions Variant Test.
+//!
+//! Description of test from NIST SP 800-22:
+//!
+//! "The focus of this test is the total number of times that a particular state is visited (i.e., occurs) in a
+//! cumulative sum random walk. The purpose of this test is to detect deviations from the expected number
+//! of visits to various states in the random walk. This test is actually a series of eighteen tests (and
+//! conclusions), one test and conclusion for each of the states: -9, -8, ..., -1 and +1, +2, ..., +9."
+
+use crate::bit_sequence::BitSequence;
+use crate::constants;
+use crate::cumulative_sums;
+use crate::customtypes;
+use crate::customtypes::StatisticalTest;
+use crate::random_excursions;
+use crate::utils;
+use anyhow::{Context, Result};
+
+const TEST_NAME: customtypes::Test = customtypes::Test::RandomExcursionsVariant;
+
+/// Perform the Random Excursions Variant Test by determining the p-values of all eighteen states.
+///
+/// # Arguments
+///
+/// bit_sequence - The bit sequence to be tested for randomness
+///
+/// # Return
+///
+/// Ok(ExcursionsResult::Applicable(p-values)) - The p-values per state x = -9..-1, 1..9
+/// Ok(ExcursionsResult::NotApplicable) - The bit string has not enough cycles J
+/// Err(err) - Some error occured
+pub fn perform_test(bit_sequence: &BitSequence) -> Result<customtypes::ExcursionsResult> {
+    log::trace!("random_excursions_variant::perform_test()");
+
+    let result = RandomExcursionsVariant.run(bit_sequence, constants::P_VALUE_THRESHOLD)?;
+
+    Ok(random_excursions::create_excursions_result(
+        &result,
+        &constants::RANDOM_EXCURSIONS_VARIANT_STATES,
+        bit_sequence.len(),
+    ))
+}
+
+/// The "Random Excursions Variant" test. The test has no parameters
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RandomExcursionsVariant;
+
+impl customtypes::StatisticalTest for RandomExcursionsVariant {
+    fn test(&self) -> customtypes::Test {
+        TEST_NAME
+    }
+
+    fn params(&self) -> Vec<(String, String)> {
+        Vec::new()
+    }
+
+    fn compute(&self, bit_sequence: &BitSequence) -> Result<customtypes::TestStatistics> {
+        log::trace!("random_excursions_variant::compute()");
+
+        self.compute_on_walk(
+            bit_sequence,
+            &cumulative_sums::compute_random_walk(bit_sequence),
+        )
+    }
+
+    fn uses_random_walk(&self) -> bool {
+        true
+    }
+
+    fn compute_on_walk(
+        &self,
+        bit_sequence: &BitSequence,
+        random_walk: &customtypes::RandomWalk,
+    ) -> Result<customtypes::TestStatistics> {
+        log::trace!("random_excursions_variant::compute_on_walk()");
+
+        // check if bit sequence is valid
+        utils::evaluate_bit_sequence(
+            TEST_NAME,
+            bit_sequence,
+            constants::RECOMMENDED_SIZE_RANDOM_EXCURSIONS,
+        )
+        .with_context(|| "Invalid bit sequence passed")?;
+
+        let cycles_j = ("J".to_owned(), random_walk.number_of_cycles() as f64);
+
+        // a test which is not applicable has no p-values
+        let p_values = match perform_test_on_walk(random_walk)? {
+            customtypes::ExcursionsResult::Applicable(p_values) => p_values
+                .into_iter()
+                .map(|(state, p_value)| (format!("x = {:+}", state), p_value))
+                .collect(),
+            customtypes::ExcursionsResult::NotApplicable { .. } => Vec::new(),
+        };
+
+        Ok(customtypes::TestStatistics::create(
+            vec![cycles_j],
+            p_values,
+        ))
+    }
+}
+
+/// Perform the Random Excursions Variant Test on an already computed random walk.
+///
+/// # Arguments
+///
+/// random_walk - The random walk of the bit string to be tested for randomness
+///
+/// # Return
+///
+/// Ok(ExcursionsResult::Applicable(p-values)) - The p-values per state x = -9..-1, 1..9
+/// Ok(ExcursionsResult::NotApplicable) - The bit string has not enough cycles J
+/// Err(err) - Some error occured
+pub fn perform_test_on_walk(
+    random_walk: &customtypes::RandomWalk,
+) -> Result<customtypes::ExcursionsResult> {
+    log::trace!("random_excursions_variant::perform_test_on_walk()");
+
+    let length = random_walk.partial_sums.len();
+    let cycles_j = random_walk.number_of_cycles();
+    log::debug!("{}: Number of cycles J = {}", TEST_NAME, cycles_j);
+
+    // the test is not applicable if J < max(0.005 * sqrt(n), 500)
+    let min_cycles = random_excursions::compute_min_cycles(length);
+    if cycles_j < min_cycles {
+        log::warn!(
+            "{} is not applicable! Number of cycles J ({}) is less than {}",
+            TEST_NAME,
+            cycles_j,
+            min_cycles
+        );
+        return Ok(customtypes::ExcursionsResult::NotApplicable {
+            cycles: cycles_j,
+            min_cycles,
+        });
+    }
+
+    // count the total number of visits xi(x) to each state x across all cycles
+    let visits = compute_visits(random_walk);
+
+    // compute p-value for each state with the complementary error function:
+    // erfc(|xi(x) - J| / sqrt(2 * J * (4 * |x| - 2)))
+    let j = cycles_j as f64;
+    let mut p_values = Vec::<(i64, f64)>::new();
+    p_values.reserve_exact(constants::RANDOM_EXCURSIONS_VARIANT_STATES.len());
+
+    for (state, &visit) in constants::RANDOM_EXCURSIONS_VARIANT_STATES
+        .iter()
+        .zip(visits.iter())
+    {
+        let denominator = (2.0 * j * (4.0 * (state.abs() as f64) - 2.0)).sqrt();
+        let p_value = statrs::function::erf::erfc(((visit as f64) - j).abs() / denominator);
+        log::debug!(
+            "{}: State x = {:+}: xi(x) = {}, p-value = {}",
+            TEST_NAME,
+            state,
+            visit,
+            p_value
+        );
+
+        if p_value < constants::P_VALUE_THRESHOLD {
+            log::warn!(
+                "{}: p-value ({}) for state x = {:+} is below threshold",
+                TEST_NAME,
+                p_value,
+                state
+            );
+        }
+
+        p_values.push((*state, p_value));
+    }
+    log::info!("{}: p-values = {:?}", TEST_NAME, p_values);
+
+    Ok(customtypes::ExcursionsResult::Applicable(p_values))
+}
+
+/// Count the total number of visits xi(x) to each state x = -9..-1, 1..9.
+///
+/// # Arguments
+///
+/// random_walk - The random walk already split into cycles
+///
+/// # Return
+///
+/// visits - The number of visits per state x
+fn compute_visits(random_walk: &customtypes::RandomWalk) -> [usize; 18] {
+    log::trace!("random_excursions_variant::compute_visits()");
+
+    let mut visits = [0_usize; 18];
+
+    for cycle in random_walk.cycles() {
+        for &partial_sum in cycle {
+            match partial_sum {
+                -9..=-1 => visits[(partial_sum + 9) as usize] += 1,
+                1..=9 => visits[(partial_sum + 8) as usize] += 1,
+                _ => {}
+            }
+        }
+    }
+
+    visits
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bit_sequence::BitSequence;
+    use crate::constants;
+    use crate::cumulative_sums;
+    use crate::customtypes;
+    use crate::logger;
+    use crate::random_excursions;
+    use crate::random_excursions_variant;
+    use crate::utils;
+
+    const LOGLEVEL: &str = "Debug";
+    const BIT_STRING_NIST_1: &str = "0110110101";
+    const CYCLES_NIST_1: usize = 3;
+    const P_VALUE_NIST_E: f64 = 0.826009;
+    const NIST_LENGTH: usize = 1000000;
+    const BIT_STRING_FAST_OSCILLATION: &str = "1010101010101010101010101010101010101010101010101010101010101010101010101010101010101010101010101010";
+
+    #[test]
+    fn test_visits() {
+        logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
+
+        // example from NIST SP 800-22: xi(-1) = 1, xi(1) = 4, xi(2) = 3
+        let random_walk = cumulative_sums::compute_random_walk(&BIT_STRING_NIST_1.parse().unwrap());
+        let visits = random_excursions_variant::compute_visits(&random_walk);
+        assert_eq!(
+            visits,
+            [0, 0, 0, 0, 0, 0, 0, 0, 1, 4, 3, 0, 0, 0, 0, 0, 0, 0]
+        );
+    }
+
+    #[test]
+    fn test_random_excursions_variant() {
+        logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
+
+        // short bit strings do not have enough cycles
+        assert_eq!(
+            random_excursions_variant::perform_test(&BIT_STRING_NIST_1.parse().unwrap()).unwrap(),
+            customtypes::ExcursionsResult::NotApplicable {
+                cycles: CYCLES_NIST_1,
+                min_cycles: constants::MIN_CYCLES_J
+            }
+        );
+        assert!(matches!(
+            random_excursions_variant::perform_test(&BIT_STRING_FAST_OSCILLATION.parse().unwrap())
+                .unwrap(),
+            customtypes::ExcursionsResult::NotApplicable { .. }
+        ));
+
+        // the first 10^6 bits of e must match the result of NIST SP 800-22. The random walk is
+        // computed once and shared with the Random Excursions Test
+        let e_file = std::env::current_dir()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_owned()
+            + constants::E_FILE;
+        let e_bit_string = utils::read_random_numbers(&e_file).unwrap();
+        let random_walk = cumulative_sums::compute_random_walk(&e_bit_string.slice(0..NIST_LENGTH));
+        assert!(matches!(
+            random_excursions::perform_test_on_walk(&random_walk).unwrap(),
+            customtypes::ExcursionsResult::Applicable(_)
+        ));
+        match random_excursions_variant::perform_test_on_walk(&random_walk).unwrap() {
+            customtypes::ExcursionsResult::Applicable(p_values) => {
+                assert_eq!(
+                    p_values.len(),
+                    constants::RANDOM_EXCURSIONS_VARIANT_STATES.len()
+                );
+                let (_, p_value) = p_values.iter().find(|(state, _)| *state == -1).unwrap();
+                assert!((p_value - P_VALUE_NIST_E).abs() < 1e-6);
+            }
+            result => panic!("Unexpected result {:?}", result),
+        }
+
+        // test sqrt(2) and sqrt(3) in their respective binary representations
+        let sqrt_2_file = std::env::current_dir()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_owned()
+            + constants::SQRT_2_FILE;
+        let sqrt_2_bit_string = utils::read_random_numbers(&sqrt_2_file).unwrap();
+        assert!(random_excursions_variant::perform_test(&sqrt_2_bit_string).is_ok());
+
+        let sqrt_3_file = std::env::current_dir()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_owned()
+            + constants::SQRT_3_FILE;
+        let sqrt_3_bit_string = utils::read_random_numbers(&sqrt_3_file).unwrap();
+        assert!(random_excursions_variant::perform_test(&sqrt_3_bit_string).is_ok());
+    }
+
+    #[test]
+    fn test_random_excursions_variant_error_cases() {
+        logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
+
+        // pass empty sequence
+        assert!(random_excursions_variant::perform_test(&BitSequence::new()).is_err());
+    }
+}
@@ -0,0 +1,443 @@
+//! This module writes the results of multiple streams in the layout of the `finalAnalysisReport.txt`
+//! of the NIST reference implementation (sts).
+//!
+//! Each row holds the histogram C1..C10 of the p-values, the uniformity p-value and the proportion
+//! of passing streams of one p-value of a test. Tests with several p-values, like the templates of
+//! the Non-overlapping Template Matching Test or the states of the Random Excursions Tests, have one
+//! row per p-value. Failing uniformities and proportions are marked with an asterisk.
+
+use crate::constants;
+use crate::customtypes;
+use crate::second_level;
+use anyhow::Result;
+
+const SEPARATOR: &str =
+    "------------------------------------------------------------------------------";
+const FOOTER_SEPARATOR: &str =
+    "- - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -";
+
+/// The order of the tests in the report of sts, which differs from the order of NIST SP 800-22
+const STS_ORDER: [customtypes::Test; 15] = [
+    customtypes::Test::FrequencyMonobit,
+    customtypes::Test::FrequencyBlock,
+    customtypes::Test::CumulativeSums,
+    customtypes::Test::Runs,
+    customtypes::Test::LongestRun,
+    customtypes::Test::BinaryMatrixRank,
+    customtypes::Test::DFTSpectral,
+    customtypes::Test::NonOverlappingTemplate,
+    customtypes::Test::OverlappingTemplate,
+    customtypes::Test::MaurersUniversalStatistical,
+    customtypes::Test::ApproximateEntropy,
+    customtypes::Test::RandomExcursions,
+    customtypes::Test::RandomExcursionsVariant,
+    customtypes::Test::Serial,
+    customtypes::Test::LinearComplexity,
+];
+
+/// Write the report of the results of multiple streams.
+///
+/// # Arguments
+///
+/// writer - The destination of the report
+/// generator - The name of the generator, usually the input file
+/// results - The results of all streams per test
+/// alpha - The significance level the streams were tested with
+///
+/// # Return
+///
+/// Ok(()) - The report was written
+/// Err(err) - Some error occured
+pub fn write_final_analysis_report(
+    writer: &mut impl std::io::Write,
+    generator: &str,
+    results: &[customtypes::MultiStreamResult],
+    alpha: f64,
+) -> Result<()> {
+    log::trace!("report::write_final_analysis_report()");
+
+    writeln!(writer, "{}", SEPARATOR)?;
+    writeln!(
+        writer,
+        "RESULTS FOR THE UNIFORMITY OF P-VALUES AND THE PROPORTION OF PASSING SEQUENCES"
+    )?;
+    writeln!(writer, "{}", SEPARATOR)?;
+    writeln!(writer, "   generator is <{}>", generator)?;
+    writeln!(writer, "{}", SEPARATOR)?;
+    writeln!(
+        writer,
+        " C1  C2  C3  C4  C5  C6  C7  C8  C9 C10  P-VALUE  PROPORTION  STATISTICAL TEST"
+    )?;
+    writeln!(writer, "{}", SEPARATOR)?;
+
+    // the rows are sorted like in sts, the order of the results of the same test is kept
+    let mut sorted_results = results.iter().collect::<Vec<_>>();
+    sorted_results.sort_by_key(|result| STS_ORDER.iter().position(|test| *test == result.test));
+
+    let mut excursions_sample_size = None;
+    for result in sorted_results {
+        let p_values = result.p_values();
+
+        if is_excursions_test(result.test) {
+            let sample_size = p_values.first().map_or(0, |(_, values)| values.len());
+            excursions_sample_size = excursions_sample_size.max(Some(sample_size));
+        }
+
+        // a test without any p-value still gets its rows, like the random excursions tests in
+        // sts if no stream has enough cycles
+        if p_values.is_empty() {
+            for _ in 0..number_of_rows(result.test) {
+                writeln!(writer, "{}", format_row(result.test, None))?;
+            }
+            continue;
+        }
+
+        // p-values which cannot be analyzed get an empty row, so the rest of the report is kept
+        for (label, values) in &p_values {
+            let analysis = match second_level::analyze(values, alpha) {
+                Ok(analysis) => Some(analysis),
+                Err(err) => {
+                    log::error!("{}: Failed to analyze {}: {:#}", result.test, label, err);
+                    None
+                }
+            };
+            writeln!(writer, "{}", format_row(result.test, analysis.as_ref()))?;
+        }
+    }
+
+    // minimum pass rates of all tests and of the random excursions tests
+    let number_of_streams = results
+        .first()
+        .map_or(0, |result| result.stream_results.len());
+    writeln!(writer, "\n\n{}", FOOTER_SEPARATOR)?;
+    writeln!(
+        writer,
+        "The minimum pass rate for each statistical test with the exception of the"
+    )?;
+    writeln!(
+        writer,
+        "random excursion (variant) test is approximately = {} for a",
+        compute_min_pass_count(number_of_streams, alpha)
+    )?;
+    writeln!(
+        writer,
+        "sample size = {} binary sequences.\n",
+        number_of_streams
+    )?;
+    match excursions_sample_size {
+        Some(sample_size) if sample_size > 0 => {
+            writeln!(
+                writer,
+                "The minimum pass rate for the random excursion (variant) test"
+            )?;
+            writeln!(
+                writer,
+                "is approximately = {} for a sample size = {} binary sequences.\n",
+                compute_min_pass_count(sample_size, alpha),
+                sample_size
+            )?;
+        }
+        _ => writeln!(
+            writer,
+            "The minimum pass rate for the random excursion (variant) test is undefined.\n"
+        )?,
+    }
+    writeln!(
+        writer,
+        "For further guidelines construct a probability table using the MAPLE program"
+    )?;
+    writeln!(
+        writer,
+        "provided in the addendum section of the documentation."
+    )?;
+    writeln!(writer, "{}", FOOTER_SEPARATOR)?;
+
+    Ok(())
+}
+
+/// Format a single row of the report.
+///
+/// # Arguments
+///
+/// test - The test the row belongs to
+/// analysis - The second-level analysis of the p-values or None if there are no p-values or they
+/// could not be analyzed
+///
+/// # Return
+///
+/// row - The histogram, uniformity p-value, proportion and name of the test
+fn format_row(
+    test: customtypes::Test,
+    analysis: Option<&customtypes::SecondLevelResult>,
+) -> String {
+    let analysis = match analysis {
+        Some(analysis) => analysis,
+        None => {
+            return format!(
+                "{}    ----     ------     {}",
+                format_histogram(&[0; constants::UNIFORMITY_BINS]),
+                test.sts_name()
+            )
+        }
+    };
+
+    // like sts, the bounds of the pass count are truncated to integers, so e.g. 96/100 passes
+    let sample_size = analysis.sample_size as f64;
+    let min_passed = (analysis.confidence_interval.0 * sample_size) as usize;
+    let max_passed = (analysis.confidence_interval.1 * sample_size) as usize;
+    let proportion_flag = if analysis.passed < min_passed || analysis.passed > max_passed {
+        '*'
+    } else {
+        ' '
+    };
+    // sts expects at least one p-value per bin and omits the uniformity otherwise
+    let uniformity = if analysis.sample_size < constants::UNIFORMITY_BINS {
+        "    ----    ".to_owned()
+    } else if analysis.uniformity_passed() {
+        format!(" {:8.6}   ", analysis.uniformity_p_value)
+    } else {
+        format!(" {:8.6} * ", analysis.uniformity_p_value)
+    };
+
+    format!(
+        "{}{}{:4}/{:<4} {}  {}",
+        format_histogram(&analysis.histogram),
+        uniformity,
+        analysis.passed,
+        analysis.sample_size,
+        proportion_flag,
+        test.sts_name()
+    )
+}
+
+/// Format the histogram C1..C10 of the p-values.
+///
+/// # Arguments
+///
+/// histogram - The number of p-values per bin
+///
+/// # Return
+///
+/// columns - The right aligned counts, each followed by a space
+fn format_histogram(histogram: &[usize; constants::UNIFORMITY_BINS]) -> String {
+    histogram
+        .iter()
+        .map(|count| format!("{:3} ", count))
+        .collect()
+}
+
+/// Compute the minimum number of passing sequences, which sts reports below the table.
+///
+/// # Arguments
+///
+/// sample_size - The number of sequences
+/// alpha - The significance level
+///
+/// # Return
+///
+/// min_pass_count - The lower bound of the confidence interval times the sample size, truncated
+fn compute_min_pass_count(sample_size: usize, alpha: f64) -> usize {
+    let (lower, _) = second_level::compute_confidence_interval(sample_size, alpha);
+
+    (lower * (sample_size as f64)) as usize
+}
+
+/// Check if the test is one of the random excursions tests, whose sample size is the number of
+/// sequences with enough cycles.
+///
+/// # Arguments
+///
+/// test - The test to be checked
+///
+/// # Return
+///
+/// true - The test is a random excursions test
+/// false - Otherwise
+fn is_excursions_test(test: customtypes::Test) -> bool {
+    matches!(
+        test,
+        customtypes::Test::RandomExcursions | customtypes::Test::RandomExcursionsVariant
+    )
+}
+
+/// The number of rows of a test if none of its p-values is known.
+///
+/// # Arguments
+///
+/// test - The test
+///
+/// # Return
+///
+/// rows - The number of states for the random excursions tests, otherwise 1
+fn number_of_rows(test: customtypes::Test) -> usize {
+    match test {
+        customtypes::Test::RandomExcursions => constants::RANDOM_EXCURSIONS_STATES.len(),
+        customtypes::Test::RandomExcursionsVariant => {
+            constants::RANDOM_EXCURSIONS_VARIANT_STATES.len()
+        }
+        _ => 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::constants;
+    use crate::customtypes;
+    use crate::logger;
+    use crate::report;
+
+    const LOGLEVEL: &str = "Debug";
+    const GENERATOR: &str = "data/data.e";
+    const HISTOGRAM: [usize; 10] = [12, 8, 11, 9, 10, 10, 13, 7, 10, 10];
+    const ROW_FREQUENCY: &str =
+        " 12   8  11   9  10  10  13   7  10  10  0.971699     99/100     Frequency";
+    const ROW_RUNS: &str = "  4   0   0   0   0   0   0   0   0  96  0.000000 *   96/100     Runs";
+    const ROW_CUSUM: &str =
+        "  5   0   0   0   0   0   0   0   0  95  0.000000 *   95/100  *  CumulativeSums";
+    const ROW_SMALL_SAMPLE: &str =
+        "  1   0   0   0   0   1   0   0   0   0     ----       1/2       Frequency";
+    const ROW_EXCURSIONS: &str =
+        "  0   0   0   0   0   0   0   0   0   0     ----     ------     RandomExcursions";
+    const ROW_INVALID: &str =
+        "  0   0   0   0   0   0   0   0   0   0     ----     ------     Frequency";
+
+    /// Create the results of a test with a single p-value per stream
+    fn create_result(
+        test: customtypes::Test,
+        p_values: &[Option<f64>],
+    ) -> customtypes::MultiStreamResult {
+        let stream_results = p_values
+            .iter()
+            .map(|p_value| {
+                let p_values = p_value
+                    .iter()
+                    .map(|p_value| ("p-value".to_owned(), *p_value))
+                    .collect();
+                Ok(customtypes::TestResult::create(
+                    test,
+                    Vec::new(),
+                    customtypes::TestStatistics::create(Vec::new(), p_values),
+                    0.0,
+                    constants::P_VALUE_THRESHOLD,
+                ))
+            })
+            .collect();
+
+        customtypes::MultiStreamResult::create(test, Vec::new(), stream_results)
+    }
+
+    /// Create p-values distributed over the bins according to the histogram
+    fn p_values_from_histogram(histogram: &[usize]) -> Vec<Option<f64>> {
+        histogram
+            .iter()
+            .enumerate()
+            .flat_map(|(bin, &count)| {
+                (0..count)
+                    .map(move |i| Some((bin as f64 + (i as f64 + 0.5) / (count as f64)) / 10.0))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_final_analysis_report() {
+        logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
+
+        // 96/100 passes like in sts, 95/100 is below the minimum pass rate
+        let mut runs = vec![Some(0.0); 4];
+        runs.extend(vec![Some(0.95); 96]);
+        let mut cusum = vec![Some(0.0); 5];
+        cusum.extend(vec![Some(0.95); 95]);
+
+        let results = [
+            create_result(
+                customtypes::Test::FrequencyMonobit,
+                &p_values_from_histogram(&HISTOGRAM),
+            ),
+            create_result(customtypes::Test::Runs, &runs),
+            create_result(customtypes::Test::RandomExcursions, &[None; 100]),
+            create_result(customtypes::Test::CumulativeSums, &cusum),
+        ];
+
+        let mut report = Vec::new();
+        report::write_final_analysis_report(
+            &mut report,
+            GENERATOR,
+            &results,
+            constants::P_VALUE_THRESHOLD,
+        )
+        .unwrap();
+        let report = String::from_utf8(report).unwrap();
+        let lines = report.lines().collect::<Vec<_>>();
+
+        assert_eq!(lines[3], "   generator is <data/data.e>");
+        assert_eq!(
+            lines[5],
+            " C1  C2  C3  C4  C5  C6  C7  C8  C9 C10  P-VALUE  PROPORTION  STATISTICAL TEST"
+        );
+
+        // the rows are in the order of sts and the random excursions test has one row per state
+        assert_eq!(&lines[7..10], [ROW_FREQUENCY, ROW_CUSUM, ROW_RUNS]);
+        assert_eq!(
+            &lines[10..18],
+            [ROW_EXCURSIONS; constants::RANDOM_EXCURSIONS_STATES.len()]
+        );
+        assert!(lines[18].is_empty());
+
+        assert!(report.contains("approximately = 96 for a\nsample size = 100 binary sequences."));
+        assert!(report.contains("random excursion (variant) test is undefined."));
+    }
+
+    #[test]
+    fn test_small_sample() {
+        logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
+
+        let results = [create_result(
+            customtypes::Test::FrequencyMonobit,
+            &[Some(0.5), Some(0.005)],
+        )];
+        let mut report = Vec::new();
+        report::write_final_analysis_report(
+            &mut report,
+            GENERATOR,
+            &results,
+            constants::P_VALUE_THRESHOLD,
+        )
+        .unwrap();
+        let report = String::from_utf8(report).unwrap();
+        assert_eq!(report.lines().nth(7).unwrap(), ROW_SMALL_SAMPLE);
+    }
+
+    #[test]
+    fn test_invalid_p_values() {
+        logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
+
+        // p-values which cannot be analyzed get an empty row instead of aborting the report
+        let results = [
+            create_result(customtypes::Test::FrequencyMonobit, &[Some(f64::NAN); 10]),
+            create_result(
+                customtypes::Test::FrequencyBlock,
+                &p_values_from_histogram(&HISTOGRAM),
+            ),
+        ];
+        let mut report = Vec::new();
+        report::write_final_analysis_report(
+            &mut report,
+            GENERATOR,
+            &results,
+            constants::P_VALUE_THRESHOLD,
+        )
+        .unwrap();
+        let report = String::from_utf8(report).unwrap();
+        let lines = report.lines().collect::<Vec<_>>();
+        assert_eq!(lines[7], ROW_INVALID);
+        assert!(lines[8].ends_with("99/100     BlockFrequency"));
+    }
+
+    #[test]
+    fn test_min_pass_count() {
+        logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
+
+        assert_eq!(report::compute_min_pass_count(100, 0.01), 96);
+        assert_eq!(report::compute_min_pass_count(1000, 0.01), 980);
+    }
+}
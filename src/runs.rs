@@ -1,5 +1,5 @@
-//! This module performs the Runs test. If the Frequency test within a block test is not passed,
-//! this test will NOT be executed!
+//! This module performs the Runs test. The test is only performed if the proportion pi of ones
+//! passes the pre-test |pi - 1/2| < 2 / sqrt(n). The suite runner skips the test otherwise.
 //!
 //! Description of test from NIST SP 800-22:
 //!
@@ -9,8 +9,10 @@
 //! ones and zeros of various lengths is as expected for a random sequence. In particular, this test determines
 //! whether the oscillation between such zeros and ones is too fast or too slow."
 
+use crate::bit_sequence::BitSequence;
 use crate::constants;
 use crate::customtypes;
+use crate::customtypes::StatisticalTest;
 use crate::utils;
 use anyhow::{bail, Context, Result};
 
@@ -20,51 +22,86 @@ const TEST_NAME: customtypes::Test = customtypes::Test::Runs;
 ///
 /// # Arguments
 ///
-/// bit_string -  The bit string to be tested for randomness
+/// bit_sequence -  The bit string to be tested for randomness
 ///
 /// # Return
 ///
 /// Ok(p-value) - The p-value which indicates whether randomness is given or not
 /// Err(err) - Some error occured
-pub fn perform_test(bit_string: &str) -> Result<f64> {
+pub fn perform_test(bit_sequence: &BitSequence) -> Result<f64> {
     log::trace!("runs::perform_test()");
 
-    // capture the current time before executing the actual test
-    let start_time = std::time::Instant::now();
+    let result = Runs.run(bit_sequence, constants::P_VALUE_THRESHOLD)?;
 
-    // check if bit string contains invalid characters
-    let length = utils::evaluate_bit_string(TEST_NAME, bit_string, constants::RECOMMENDED_SIZE)
-        .with_context(|| "Invalid character(s) in passed bit string detected")?
-        as f64;
+    Ok(result.p_values[0].1)
+}
+
+/// The "Runs" test. The test has no parameters
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Runs;
 
-    // determine the number of ones in given bit string and compute pre-test proportion = #ones/length
-    let pre_test_proportion = compute_pre_test_proportion(bit_string, length);
+impl customtypes::StatisticalTest for Runs {
+    fn test(&self) -> customtypes::Test {
+        TEST_NAME
+    }
+
+    fn params(&self) -> Vec<(String, String)> {
+        Vec::new()
+    }
 
-    // check whether test can be performed if requirement 2 / sqrt(length) is not satisfied
-    evaluate_requirement(length, pre_test_proportion)?;
+    fn compute(&self, bit_sequence: &BitSequence) -> Result<customtypes::TestStatistics> {
+        log::trace!("runs::compute()");
 
-    // compute observed runs test statistics V_n(obs). Therefore compare current bit with
-    // consecutive one. If not equal, add 1 to counter, otherwise do nothing
-    let v_n_observed = compute_v_n_observed(bit_string);
+        // check if bit sequence is valid
+        let length =
+            utils::evaluate_bit_sequence(TEST_NAME, bit_sequence, constants::RECOMMENDED_SIZE)
+                .with_context(|| "Invalid bit sequence passed")? as f64;
 
-    // finally, compute p-value with complementary error function
-    let fraction = compute_fraction(pre_test_proportion, v_n_observed, length);
+        // determine the number of ones in given bit string and compute pre-test proportion = #ones/length
+        let pre_test_proportion = compute_pre_test_proportion(bit_sequence, length);
 
-    let p_value = statrs::function::erf::erfc(fraction);
-    log::info!("{TEST_NAME}: p-value = {p_value}");
+        // check whether test can be performed if requirement 2 / sqrt(length) is not satisfied
+        evaluate_requirement(length, pre_test_proportion)?;
 
-    // capture the current time after the test got executed and calculate elapsed time
-    let end_time = std::time::Instant::now();
-    let elapsed_time = end_time.duration_since(start_time).as_secs_f64();
-    log::info!("{TEST_NAME} took {:.6} seconds", elapsed_time);
+        // compute observed runs test statistics V_n(obs). Therefore compare current bit with
+        // consecutive one. If not equal, add 1 to counter, otherwise do nothing
+        let v_n_observed = compute_v_n_observed(bit_sequence);
+
+        // finally, compute p-value with complementary error function
+        let fraction = compute_fraction(pre_test_proportion, v_n_observed, length);
+
+        let p_value = statrs::function::erf::erfc(fraction);
+        log::info!("{TEST_NAME}: p-value = {p_value}");
+
+        Ok(customtypes::TestStatistics::create(
+            vec![
+                ("pi".to_owned(), pre_test_proportion),
+                ("V_n(obs)".to_owned(), v_n_observed as f64),
+            ],
+            vec![("p-value".to_owned(), p_value)],
+        ))
+    }
 
-    Ok(p_value)
+    fn pre_test(&self, bit_sequence: &BitSequence) -> Option<String> {
+        log::trace!("runs::pre_test()");
+
+        // sequences which are too short are rejected by the test itself
+        if bit_sequence.is_empty() {
+            return None;
+        }
+
+        let length = bit_sequence.len() as f64;
+        let pre_test_proportion = compute_pre_test_proportion(bit_sequence, length);
+        evaluate_requirement(length, pre_test_proportion)
+            .err()
+            .map(|err| err.to_string())
+    }
 }
 
-fn compute_pre_test_proportion(bit_string: &str, length: f64) -> f64 {
+fn compute_pre_test_proportion(bit_sequence: &BitSequence, length: f64) -> f64 {
     log::trace!("runs::compute_pre_test_proportion()");
 
-    let count_ones = bit_string.chars().filter(|&c| c == '1').count() as f64;
+    let count_ones = bit_sequence.count_ones() as f64;
 
     let pre_test_proportion = count_ones / length;
     log::debug!(
@@ -90,17 +127,21 @@ fn evaluate_requirement(length: f64, pre_test_proportion: f64) -> Result<()> {
     Ok(())
 }
 
-fn compute_v_n_observed(bit_string: &str) -> u64 {
+fn compute_v_n_observed(bit_sequence: &BitSequence) -> u64 {
     log::trace!("runs::compute_v_n_observed()");
 
-    let mut v_n_observed = 1;
-    let bytes = bit_string.as_bytes();
-
-    for bit in 0..bytes.len() - 1 {
-        if bytes[bit] != bytes[bit + 1] {
-            v_n_observed += 1;
-        }
-    }
+    // a run ends wherever a bit differs from its successor. Compare up to 64 bits at once with
+    // the same bits shifted by one position
+    let transitions = bit_sequence.len() - 1;
+    let v_n_observed = 1
+        + (0..transitions)
+            .step_by(64)
+            .map(|start| {
+                let len = 64.min(transitions - start);
+                (bit_sequence.bits(start, len) ^ bit_sequence.bits(start + 1, len)).count_ones()
+                    as u64
+            })
+            .sum::<u64>();
     log::debug!("{TEST_NAME}: v_n_observed value: {v_n_observed}");
 
     v_n_observed
@@ -120,6 +161,7 @@ fn compute_fraction(pre_test_proportion: f64, v_n_observed: u64, length: f64) ->
 
 #[cfg(test)]
 mod tests {
+    use crate::bit_sequence::BitSequence;
     use crate::constants;
     use crate::logger;
     use crate::runs;
@@ -135,22 +177,21 @@ mod tests {
     const BIT_STRING_FAST_OSCILLATION: &str = "1010101010101010101010101010101010101010101010101010101010101010101010101010101010101010101010101010";
     const BIT_STRING_SLOW_OSCILLATION: &str = "1011111111111111111111110111111111111111111111111101111111111111111111111111101111111111111111111110";
     const BIT_STRING_PERFECT: &str = "1100110011001100110011001100110011001100110011001100110011001100110011001100110011001100110011001100";
-    const INVALID_BIT_STRING: &str = "010101111010101010101010101010a0101010101010100101010101";
 
     #[test]
     fn test_runs() {
         logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
 
         assert_eq!(
-            runs::perform_test(BIT_STRING_NIST_1).unwrap(),
+            runs::perform_test(&BIT_STRING_NIST_1.parse().unwrap()).unwrap(),
             P_VALUE_NIST_1
         );
         assert_eq!(
-            runs::perform_test(BIT_STRING_NIST_2).unwrap(),
+            runs::perform_test(&BIT_STRING_NIST_2.parse().unwrap()).unwrap(),
             P_VALUE_NIST_2
         );
-        assert!(runs::perform_test(BIT_STRING_FAST_OSCILLATION).unwrap() <= 0.01);
-        assert!(runs::perform_test(BIT_STRING_PERFECT).unwrap() == 1.00);
+        assert!(runs::perform_test(&BIT_STRING_FAST_OSCILLATION.parse().unwrap()).unwrap() <= 0.01);
+        assert!(runs::perform_test(&BIT_STRING_PERFECT.parse().unwrap()).unwrap() == 1.00);
 
         // test pi, e, sqrt(2) and sqrt(3) in their respective binary representations
         let pi_file = std::env::current_dir()
@@ -203,15 +244,12 @@ mod tests {
     fn test_runs_error_cases() {
         logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
 
-        // pass empty string
-        assert!(runs::perform_test("").is_err());
-
-        // pass invalid bit string
-        assert!(runs::perform_test(INVALID_BIT_STRING).is_err());
+        // pass empty sequence
+        assert!(runs::perform_test(&BitSequence::new()).is_err());
 
         // pass bit strings which are not applicable with test
-        assert!(runs::perform_test(BIT_STRING_ONLY_ZEROS).is_err());
-        assert!(runs::perform_test(BIT_STRING_ONLY_ONES).is_err());
-        assert!(runs::perform_test(BIT_STRING_SLOW_OSCILLATION).is_err());
+        assert!(runs::perform_test(&BIT_STRING_ONLY_ZEROS.parse().unwrap()).is_err());
+        assert!(runs::perform_test(&BIT_STRING_ONLY_ONES.parse().unwrap()).is_err());
+        assert!(runs::perform_test(&BIT_STRING_SLOW_OSCILLATION.parse().unwrap()).is_err());
     }
 }
@@ -0,0 +1,242 @@
+//! This module performs the second-level analysis of the p-values of multiple sequences.
+//!
+//! Description of the analysis from NIST SP 800-22 section 4.2:
+//!
+//! "For each statistical test, a set of P-values (corresponding to the set of sequences) is
+//! produced. For a fixed significance level, a certain percentage of P-values are expected to
+//! indicate failure. [...] The distribution of P-values is examined to ensure uniformity."
+//!
+//! The proportion of passing sequences has to lie within the confidence interval
+//! p ± 3 * sqrt(p * (1 - p) / m) with p = 1 - alpha and m sequences. The uniformity is checked
+//! with a chi_square test over ten bins of the p-values.
+
+use crate::constants;
+use crate::customtypes;
+use anyhow::Result;
+
+/// Analyze the p-values which a test produced for multiple sequences.
+///
+/// # Arguments
+///
+/// p_values - The p-values of the sequences, one per sequence
+/// alpha - The significance level the sequences were tested with
+///
+/// # Return
+///
+/// Ok(result) - The proportion of passing sequences and the uniformity of the p-values
+/// Err(err) - Some error occured
+pub fn analyze(p_values: &[f64], alpha: f64) -> Result<customtypes::SecondLevelResult> {
+    log::trace!("second_level::analyze()");
+
+    if p_values.is_empty() {
+        anyhow::bail!("No p-values to analyze passed");
+    }
+    if !(alpha > 0.0 && alpha < 1.0) {
+        anyhow::bail!(
+            "Significance level alpha ({}) must be between 0 and 1",
+            alpha
+        );
+    }
+    if let Some(p_value) = p_values
+        .iter()
+        .find(|p_value| !(0.0..=1.0).contains(*p_value))
+    {
+        anyhow::bail!("Invalid p-value {} passed", p_value);
+    }
+
+    let sample_size = p_values.len();
+    if sample_size < constants::MIN_SEQUENCES_UNIFORMITY {
+        log::warn!(
+            "Uniformity of p-values should be computed from at least {} sequences, passed: {}",
+            constants::MIN_SEQUENCES_UNIFORMITY,
+            sample_size
+        );
+    }
+
+    // count the passing sequences and compute the confidence interval of the proportion
+    let passed = p_values.iter().filter(|&&p_value| p_value >= alpha).count();
+    let confidence_interval = compute_confidence_interval(sample_size, alpha);
+
+    // sort the p-values into ten bins and compute the uniformity p-value
+    let histogram = compute_histogram(p_values);
+    let uniformity_p_value = compute_uniformity_p_value(&histogram);
+
+    let result = customtypes::SecondLevelResult::create(
+        passed,
+        confidence_interval,
+        histogram,
+        uniformity_p_value,
+    );
+    log::info!(
+        "Proportion {}/{} = {} (interval {:?}), uniformity p-value = {}",
+        result.passed,
+        result.sample_size,
+        result.proportion,
+        result.confidence_interval,
+        result.uniformity_p_value
+    );
+
+    Ok(result)
+}
+
+/// Compute the confidence interval of the proportion of passing sequences.
+///
+/// # Arguments
+///
+/// sample_size - The number of sequences m
+/// alpha - The significance level
+///
+/// # Return
+///
+/// (lower, upper) - p ± 3 * sqrt(p * (1 - p) / m) with p = 1 - alpha
+pub fn compute_confidence_interval(sample_size: usize, alpha: f64) -> (f64, f64) {
+    log::trace!("second_level::compute_confidence_interval()");
+
+    let p_hat = 1.0 - alpha;
+    let deviation = 3.0 * (p_hat * (1.0 - p_hat) / (sample_size as f64)).sqrt();
+
+    (p_hat - deviation, p_hat + deviation)
+}
+
+/// Count the p-values in the ten bins [0.0, 0.1), ..., [0.8, 0.9), [0.9, 1.0].
+///
+/// # Arguments
+///
+/// p_values - The p-values to be counted
+///
+/// # Return
+///
+/// histogram - The number of p-values per bin
+fn compute_histogram(p_values: &[f64]) -> [usize; constants::UNIFORMITY_BINS] {
+    log::trace!("second_level::compute_histogram()");
+
+    let mut histogram = [0_usize; constants::UNIFORMITY_BINS];
+    for p_value in p_values {
+        // a p-value of exactly 1 belongs to the last bin
+        let bin = ((p_value * constants::UNIFORMITY_BINS as f64) as usize)
+            .min(constants::UNIFORMITY_BINS - 1);
+        histogram[bin] += 1;
+    }
+    log::debug!("Histogram of p-values: {:?}", histogram);
+
+    histogram
+}
+
+/// Compute the uniformity p-value of the histogram of p-values.
+///
+/// # Arguments
+///
+/// histogram - The number of p-values per bin
+///
+/// # Return
+///
+/// p-value - igamc(9/2, chi_square/2) with chi_square = sum((F_i - s/10)^2 / (s/10))
+fn compute_uniformity_p_value(histogram: &[usize; constants::UNIFORMITY_BINS]) -> f64 {
+    log::trace!("second_level::compute_uniformity_p_value()");
+
+    let expected = (histogram.iter().sum::<usize>() as f64) / (constants::UNIFORMITY_BINS as f64);
+    let chi_square = histogram
+        .iter()
+        .map(|&count| ((count as f64) - expected).powi(2) / expected)
+        .sum::<f64>();
+    log::debug!("Chi_square value of p-value distribution: {}", chi_square);
+
+    // Note: If chi_square is zero, which is an invalid input for igamc, return p-value of 1
+    if chi_square == 0.0 {
+        1.0
+    } else {
+        statrs::function::gamma::gamma_ur(
+            ((constants::UNIFORMITY_BINS - 1) as f64) * 0.5,
+            chi_square * 0.5,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::constants;
+    use crate::logger;
+    use crate::second_level;
+
+    const LOGLEVEL: &str = "Debug";
+    const INTERVAL_NIST_1000: (f64, f64) = (0.9805607, 0.9994392);
+    const HISTOGRAM_1: [usize; 10] = [12, 8, 11, 9, 10, 10, 13, 7, 10, 10];
+    const UNIFORMITY_P_VALUE_1: f64 = 0.971699;
+    const HISTOGRAM_2: [usize; 10] = [20, 5, 11, 9, 10, 10, 13, 7, 10, 5];
+    const UNIFORMITY_P_VALUE_2: f64 = 0.048716;
+
+    /// Create p-values distributed over the bins according to the histogram
+    fn p_values_from_histogram(histogram: &[usize]) -> Vec<f64> {
+        histogram
+            .iter()
+            .enumerate()
+            .flat_map(|(bin, &count)| {
+                (0..count).map(move |i| (bin as f64 + (i as f64 + 0.5) / (count as f64)) / 10.0)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_confidence_interval() {
+        logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
+
+        // example from NIST SP 800-22 section 4.2.1: 0.99 ± 0.0094392 for m = 1000
+        let (lower, upper) = second_level::compute_confidence_interval(1000, 0.01);
+        assert!((lower - INTERVAL_NIST_1000.0).abs() < 1e-6);
+        assert!((upper - INTERVAL_NIST_1000.1).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_histogram() {
+        logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
+
+        assert_eq!(
+            second_level::compute_histogram(&p_values_from_histogram(&HISTOGRAM_1)),
+            HISTOGRAM_1
+        );
+        assert_eq!(
+            second_level::compute_histogram(&[0.0, 0.1, 0.0999, 0.95, 1.0]),
+            [2, 1, 0, 0, 0, 0, 0, 0, 0, 2]
+        );
+    }
+
+    #[test]
+    fn test_analyze() {
+        logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
+
+        // nearly uniform p-values
+        let result = second_level::analyze(&p_values_from_histogram(&HISTOGRAM_1), 0.01).unwrap();
+        assert_eq!(result.sample_size, 100);
+        assert_eq!(result.passed, 99);
+        assert_eq!(result.proportion, 0.99);
+        assert!((result.uniformity_p_value - UNIFORMITY_P_VALUE_1).abs() < 1e-6);
+        assert!(result.proportion_passed());
+        assert!(result.uniformity_passed());
+        assert!(result.passed());
+
+        // too many p-values below alpha = 0.1: the proportion 80/100 is below the interval
+        // 0.9 ± 0.09, but the uniformity still holds
+        let result = second_level::analyze(&p_values_from_histogram(&HISTOGRAM_2), 0.1).unwrap();
+        assert_eq!(result.passed, 80);
+        assert!(!result.proportion_passed());
+        assert!((result.uniformity_p_value - UNIFORMITY_P_VALUE_2).abs() < 1e-6);
+        assert!(result.uniformity_passed());
+        assert!(!result.passed());
+
+        // all p-values in the same bin are not uniform
+        let result = second_level::analyze(&[0.5; 100], 0.01).unwrap();
+        assert!(result.proportion_passed());
+        assert!(result.uniformity_p_value < constants::UNIFORMITY_THRESHOLD);
+        assert!(!result.uniformity_passed());
+    }
+
+    #[test]
+    fn test_analyze_error_cases() {
+        logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
+
+        assert!(second_level::analyze(&[], 0.01).is_err());
+        assert!(second_level::analyze(&[0.5, 0.2], 0.0).is_err());
+        assert!(second_level::analyze(&[0.5, 1.2], 0.01).is_err());
+        assert!(second_level::analyze(&[0.5, f64::NAN], 0.01).is_err());
+    }
+}
@@ -0,0 +1,342 @@
+//! This module performs the Serial Test.
+//!
+//! Description of test from NIST SP 800-22:
+//!
+//! "The focus of this test is the frequency of all possible overlapping m-bit patterns across the entire
+//! sequence. The purpose of this test is to determine whether the number of occurrences of the 2^m m-bit
+//! overlapping patterns is approximately the same as would be expected for a random sequence. Random
+//! sequences have uniformity; that is, every m-bit pattern has the same chance of appearing as every other
+//! m-bit pattern. Note that for m = 1, the Serial test is equivalent to the Frequency test of Section 2.1."
+
+use crate::bit_sequence::BitSequence;
+use crate::constants;
+use crate::customtypes;
+use crate::customtypes::StatisticalTest;
+use crate::utils;
+use anyhow::{Context, Result};
+
+const TEST_NAME: customtypes::Test = customtypes::Test::Serial;
+
+/// Perform the Serial Test by determining both p-values.
+///
+/// # Arguments
+///
+/// bit_sequence - The bit sequence to be tested for randomness
+/// block_len_m - The length m of the overlapping patterns
+///
+/// # Return
+///
+/// Ok((p-value1, p-value2)) - The p-values which indicate whether randomness is given or not
+/// Err(err) - Some error occured
+pub fn perform_test(bit_sequence: &BitSequence, block_len_m: usize) -> Result<(f64, f64)> {
+    log::trace!("serial::perform_test()");
+
+    let result = Serial::create(block_len_m).run(bit_sequence, constants::P_VALUE_THRESHOLD)?;
+
+    Ok((result.p_values[0].1, result.p_values[1].1))
+}
+
+/// Parameters of the "Serial" test
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Serial {
+    pub block_len_m: usize,
+}
+
+impl Serial {
+    pub fn create(block_len_m: usize) -> Self {
+        Serial { block_len_m }
+    }
+}
+
+impl customtypes::StatisticalTest for Serial {
+    fn test(&self) -> customtypes::Test {
+        TEST_NAME
+    }
+
+    fn params(&self) -> Vec<(String, String)> {
+        vec![("block_len_m".to_owned(), self.block_len_m.to_string())]
+    }
+
+    fn compute(&self, bit_sequence: &BitSequence) -> Result<customtypes::TestStatistics> {
+        log::trace!("serial::compute()");
+
+        let block_len_m = self.block_len_m;
+
+        // check if bit sequence is valid
+        let length =
+            utils::evaluate_bit_sequence(TEST_NAME, bit_sequence, constants::RECOMMENDED_SIZE)
+                .with_context(|| "Invalid bit sequence passed")?;
+
+        // check pattern length m for validity
+        evaluate_block_len(length, block_len_m)
+            .with_context(|| "Pattern length m does not match defined requirements")?;
+
+        // compute psi_square statistics for the pattern lengths m, m - 1 and m - 2
+        let psi_square_m = compute_psi_square(bit_sequence, block_len_m);
+        let psi_square_m_1 = compute_psi_square(bit_sequence, block_len_m - 1);
+        let psi_square_m_2 = compute_psi_square(bit_sequence, block_len_m - 2);
+
+        // compute the first and second differences
+        // del_psi_square = psi_square_m - psi_square_(m-1)
+        // del2_psi_square = psi_square_m - 2 * psi_square_(m-1) + psi_square_(m-2)
+        let del_psi_square = psi_square_m - psi_square_m_1;
+        let del2_psi_square = psi_square_m - 2.0 * psi_square_m_1 + psi_square_m_2;
+        log::debug!(
+            "{}: del_psi_square = {}, del2_psi_square = {}",
+            TEST_NAME,
+            del_psi_square,
+            del2_psi_square
+        );
+
+        // finally, compute both p-values with the incomplete gamma function:
+        // p-value1 = igamc(2^(m-2), del_psi_square / 2)
+        // p-value2 = igamc(2^(m-3), del2_psi_square / 2)
+        let p_value_1 = compute_p_value(2.0_f64.powi(block_len_m as i32 - 2), del_psi_square);
+        let p_value_2 = compute_p_value(2.0_f64.powi(block_len_m as i32 - 3), del2_psi_square);
+        log::info!(
+            "{}: p-value1 = {}, p-value2 = {}",
+            TEST_NAME,
+            p_value_1,
+            p_value_2
+        );
+
+        Ok(customtypes::TestStatistics::create(
+            vec![
+                ("del_psi_square".to_owned(), del_psi_square),
+                ("del2_psi_square".to_owned(), del2_psi_square),
+            ],
+            vec![
+                ("p-value1".to_owned(), p_value_1),
+                ("p-value2".to_owned(), p_value_2),
+            ],
+        ))
+    }
+}
+
+/// Evaluate passed pattern length m.
+///
+/// # Arguments
+///
+/// bit_string_length - Length of bit string
+/// block_len_m - The length m of the overlapping patterns
+///
+/// # Return
+///
+/// Ok() - Pattern length is valid
+/// Err(err) - Some error occured
+fn evaluate_block_len(bit_string_length: usize, block_len_m: usize) -> Result<()> {
+    log::trace!("serial::evaluate_block_len()");
+
+    // the test needs the patterns of length m - 2, and more than n patterns make no sense
+    let log2_length = bit_string_length.ilog2() as usize;
+    if block_len_m < 2 || block_len_m > log2_length {
+        anyhow::bail!(
+            "{}: Pattern length m ({}) must be between 2 and {}",
+            TEST_NAME,
+            block_len_m,
+            log2_length
+        );
+    }
+
+    // NIST recommends m < floor(log2(n)) - 2. Log a warning if this does not hold
+    if block_len_m + 2 >= log2_length {
+        log::warn!(
+            "{}: Recommended pattern length m is less than {}, passed: {}",
+            TEST_NAME,
+            log2_length.saturating_sub(2),
+            block_len_m
+        );
+    }
+
+    log::info!("{}: Pattern length m = {}", TEST_NAME, block_len_m);
+
+    Ok(())
+}
+
+/// Compute the psi_square statistic for all overlapping patterns of the given length. The bit
+/// string is augmented by appending its first m - 1 bits to the end.
+///
+/// # Arguments
+///
+/// bit_sequence - The bit sequence to count the patterns in
+/// pattern_len - The length of the patterns
+///
+/// # Return
+///
+/// psi_square - The statistic 2^m / n * sum(v_i^2) - n
+fn compute_psi_square(bit_sequence: &BitSequence, pattern_len: usize) -> f64 {
+    log::trace!("serial::compute_psi_square()");
+
+    // by definition psi_square is zero for patterns of length 0
+    if pattern_len == 0 {
+        return 0.0;
+    }
+
+    let length = bit_sequence.len() as f64;
+    let counts = utils::count_overlapping_patterns(bit_sequence, pattern_len);
+
+    let sum = counts
+        .iter()
+        .map(|&count| (count as f64).powi(2))
+        .sum::<f64>();
+    let psi_square = (counts.len() as f64) / length * sum - length;
+    log::debug!(
+        "{}: psi_square for pattern length {}: {}",
+        TEST_NAME,
+        pattern_len,
+        psi_square
+    );
+
+    psi_square
+}
+
+/// Compute a p-value with the incomplete gamma function.
+///
+/// # Arguments
+///
+/// shape - The first argument of igamc
+/// del_psi_square - The difference of psi_square statistics
+///
+/// # Return
+///
+/// p_value - igamc(shape, del_psi_square / 2)
+fn compute_p_value(shape: f64, del_psi_square: f64) -> f64 {
+    // a difference of zero (perfect distribution) is an invalid input for igamc. Return p-value
+    // of 1 then
+    if del_psi_square <= 0.0 {
+        1.0
+    } else {
+        statrs::function::gamma::gamma_ur(shape, del_psi_square * 0.5)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bit_sequence::BitSequence;
+    use crate::constants;
+    use crate::logger;
+    use crate::serial;
+    use crate::utils;
+
+    const LOGLEVEL: &str = "Debug";
+    const BIT_STRING_NIST_1: &str = "0011011101";
+    const PSI_SQUARES_NIST_1: (f64, f64, f64) = (2.8, 1.2, 0.4);
+    const P_VALUES_NIST_1: (f64, f64) = (0.808792, 0.670320);
+    const P_VALUES_NIST_E_2: (f64, f64) = (0.843764, 0.561915);
+    const P_VALUES_NIST_E_16: (f64, f64) = (0.766182, 0.462921);
+    const NIST_LENGTH: usize = 1000000;
+    const BIT_STRING_SAME_PATTERN: &str = "1101101101101101101101101101101101101101101101101101101101101101101101101101101101101101101101101101";
+
+    #[test]
+    fn test_psi_square() {
+        logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
+
+        assert!(
+            (serial::compute_psi_square(&BIT_STRING_NIST_1.parse().unwrap(), 3)
+                - PSI_SQUARES_NIST_1.0)
+                .abs()
+                < 1e-9
+        );
+        assert!(
+            (serial::compute_psi_square(&BIT_STRING_NIST_1.parse().unwrap(), 2)
+                - PSI_SQUARES_NIST_1.1)
+                .abs()
+                < 1e-9
+        );
+        assert!(
+            (serial::compute_psi_square(&BIT_STRING_NIST_1.parse().unwrap(), 1)
+                - PSI_SQUARES_NIST_1.2)
+                .abs()
+                < 1e-9
+        );
+        assert_eq!(
+            serial::compute_psi_square(&BIT_STRING_NIST_1.parse().unwrap(), 0),
+            0.0
+        );
+    }
+
+    #[test]
+    fn test_serial() {
+        logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
+
+        let (p_value_1, p_value_2) =
+            serial::perform_test(&BIT_STRING_NIST_1.parse().unwrap(), 3).unwrap();
+        assert!((p_value_1 - P_VALUES_NIST_1.0).abs() < 1e-6);
+        assert!((p_value_2 - P_VALUES_NIST_1.1).abs() < 1e-6);
+
+        let (p_value_1, p_value_2) =
+            serial::perform_test(&BIT_STRING_SAME_PATTERN.parse().unwrap(), 3).unwrap();
+        assert!(p_value_1 < 0.01);
+        assert!(p_value_2 < 0.01);
+
+        // the first 10^6 bits of e must match the results of NIST SP 800-22
+        let e_file = std::env::current_dir()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_owned()
+            + constants::E_FILE;
+        let e_bit_string = utils::read_random_numbers(&e_file).unwrap();
+        let (p_value_1, p_value_2) =
+            serial::perform_test(&e_bit_string.slice(0..NIST_LENGTH), 2).unwrap();
+        assert!((p_value_1 - P_VALUES_NIST_E_2.0).abs() < 1e-6);
+        assert!((p_value_2 - P_VALUES_NIST_E_2.1).abs() < 1e-6);
+        let (p_value_1, p_value_2) =
+            serial::perform_test(&e_bit_string.slice(0..NIST_LENGTH), 16).unwrap();
+        assert!((p_value_1 - P_VALUES_NIST_E_16.0).abs() < 1e-6);
+        assert!((p_value_2 - P_VALUES_NIST_E_16.1).abs() < 1e-6);
+
+        // test pi, sqrt(2), sqrt(3) and SHA-3 in their respective binary representations
+        let pi_file = std::env::current_dir()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_owned()
+            + constants::PI_FILE;
+        let pi_bit_string = utils::read_random_numbers(&pi_file).unwrap();
+        let (p_value_1, p_value_2) = serial::perform_test(&pi_bit_string, 16).unwrap();
+        assert!(p_value_1 >= 0.01 && p_value_2 >= 0.01);
+
+        let sqrt_2_file = std::env::current_dir()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_owned()
+            + constants::SQRT_2_FILE;
+        let sqrt_2_bit_string = utils::read_random_numbers(&sqrt_2_file).unwrap();
+        let (p_value_1, p_value_2) = serial::perform_test(&sqrt_2_bit_string, 16).unwrap();
+        assert!(p_value_1 >= 0.01 && p_value_2 >= 0.01);
+
+        let sqrt_3_file = std::env::current_dir()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_owned()
+            + constants::SQRT_3_FILE;
+        let sqrt_3_bit_string = utils::read_random_numbers(&sqrt_3_file).unwrap();
+        let (p_value_1, p_value_2) = serial::perform_test(&sqrt_3_bit_string, 16).unwrap();
+        assert!(p_value_1 >= 0.01 && p_value_2 >= 0.01);
+
+        let sha_3_file = std::env::current_dir()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_owned()
+            + constants::SHA_3_FILE;
+        let sha_3_bit_string = utils::read_random_numbers(&sha_3_file).unwrap();
+        let (p_value_1, p_value_2) = serial::perform_test(&sha_3_bit_string, 16).unwrap();
+        assert!(p_value_1 >= 0.01 && p_value_2 >= 0.01);
+    }
+
+    #[test]
+    fn test_serial_error_cases() {
+        logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
+
+        // pass empty sequence
+        assert!(serial::perform_test(&BitSequence::new(), 3).is_err());
+
+        // pass invalid pattern lengths
+        assert!(serial::perform_test(&BIT_STRING_NIST_1.parse().unwrap(), 1).is_err());
+        assert!(serial::perform_test(&BIT_STRING_NIST_1.parse().unwrap(), 4).is_err());
+    }
+}
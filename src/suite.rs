@@ -0,0 +1,308 @@
+//! This module performs the tests as a suite with prerequisites between the tests.
+//!
+//! NIST SP 800-22 recommends the Frequency (Monobit) Test as the first test: "All subsequent tests
+//! depend on the passing of this test." Therefore every other test is only performed on a bit
+//! sequence if the monobit test passes on it, also if the monobit test itself was not selected.
+//! Additionally, a test may check requirements of its own before it is performed, e.g. the pre-test
+//! of the Runs Test. Tests which are not performed are reported as skipped with the reason.
+
+use crate::bit_sequence::BitSequence;
+use crate::customtypes;
+use crate::frequency_monobit;
+use crate::multi_stream;
+use rayon::prelude::*;
+use std::sync::OnceLock;
+
+/// Declare the prerequisites of a test: the tests which have to pass on a bit sequence before the
+/// test is performed on it.
+///
+/// # Arguments
+///
+/// test - The test whose prerequisites are requested
+///
+/// # Return
+///
+/// prerequisites - The tests which have to pass first
+pub fn prerequisites(test: customtypes::Test) -> Vec<Box<dyn customtypes::StatisticalTest>> {
+    match test {
+        customtypes::Test::FrequencyMonobit => Vec::new(),
+        _ => vec![Box::new(frequency_monobit::FrequencyMonobit)],
+    }
+}
+
+/// Perform the tests on each stream in parallel, skipping the tests whose prerequisites do not
+/// hold on a stream, and collect the results per test.
+///
+/// # Arguments
+///
+/// tests - The tests to be performed
+/// streams - The streams to perform the tests on
+/// alpha - The significance level to decide about randomness of each stream
+///
+/// # Return
+///
+/// results - The results of all streams for each test in the order of the passed tests
+pub fn perform_tests(
+    tests: &[Box<dyn customtypes::StatisticalTest>],
+    streams: &[BitSequence],
+    alpha: f64,
+) -> Vec<customtypes::MultiStreamResult> {
+    log::trace!("suite::perform_tests()");
+
+    // perform each prerequisite once per stream and keep its results to decide about dependent
+    // tests. A selected test which is also a prerequisite reuses these results
+    let mut gatekeepers = Vec::<Box<dyn customtypes::StatisticalTest>>::new();
+    for prerequisite in tests.iter().flat_map(|test| prerequisites(test.test())) {
+        if !gatekeepers
+            .iter()
+            .any(|gatekeeper| gatekeeper.test() == prerequisite.test())
+        {
+            gatekeepers.push(prerequisite);
+        }
+    }
+
+    let stream_results = streams
+        .par_iter()
+        .enumerate()
+        .map(|(index, stream)| {
+            log::debug!("Stream {}/{}", index + 1, streams.len());
+
+            let random_walk = OnceLock::new();
+            let gates = gatekeepers
+                .par_iter()
+                .map(|gatekeeper| {
+                    let gatekeeper_walk =
+                        multi_stream::shared_random_walk(gatekeeper.as_ref(), stream, &random_walk);
+                    gatekeeper.run_on_walk(stream, gatekeeper_walk, alpha).ok()
+                })
+                .collect::<Vec<_>>();
+
+            tests
+                .par_iter()
+                .map(|test| {
+                    let required = prerequisites(test.test())
+                        .iter()
+                        .map(|prerequisite| prerequisite.test())
+                        .collect::<Vec<_>>();
+                    let reason = gatekeepers
+                        .iter()
+                        .zip(gates.iter())
+                        .filter(|(gatekeeper, _)| required.contains(&gatekeeper.test()))
+                        .find_map(|(_, result)| skip_reason(result.as_ref()))
+                        .or_else(|| test.pre_test(stream));
+                    let gate = gatekeepers
+                        .iter()
+                        .zip(gates.iter())
+                        .find(|(gatekeeper, _)| {
+                            gatekeeper.test() == test.test() && gatekeeper.params() == test.params()
+                        })
+                        .and_then(|(_, result)| result.clone());
+
+                    match (reason, gate) {
+                        (Some(reason), _) => {
+                            log::info!("{}: Skipped stream {}: {}", test.name(), index + 1, reason);
+                            Ok(customtypes::TestResult::skipped(
+                                test.test(),
+                                test.params(),
+                                reason,
+                                alpha,
+                            ))
+                        }
+                        (None, Some(result)) => Ok(result),
+                        (None, None) => {
+                            let random_walk = multi_stream::shared_random_walk(
+                                test.as_ref(),
+                                stream,
+                                &random_walk,
+                            );
+                            test.run_on_walk(stream, random_walk, alpha)
+                        }
+                    }
+                })
+                .collect()
+        })
+        .collect();
+
+    multi_stream::collect_per_test(tests, stream_results)
+}
+
+/// Decide about dependent tests from the result of a prerequisite on a stream.
+///
+/// # Arguments
+///
+/// result - The result of the prerequisite, None if it could not be performed
+///
+/// # Return
+///
+/// Some(reason) - The prerequisite failed, dependent tests have to be skipped
+/// None - The prerequisite passed or could not be performed, which the dependent tests report
+/// themselves
+fn skip_reason(result: Option<&customtypes::TestResult>) -> Option<String> {
+    match result {
+        Some(result) if result.verdict == customtypes::Verdict::Fail => Some(format!(
+            "{} failed with p-value {:.6}",
+            result.test,
+            result.p_value().unwrap_or_default()
+        )),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bit_sequence::BitSequence;
+    use crate::constants;
+    use crate::customtypes;
+    use crate::frequency_block;
+    use crate::frequency_monobit;
+    use crate::logger;
+    use crate::multi_stream;
+    use crate::runs;
+    use crate::suite;
+    use crate::utils;
+
+    const LOGLEVEL: &str = "Info";
+    const STREAM_LEN: usize = 100000;
+    const NUMBER_OF_STREAMS: usize = 10;
+    const ALPHA: f64 = 0.001;
+    const REASON_MONOBIT: &str = "'Frequency Monobit Test' failed with p-value";
+
+    #[test]
+    fn test_prerequisites() {
+        logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
+
+        assert!(suite::prerequisites(customtypes::Test::FrequencyMonobit).is_empty());
+        for test in &customtypes::Test::ALL[1..] {
+            let prerequisites = suite::prerequisites(*test);
+            assert_eq!(prerequisites.len(), 1);
+            assert_eq!(prerequisites[0].test(), customtypes::Test::FrequencyMonobit);
+        }
+    }
+
+    #[test]
+    fn test_perform_tests() {
+        logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
+
+        let e_file = std::env::current_dir()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_owned()
+            + constants::E_FILE;
+        let e_bit_string = utils::read_random_numbers(&e_file).unwrap();
+        let mut streams =
+            multi_stream::split_into_streams(&e_bit_string, STREAM_LEN, NUMBER_OF_STREAMS).unwrap();
+
+        // the last stream is biased towards ones, so the monobit test fails on it. All other
+        // streams pass the monobit test with alpha = 0.001
+        let biased = streams[NUMBER_OF_STREAMS - 1]
+            .iter()
+            .enumerate()
+            .map(|(index, bit)| bit || index % 8 == 0)
+            .collect::<BitSequence>();
+        streams[NUMBER_OF_STREAMS - 1] = biased;
+
+        // the monobit test is performed as prerequisite although it is not selected
+        let tests: Vec<Box<dyn customtypes::StatisticalTest>> = vec![
+            Box::new(frequency_block::FrequencyBlock::create(1001)),
+            Box::new(runs::Runs),
+        ];
+        let results = suite::perform_tests(&tests, &streams, ALPHA);
+        assert_eq!(results.len(), tests.len());
+
+        for result in &results {
+            assert_eq!(result.number_of_errors(), 0);
+            let stream_results = result
+                .stream_results
+                .iter()
+                .map(|stream_result| stream_result.as_ref().unwrap())
+                .collect::<Vec<_>>();
+
+            assert!(stream_results[..NUMBER_OF_STREAMS - 1]
+                .iter()
+                .all(|stream_result| stream_result.verdict != customtypes::Verdict::Skipped));
+            let skipped = stream_results[NUMBER_OF_STREAMS - 1];
+            assert_eq!(skipped.verdict, customtypes::Verdict::Skipped);
+            assert!(skipped.p_values.is_empty());
+            assert!(skipped.reason.as_ref().unwrap().starts_with(REASON_MONOBIT));
+
+            // skipped streams do not contribute any p-values
+            assert_eq!(result.p_values()[0].1.len(), NUMBER_OF_STREAMS - 1);
+        }
+
+        // the results of the performed tests are the same as without prerequisites
+        let ungated = multi_stream::perform_tests(&tests, &streams, ALPHA);
+        for (result, ungated) in results.iter().zip(ungated.iter()) {
+            for (stream_result, ungated) in result.stream_results[..NUMBER_OF_STREAMS - 1]
+                .iter()
+                .zip(ungated.stream_results.iter())
+            {
+                assert_eq!(
+                    stream_result.as_ref().unwrap().p_values,
+                    ungated.as_ref().unwrap().p_values
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_perform_tests_selected_prerequisite() {
+        logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
+
+        // the monobit test fails on the stream of only ones and passes on the alternating stream
+        let streams = [
+            (0..1000).map(|_| true).collect::<BitSequence>(),
+            (0..1000)
+                .map(|index| index % 2 == 0)
+                .collect::<BitSequence>(),
+        ];
+
+        // the selected monobit test reports the results of the prerequisite, it is not skipped
+        let tests: Vec<Box<dyn customtypes::StatisticalTest>> = vec![
+            Box::new(frequency_monobit::FrequencyMonobit),
+            Box::new(runs::Runs),
+        ];
+        let results = suite::perform_tests(&tests, &streams, ALPHA);
+        let ungated = multi_stream::perform_tests(&tests[..1], &streams, ALPHA);
+        for (stream_result, ungated) in results[0]
+            .stream_results
+            .iter()
+            .zip(ungated[0].stream_results.iter())
+        {
+            let stream_result = stream_result.as_ref().unwrap();
+            let ungated = ungated.as_ref().unwrap();
+            assert_eq!(stream_result.verdict, ungated.verdict);
+            assert_eq!(stream_result.p_values, ungated.p_values);
+        }
+        assert_eq!(
+            results[0].stream_results[0].as_ref().unwrap().verdict,
+            customtypes::Verdict::Fail
+        );
+        assert_eq!(
+            results[1].stream_results[0].as_ref().unwrap().verdict,
+            customtypes::Verdict::Skipped
+        );
+    }
+
+    #[test]
+    fn test_perform_tests_pre_test() {
+        logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
+
+        // the proportion of ones 0.521 fails the pre-test of the runs test, but passes the monobit
+        // test with alpha = 0.00001
+        let bit_sequence = (0..10000)
+            .map(|index| index % 2 == 0 || index % 50 == 1 || index % 1000 == 3)
+            .collect::<BitSequence>();
+        let results = suite::perform_tests(
+            &[Box::new(runs::Runs)],
+            std::slice::from_ref(&bit_sequence),
+            0.00001,
+        );
+        let result = results[0].stream_results[0].as_ref().unwrap();
+        assert_eq!(result.verdict, customtypes::Verdict::Skipped);
+        assert!(result.reason.as_ref().unwrap().contains("not applicable"));
+
+        // the runs test on its own still rejects the bit sequence
+        assert!(runs::perform_test(&bit_sequence).is_err());
+    }
+}
@@ -1,3 +1,6 @@
+//! This module contains helpers to create sample files of good and bad random numbers for tests.
+#![allow(dead_code)]
+
 use anyhow::{Context, Result};
 use rand::Rng;
 use sha3::{Digest, Sha3_512};
@@ -59,7 +62,7 @@ pub fn create_good_random_numbers(
 
             for byte in hash_result.iter() {
                 writer
-                    .write_all(&format!("{:08b}", byte).as_bytes())
+                    .write_all(format!("{:08b}", byte).as_bytes())
                     .with_context(|| {
                         format!("Failed to write random bits into file '{}'", &filename)
                     })?;
@@ -86,8 +89,8 @@ pub fn create_good_random_numbers(
 ///
 /// Ok() - Samples could be created successfully
 /// Err(err) - Some error occured
-
-pub fn create_bad_random_numbers(num_of_samples: usize,
+pub fn create_bad_random_numbers(
+    num_of_samples: usize,
     num_of_bits: usize,
     dest: &str,
 ) -> Result<()> {
@@ -106,7 +109,8 @@ pub fn create_bad_random_numbers(num_of_samples: usize,
             + "/"
             + format!("sample_{:0width$}", sample + 1, width = num_digits).as_str();
 
-        let file = std::fs::File::create(&filename).with_context(|| format!("Failed to create file '{}'", &filename))?;
+        let file = std::fs::File::create(&filename)
+            .with_context(|| format!("Failed to create file '{}'", &filename))?;
         let mut writer = BufWriter::new(file);
 
         // create random seed
@@ -118,14 +122,11 @@ pub fn create_bad_random_numbers(num_of_samples: usize,
                 num = (A.wrapping_mul(num) + C) % M;
                 let byte = (num & 0xFF) as u8;
 
-                writer.write_all(&format!("{:08b}", byte).as_bytes())?;
+                writer.write_all(format!("{:08b}", byte).as_bytes())?;
             }
-             writer.write_all(b"\n")?;
+            writer.write_all(b"\n")?;
         }
     }
 
     Ok(())
-
-
-
 }
@@ -1,41 +1,45 @@
 //! This module contains useful functions to support the statistical tests from the NIST suite.
 
+use crate::bit_sequence::BitSequence;
+use crate::constants;
 use crate::customtypes;
 use anyhow::{Context, Result};
+use base64::Engine;
+use sha2::Digest;
 use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::io::Read;
 
-/// Evaluate passed bit string.
+/// The number of bytes read at once from streamed input
+const READ_CHUNK_SIZE: usize = 1 << 16;
+
+/// Evaluate passed bit sequence.
 ///
 /// # Arguments
 ///
 /// test_name - The name of the test the evaluation is made for
-/// bit_string - The bit string to evaluate
-/// recommended_size - Log a warning if passed bit string has not recommended size
+/// bit_sequence - The bit sequence to evaluate
+/// recommended_size - Log a warning if passed bit sequence has not recommended size
 ///
 /// # Return
 ///
-/// Ok(length) - Return length of bit string if everything is okay
+/// Ok(length) - Return length of bit sequence if everything is okay
 /// Err(err) - Some error occured
-pub fn evaluate_bit_string(
+pub fn evaluate_bit_sequence(
     test_name: customtypes::Test,
-    bit_string: &str,
+    bit_sequence: &BitSequence,
     recommended_size: usize,
 ) -> Result<usize> {
-    log::trace!("utils::evaluate_bit_string()");
+    log::trace!("utils::evaluate_bit_sequence()");
 
-    // check validity of passed bit string
-    if bit_string.is_empty() || bit_string.chars().any(|c| c != '0' && c != '1') {
-        anyhow::bail!(
-            "{}: Bit string is either empty or contains invalid character(s)",
-            test_name
-        );
+    // the bits themselves are valid by construction, so only the length needs to be checked
+    if bit_sequence.is_empty() {
+        anyhow::bail!("{}: Bit sequence is empty", test_name);
     }
 
-    let length = bit_string.len();
-    log::debug!("{}: Bit string has the length {}", test_name, length);
+    let length = bit_sequence.len();
+    log::debug!("{}: Bit sequence has the length {}", test_name, length);
 
-    // If bit string has not the recommended size, it is not an error but log a warning anyways
+    // If bit sequence has not the recommended size, it is not an error but log a warning anyways
     if length < recommended_size {
         log::warn!(
             "Recommended size for {} is at least {} bits. Consider imprecision when calculating p-value",
@@ -47,220 +51,355 @@ pub fn evaluate_bit_string(
     Ok(length)
 }
 
-/// Convert a given vector of hexadecimal bytes into a bit string.
+/// Read file containing already generated random numbers (either as hex bytes or as bit string).
+/// The format is guessed from the contents of the file, see `read_random_numbers_with_format`
+/// to pass the format explicitly.
 ///
 /// # Arguments
 ///
-/// hex_bytes - A byte vector to be converted
+/// file_path - The path to the file containing random bytes
 ///
 /// # Return
 ///
-/// Ok(bit_string) - The bit string converted from given hex string
+/// Ok(bit_sequence) - The read bit sequence
 /// Err(err) - Some error occured
-pub fn hex_bytes_to_bit_string(hex_bytes: Vec<u8>) -> Result<String> {
-    log::trace!("utils::hex_bytes_to_bit_string()");
+pub fn read_random_numbers(file_path: &str) -> Result<BitSequence> {
+    log::trace!("utils::read_random_numbers()");
 
-    // check if given vector is empty or not
-    if hex_bytes.is_empty() {
-        anyhow::bail!("No hexadecimal bytes to convert passed!");
-    }
+    read_random_numbers_with_format(
+        file_path,
+        customtypes::InputFormat::Auto,
+        customtypes::BitOrder::MsbFirst,
+    )
+}
 
-    // now convert hex bytes to bit string
-    let bit_string = hex_bytes
-        .iter()
-        .flat_map(|&byte| {
-            (0..8)
-                .rev()
-                .map(move |i| if byte & (1 << i) == 0 { '0' } else { '1' })
-        })
-        .collect();
+/// Read file containing already generated random numbers in the given format.
+///
+/// # Arguments
+///
+/// file_path - The path to the file containing random bytes
+/// input_format - The format of the file contents
+/// bit_order - The order of the bits within each byte (ignored for ASCII bits)
+///
+/// # Return
+///
+/// Ok(bit_sequence) - The read bit sequence
+/// Err(err) - Some error occured
+pub fn read_random_numbers_with_format(
+    file_path: &str,
+    input_format: customtypes::InputFormat,
+    bit_order: customtypes::BitOrder,
+) -> Result<BitSequence> {
+    log::trace!("utils::read_random_numbers_with_format()");
+
+    // read the raw contents of the file, they are interpreted according to the format
+    let contents =
+        std::fs::read(file_path).with_context(|| format!("Failed to open file '{}'", file_path))?;
+
+    let bit_sequence = parse_random_numbers(&contents, input_format, bit_order)
+        .with_context(|| format!("Failed to parse random numbers in file '{}'", file_path))?;
 
     log::info!(
-        "Successfully converted {} hex bytes to bit string",
-        hex_bytes.len()
+        "Successfully read {} random bits from '{}'",
+        bit_sequence.len(),
+        file_path
     );
 
-    Ok(bit_string)
+    Ok(bit_sequence)
 }
 
-/// Read file containing already generated random numbers (either as hex bytes or as bit string).
+/// Convert the contents of a file containing random numbers into a bit sequence. Whitespace in
+/// text formats, e.g. line breaks, is ignored.
 ///
 /// # Arguments
 ///
-/// file_path - The path to the file containing random bytes
+/// contents - The raw contents to be converted
+/// input_format - The format of the contents
+/// bit_order - The order of the bits within each byte (ignored for ASCII bits)
 ///
 /// # Return
 ///
-/// Ok(bit_string) - The read bit string
+/// Ok(bit_sequence) - The converted bit sequence
 /// Err(err) - Some error occured
-pub fn read_random_numbers(file_path: &str) -> Result<String> {
-    log::trace!("utils::read_random_numbers()");
-
-    // open the file
-    let file =
-        File::open(file_path).with_context(|| format!("Failed to open file '{}'", file_path))?;
-
-    // read the contents of the file into a string
-    // if the random number is separated into multiple lines, concatenate them into one line
-    let mut random_string = String::new();
-    let reader = BufReader::new(file);
-    for line in reader.lines() {
-        if let Ok(line) = line {
-            random_string.push_str(&line);
-        }
+pub fn parse_random_numbers(
+    contents: &[u8],
+    input_format: customtypes::InputFormat,
+    bit_order: customtypes::BitOrder,
+) -> Result<BitSequence> {
+    log::trace!("utils::parse_random_numbers()");
+
+    // raw binary bytes can be converted directly
+    if input_format == customtypes::InputFormat::Binary {
+        return Ok(BitSequence::from_bytes_with_order(contents, bit_order));
     }
 
-    // remove any whitespace characters from the string
+    // all other formats are text. Remove any whitespace characters from the text
+    let mut random_string = std::str::from_utf8(contents)
+        .with_context(|| "Random numbers are neither binary nor valid text")?
+        .to_owned();
     random_string.retain(|c| !c.is_whitespace());
 
-    // now decide whether we do have hexadecimal bytes or binary string
-    let bit_string = if random_string.chars().all(|c| c == '0' || c == '1') {
-        random_string
-    } else if random_string.chars().all(|c| c.is_ascii_hexdigit()) {
-        // parse the hexadecimal string into bytes
-        let random_bytes = hex::decode(&random_string)
-            .map_err(|e| anyhow::anyhow!("Failed to parse hexadecimal string: {}", e))?;
-        // now convert to bit string
-        hex_bytes_to_bit_string(random_bytes)?
+    // now decide whether we do have hexadecimal bytes or binary string if the format is not given
+    let input_format = match input_format {
+        customtypes::InputFormat::Auto => {
+            if random_string.chars().all(|c| c == '0' || c == '1') {
+                customtypes::InputFormat::Ascii
+            } else if random_string.chars().all(|c| c.is_ascii_hexdigit()) {
+                customtypes::InputFormat::Hex
+            } else {
+                anyhow::bail!(
+                    "Random numbers neither contain valid hex bytes nor valid bit string!"
+                );
+            }
+        }
+        input_format => input_format,
+    };
+    log::debug!("Parsing random numbers as {:?}", input_format);
+
+    let bit_sequence = match input_format {
+        customtypes::InputFormat::Ascii => BitSequence::from_ascii_bits(&random_string)?,
+        customtypes::InputFormat::Hex => {
+            let random_bytes = hex::decode(&random_string)
+                .map_err(|e| anyhow::anyhow!("Failed to parse hexadecimal string: {}", e))?;
+            BitSequence::from_bytes_with_order(&random_bytes, bit_order)
+        }
+        customtypes::InputFormat::Base64 => {
+            let random_bytes = base64::engine::general_purpose::STANDARD
+                .decode(&random_string)
+                .map_err(|e| anyhow::anyhow!("Failed to parse base64 string: {}", e))?;
+            BitSequence::from_bytes_with_order(&random_bytes, bit_order)
+        }
+        customtypes::InputFormat::Auto | customtypes::InputFormat::Binary => unreachable!(),
+    };
+
+    Ok(bit_sequence)
+}
+
+/// Read random numbers from a file, a named pipe or the standard input ("-"). Raw binary input is
+/// read in chunks and reading stops as soon as max_bits are read, so piped generator output does
+/// not need to be stored in a file. Since the standard input is meant for piped generator output,
+/// the automatic format means raw binary there.
+///
+/// # Arguments
+///
+/// source - The path of the file or named pipe or "-" for the standard input
+/// input_format - The format of the input
+/// bit_order - The order of the bits within each byte (ignored for ASCII bits)
+/// max_bits - The maximum number of bits to read or None to read until the end of the input
+///
+/// # Return
+///
+/// Ok((bit_sequence, sha256)) - The read bit sequence and the SHA-256 of the consumed input
+/// Err(err) - Some error occured
+pub fn read_random_numbers_from_source(
+    source: &str,
+    input_format: customtypes::InputFormat,
+    bit_order: customtypes::BitOrder,
+    max_bits: Option<usize>,
+) -> Result<(BitSequence, String)> {
+    log::trace!("utils::read_random_numbers_from_source()");
+
+    let (bit_sequence, sha256) = if source == constants::STDIN_SOURCE {
+        let input_format = match input_format {
+            customtypes::InputFormat::Auto => customtypes::InputFormat::Binary,
+            input_format => input_format,
+        };
+        read_random_numbers_from_reader(std::io::stdin().lock(), input_format, bit_order, max_bits)
+            .with_context(|| "Failed to read random numbers from standard input")?
     } else {
-        anyhow::bail!(
-            "File '{}' neither contains valid hex bytes nor valid bit string!",
-            file_path
-        );
+        let file =
+            File::open(source).with_context(|| format!("Failed to open file '{}'", source))?;
+        read_random_numbers_from_reader(file, input_format, bit_order, max_bits)
+            .with_context(|| format!("Failed to read random numbers from '{}'", source))?
     };
 
     log::info!(
         "Successfully read {} random bits from '{}'",
-        bit_string.len(),
-        file_path
+        bit_sequence.len(),
+        source
     );
 
-    Ok(bit_string)
+    Ok((bit_sequence, sha256))
 }
 
-/// Untar a given archive to specific destination.
+/// Read random numbers from a reader. Raw binary input is read in chunks until the end of the
+/// input or until max_bits are read. Text formats are read completely and truncated afterwards,
+/// since the format has to be known and whitespace may occur anywhere.
 ///
 /// # Arguments
 ///
-/// archive_name - Archive to be decompressed
-/// dest - Destination directory to unpack the archive contents
+/// reader - The source of the random numbers
+/// input_format - The format of the input
+/// bit_order - The order of the bits within each byte (ignored for ASCII bits)
+/// max_bits - The maximum number of bits to read or None to read until the end of the input
 ///
 /// # Return
 ///
-/// Ok() - Successfully unpacked archive
+/// Ok((bit_sequence, sha256)) - The read bit sequence and the SHA-256 of the consumed input
 /// Err(err) - Some error occured
-pub fn untar_archive(archive_name: &str, dest: &str) -> Result<()> {
-    log::trace!("utils::untar_archive()");
+pub fn read_random_numbers_from_reader(
+    mut reader: impl Read,
+    input_format: customtypes::InputFormat,
+    bit_order: customtypes::BitOrder,
+    max_bits: Option<usize>,
+) -> Result<(BitSequence, String)> {
+    log::trace!("utils::read_random_numbers_from_reader()");
+
+    let mut hasher = sha2::Sha256::new();
+
+    let mut bit_sequence = if input_format == customtypes::InputFormat::Binary {
+        // stop reading after the byte containing the last requested bit
+        let max_bytes = max_bits.map_or(u64::MAX, |max_bits| max_bits.div_ceil(8) as u64);
+        let mut reader = reader.take(max_bytes);
+        let mut bit_sequence = BitSequence::new();
+        let mut chunk = vec![0_u8; READ_CHUNK_SIZE];
+
+        loop {
+            let read = match reader.read(&mut chunk) {
+                Ok(0) => break,
+                Ok(read) => read,
+                Err(err) if err.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err).with_context(|| "Failed to read random bytes"),
+            };
+            hasher.update(&chunk[..read]);
+            bit_sequence.extend_from_bytes(&chunk[..read], bit_order);
+        }
+
+        bit_sequence
+    } else {
+        let mut contents = Vec::new();
+        reader
+            .read_to_end(&mut contents)
+            .with_context(|| "Failed to read random numbers")?;
+        hasher.update(&contents);
 
-    // check whether archive and destination exist
-    if !std::path::Path::new(archive_name).exists() {
-        anyhow::bail!("Archive '{}' does not exist!", archive_name);
+        parse_random_numbers(&contents, input_format, bit_order)?
+    };
+
+    if let Some(max_bits) = max_bits {
+        if bit_sequence.len() < max_bits {
+            log::warn!(
+                "Input ended after {} bits, {} bits were requested",
+                bit_sequence.len(),
+                max_bits
+            );
+        }
+        bit_sequence.truncate(max_bits);
     }
-    if !(std::path::Path::new(dest).exists() && std::path::Path::new(dest).is_dir()) {
-        anyhow::bail!(
-            "Destination path '{}' neither exists nor is a directory",
-            dest
-        );
+
+    Ok((bit_sequence, hex::encode(hasher.finalize())))
+}
+
+/// Count the occurences of all overlapping patterns of given length. The bit sequence is treated
+/// cyclically, i.e., its first (pattern_len - 1) bits are appended to the end so that exactly n
+/// patterns are counted.
+///
+/// # Arguments
+///
+/// bit_sequence - The bit sequence to count the patterns in
+/// pattern_len - The length of the patterns
+///
+/// # Return
+///
+/// counts - The number of occurences of each pattern, indexed by its binary value
+pub fn count_overlapping_patterns(bit_sequence: &BitSequence, pattern_len: usize) -> Vec<usize> {
+    log::trace!("utils::count_overlapping_patterns()");
+
+    // there is exactly one (empty) pattern of length 0
+    if pattern_len == 0 {
+        return vec![bit_sequence.len()];
     }
 
-    // now try to untar the archive
-    let file = File::open(archive_name)
-        .with_context(|| format!("Failed to open archive '{}'", archive_name))?;
-    let decompressed = flate2::read::GzDecoder::new(file);
+    let mask = (1_usize << pattern_len) - 1;
 
-    let mut archive = tar::Archive::new(decompressed);
-    archive
-        .unpack(dest)
-        .with_context(|| format!("Failed to unpack archive '{}'", archive_name))?;
+    // count the occurences of each pattern with a rolling window over the augmented sequence
+    let mut counts = vec![0_usize; 1 << pattern_len];
+    let mut window = 0_usize;
 
-    log::debug!(
-        "Successfully unpacked archive '{}' to '{}'",
-        archive_name,
-        dest
-    );
+    for (index, bit) in bit_sequence
+        .iter()
+        .chain(bit_sequence.iter().take(pattern_len - 1))
+        .enumerate()
+    {
+        window = ((window << 1) | bit as usize) & mask;
+        if index + 1 >= pattern_len {
+            counts[window] += 1;
+        }
+    }
 
-    Ok(())
+    counts
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::bit_sequence::BitSequence;
     use crate::customtypes;
     use crate::logger;
     use crate::utils;
-    use crate::test_helper;
-    use std::io::Read;
+    use crate::utils::READ_CHUNK_SIZE;
 
     const LOGLEVEL: &str = "Trace";
     const TEST_NAME: customtypes::Test = customtypes::Test::FrequencyMonobit;
-    static RANDOM_BYTES_1: [u8; 6] = [0xab, 0x00, 0xde, 0xd6, 0xf3, 0xc0];
-    static RANDOM_BYTES_2: [u8; 9] = [0x00, 0xFF, 0xDE, 0x89, 0xC0, 0x3D, 0xA6, 0xC2, 0xB5];
     const BIT_STRING_1: &str = "101010110000000011011110110101101111001111000000";
     const BIT_STRING_2: &str =
         "000000001111111111011110100010011100000000111101101001101100001010110101";
-    const INVALID_BIT_STRING: &str = "010101010101011110101010101010101X0101010101010101010101";
     const NUMBER_OF_BYTES: usize = 100;
+    static RANDOM_BYTES: [u8; 4] = [0xde, 0xad, 0x01, 0x80];
+    const HEX_STRING: &str = "dead\n0180\n";
+    const BASE64_STRING: &str = "3q0BgA==";
+    const SHA256_RANDOM_BYTES: &str =
+        "07254d92fc6ff99edc04c4aa7133d0980265df9266b6aad708f16c0459a5105e";
+    const BIT_STRING_MSB_FIRST: &str = "11011110101011010000000110000000";
+    const BIT_STRING_LSB_FIRST: &str = "01111011101101011000000000000001";
     const HEX_BYTES_FILE: &str = "/src/testdata/random_hex_bytes";
     const BIT_STRING_FROM_FILE: &str = "110010101111111010111010101111101101111010101101101111101110111111000000110111101010111111111110";
     const BIT_STRING_FILE: &str = "/src/testdata/random_bit_string";
     const INVALID_CHAR_IN_FILE: &str = "/src/testdata/random_invalid_char";
     const INVALID_FILE: &str = "/non-existing-dir/random_numbers";
-    const TEMPLATE_FILE: &str = "/templates/template2.tar.gz";
-    const ARCHIVE_DEST_DIR: &str = "/tmp";
-    const TEMPLATE_FILE_DEST: &str = "/tmp/template2";
 
     #[test]
-    fn test_hex_bytes_to_bit_string() {
+    fn test_evaluate_bit_sequence() {
         logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
 
-        let _ = test_helper::create_bad_random_numbers(500, 1_000_000, "/tmp/samples");
-
+        let bit_sequence_1: BitSequence = BIT_STRING_1.parse().unwrap();
         assert_eq!(
-            utils::hex_bytes_to_bit_string(RANDOM_BYTES_1.to_vec()).unwrap(),
-            BIT_STRING_1
+            utils::evaluate_bit_sequence(TEST_NAME, &bit_sequence_1, BIT_STRING_1.len()).unwrap(),
+            BIT_STRING_1.len()
         );
+        let bit_sequence_2: BitSequence = BIT_STRING_2.parse().unwrap();
         assert_eq!(
-            utils::hex_bytes_to_bit_string(RANDOM_BYTES_2.to_vec()).unwrap(),
-            BIT_STRING_2
+            utils::evaluate_bit_sequence(TEST_NAME, &bit_sequence_2, NUMBER_OF_BYTES).unwrap(),
+            BIT_STRING_2.len()
         );
 
-        let success: bool;
-
-        // pass empty vector
-        match utils::hex_bytes_to_bit_string(Vec::<u8>::new()) {
-            Ok(_) => success = true,
-            Err(_) => success = false,
-        };
-        assert!(!success);
+        // pass empty sequence
+        assert!(
+            utils::evaluate_bit_sequence(TEST_NAME, &BitSequence::new(), NUMBER_OF_BYTES).is_err()
+        );
     }
 
     #[test]
-    fn test_evaluate_bit_string() {
+    fn test_count_overlapping_patterns() {
         logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
 
+        // example from NIST SP 800-22 (Serial Test): "0011011101" augmented by "00"
+        let bit_sequence: BitSequence = "0011011101".parse().unwrap();
         assert_eq!(
-            utils::evaluate_bit_string(TEST_NAME, BIT_STRING_1, BIT_STRING_1.len()).unwrap(),
-            BIT_STRING_1.len()
+            utils::count_overlapping_patterns(&bit_sequence, 3),
+            vec![0, 1, 1, 2, 1, 2, 2, 1]
         );
         assert_eq!(
-            utils::evaluate_bit_string(TEST_NAME, BIT_STRING_2, BIT_STRING_2.len()).unwrap(),
-            BIT_STRING_2.len()
+            utils::count_overlapping_patterns(&bit_sequence, 2),
+            vec![1, 3, 3, 3]
+        );
+        assert_eq!(
+            utils::count_overlapping_patterns(&bit_sequence, 1),
+            vec![4, 6]
+        );
+        assert_eq!(
+            utils::count_overlapping_patterns(&bit_sequence, 0),
+            vec![10]
         );
-
-        let mut success: bool;
-
-        // pass empty string
-        match utils::evaluate_bit_string(TEST_NAME, "", NUMBER_OF_BYTES) {
-            Ok(_) => success = true,
-            Err(_) => success = false,
-        };
-        assert!(!success);
-
-        // pass invalid string
-        match utils::evaluate_bit_string(TEST_NAME, INVALID_BIT_STRING, NUMBER_OF_BYTES) {
-            Ok(_) => success = true,
-            Err(_) => success = false,
-        };
-        assert!(!success);
     }
 
     #[test]
@@ -275,7 +414,9 @@ mod tests {
             .to_owned()
             + HEX_BYTES_FILE;
         assert_eq!(
-            utils::read_random_numbers(&hex_bytes_file).unwrap(),
+            utils::read_random_numbers(&hex_bytes_file)
+                .unwrap()
+                .to_string(),
             BIT_STRING_FROM_FILE
         );
 
@@ -287,11 +428,29 @@ mod tests {
             .to_owned()
             + BIT_STRING_FILE;
         assert_eq!(
-            utils::read_random_numbers(&bit_string_file).unwrap(),
+            utils::read_random_numbers(&bit_string_file)
+                .unwrap()
+                .to_string(),
             BIT_STRING_FROM_FILE
         );
 
-        let mut success: bool;
+        // read the files with explicitly passed format
+        assert_eq!(
+            utils::read_random_numbers_with_format(
+                &hex_bytes_file,
+                customtypes::InputFormat::Hex,
+                customtypes::BitOrder::MsbFirst
+            )
+            .unwrap()
+            .to_string(),
+            BIT_STRING_FROM_FILE
+        );
+        assert!(utils::read_random_numbers_with_format(
+            &hex_bytes_file,
+            customtypes::InputFormat::Ascii,
+            customtypes::BitOrder::MsbFirst
+        )
+        .is_err());
 
         // try to read file containing invalid character
         let invalid_char_file = std::env::current_dir()
@@ -300,59 +459,231 @@ mod tests {
             .unwrap()
             .to_owned()
             + INVALID_CHAR_IN_FILE;
-        match utils::read_random_numbers(&invalid_char_file) {
-            Ok(_) => success = true,
-            Err(_) => success = false,
-        };
-        assert!(!success);
+        assert!(utils::read_random_numbers(&invalid_char_file).is_err());
 
         // try to read non-existing file
-        match utils::read_random_numbers(INVALID_FILE) {
-            Ok(_) => success = true,
-            Err(_) => success = false,
-        };
-        assert!(!success);
+        assert!(utils::read_random_numbers(INVALID_FILE).is_err());
     }
 
     #[test]
-    fn test_untar_archive() {
+    fn test_parse_random_numbers() {
         logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
 
-        if std::path::Path::new(TEMPLATE_FILE_DEST).exists() {
-            let _ = std::fs::remove_file(TEMPLATE_FILE_DEST);
+        // the same bytes in all formats with both bit orders
+        for (contents, input_format) in [
+            (&RANDOM_BYTES[..], customtypes::InputFormat::Binary),
+            (HEX_STRING.as_bytes(), customtypes::InputFormat::Hex),
+            (HEX_STRING.as_bytes(), customtypes::InputFormat::Auto),
+            (BASE64_STRING.as_bytes(), customtypes::InputFormat::Base64),
+        ] {
+            assert_eq!(
+                utils::parse_random_numbers(
+                    contents,
+                    input_format,
+                    customtypes::BitOrder::MsbFirst
+                )
+                .unwrap()
+                .to_string(),
+                BIT_STRING_MSB_FIRST
+            );
+            assert_eq!(
+                utils::parse_random_numbers(
+                    contents,
+                    input_format,
+                    customtypes::BitOrder::LsbFirst
+                )
+                .unwrap()
+                .to_string(),
+                BIT_STRING_LSB_FIRST
+            );
+        }
+
+        // ASCII bits are not affected by the bit order
+        for input_format in [
+            customtypes::InputFormat::Ascii,
+            customtypes::InputFormat::Auto,
+        ] {
+            assert_eq!(
+                utils::parse_random_numbers(
+                    BIT_STRING_MSB_FIRST.as_bytes(),
+                    input_format,
+                    customtypes::BitOrder::LsbFirst
+                )
+                .unwrap()
+                .to_string(),
+                BIT_STRING_MSB_FIRST
+            );
         }
-        assert!(!std::path::Path::new(TEMPLATE_FILE_DEST).exists());
 
-        // untar archive from templates folder
-        let template = std::env::current_dir()
+        // hex digits consisting of '0' and '1' only are guessed to be bits unless passed explicitly
+        let ambiguous = b"0110\n1001\n";
+        assert_eq!(
+            utils::parse_random_numbers(
+                ambiguous,
+                customtypes::InputFormat::Auto,
+                customtypes::BitOrder::MsbFirst
+            )
+            .unwrap()
+            .to_string(),
+            "01101001"
+        );
+        assert_eq!(
+            utils::parse_random_numbers(
+                ambiguous,
+                customtypes::InputFormat::Hex,
+                customtypes::BitOrder::MsbFirst
+            )
+            .unwrap()
+            .to_string(),
+            "00000001000100000001000000000001"
+        );
+
+        // invalid contents for the given format
+        let msb_first = customtypes::BitOrder::MsbFirst;
+        assert!(
+            utils::parse_random_numbers(b"0120", customtypes::InputFormat::Ascii, msb_first)
+                .is_err()
+        );
+        assert!(
+            utils::parse_random_numbers(b"abc", customtypes::InputFormat::Hex, msb_first).is_err()
+        );
+        assert!(
+            utils::parse_random_numbers(b"q=q=", customtypes::InputFormat::Base64, msb_first)
+                .is_err()
+        );
+        assert!(
+            utils::parse_random_numbers(b"xyz", customtypes::InputFormat::Auto, msb_first).is_err()
+        );
+        assert!(utils::parse_random_numbers(
+            &RANDOM_BYTES,
+            customtypes::InputFormat::Auto,
+            msb_first
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_read_random_numbers_from_reader() {
+        logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
+
+        // raw bytes with both bit orders and the SHA-256 of the input
+        let (bit_sequence, sha256) = utils::read_random_numbers_from_reader(
+            &RANDOM_BYTES[..],
+            customtypes::InputFormat::Binary,
+            customtypes::BitOrder::LsbFirst,
+            None,
+        )
+        .unwrap();
+        assert_eq!(bit_sequence.to_string(), BIT_STRING_LSB_FIRST);
+        assert_eq!(sha256, SHA256_RANDOM_BYTES);
+
+        // reading stops after the byte containing the last requested bit
+        let mut reader = &RANDOM_BYTES[..];
+        let (bit_sequence, sha256) = utils::read_random_numbers_from_reader(
+            &mut reader,
+            customtypes::InputFormat::Binary,
+            customtypes::BitOrder::MsbFirst,
+            Some(10),
+        )
+        .unwrap();
+        assert_eq!(bit_sequence.to_string(), BIT_STRING_MSB_FIRST[..10]);
+        assert_eq!(reader, &RANDOM_BYTES[2..]);
+        assert_eq!(
+            sha256,
+            utils::read_random_numbers_from_reader(
+                &RANDOM_BYTES[..2],
+                customtypes::InputFormat::Binary,
+                customtypes::BitOrder::MsbFirst,
+                None,
+            )
+            .unwrap()
+            .1
+        );
+
+        // input spanning several chunks
+        let random_bytes = (0..3 * READ_CHUNK_SIZE + 5)
+            .map(|index| (index * 7 % 256) as u8)
+            .collect::<Vec<_>>();
+        let (bit_sequence, _) = utils::read_random_numbers_from_reader(
+            &random_bytes[..],
+            customtypes::InputFormat::Binary,
+            customtypes::BitOrder::MsbFirst,
+            None,
+        )
+        .unwrap();
+        assert_eq!(bit_sequence, BitSequence::from_bytes(&random_bytes));
+
+        // text formats are truncated to the requested number of bits
+        let (bit_sequence, _) = utils::read_random_numbers_from_reader(
+            HEX_STRING.as_bytes(),
+            customtypes::InputFormat::Hex,
+            customtypes::BitOrder::MsbFirst,
+            Some(12),
+        )
+        .unwrap();
+        assert_eq!(bit_sequence.to_string(), BIT_STRING_MSB_FIRST[..12]);
+
+        // more bits requested than available
+        let (bit_sequence, _) = utils::read_random_numbers_from_reader(
+            BASE64_STRING.as_bytes(),
+            customtypes::InputFormat::Base64,
+            customtypes::BitOrder::MsbFirst,
+            Some(1000),
+        )
+        .unwrap();
+        assert_eq!(bit_sequence.to_string(), BIT_STRING_MSB_FIRST);
+
+        // invalid text
+        assert!(utils::read_random_numbers_from_reader(
+            HEX_STRING.as_bytes(),
+            customtypes::InputFormat::Ascii,
+            customtypes::BitOrder::MsbFirst,
+            None,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_read_random_numbers_from_source() {
+        logger::init_logger(LOGLEVEL).expect("Could not initialize logger");
+
+        let hex_bytes_file = std::env::current_dir()
             .unwrap()
             .to_str()
             .unwrap()
             .to_owned()
-            + TEMPLATE_FILE;
-        assert!(std::path::Path::new(&template).exists());
+            + HEX_BYTES_FILE;
+        let (bit_sequence, _) = utils::read_random_numbers_from_source(
+            &hex_bytes_file,
+            customtypes::InputFormat::Auto,
+            customtypes::BitOrder::MsbFirst,
+            None,
+        )
+        .unwrap();
+        assert_eq!(bit_sequence.to_string(), BIT_STRING_FROM_FILE);
+
+        let (bit_sequence, _) = utils::read_random_numbers_from_source(
+            &hex_bytes_file,
+            customtypes::InputFormat::Hex,
+            customtypes::BitOrder::MsbFirst,
+            Some(20),
+        )
+        .unwrap();
+        assert_eq!(bit_sequence.to_string(), BIT_STRING_FROM_FILE[..20]);
 
-        let success: bool;
-        match utils::untar_archive(&template, ARCHIVE_DEST_DIR) {
-            Ok(_) => success = true,
-            Err(_) => success = false,
-        };
-        assert!(success);
-        assert!(std::path::Path::new(TEMPLATE_FILE_DEST).exists());
-
-        // check contents of file
-        let mut extracted_file =
-            std::fs::File::open(TEMPLATE_FILE_DEST).expect("Failed to open extracted file");
-        let mut contents = String::new();
-        extracted_file
-            .read_to_string(&mut contents)
-            .expect("Failed to read contents");
-        assert!(!contents.is_empty());
-
-        // XXX check some error cases
-
-        // cleanup
-        let _ = std::fs::remove_file(TEMPLATE_FILE_DEST);
-        assert!(!std::path::Path::new(TEMPLATE_FILE_DEST).exists());
+        // try to read non-existing file
+        let invalid_file = std::env::current_dir()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_owned()
+            + INVALID_FILE;
+        assert!(utils::read_random_numbers_from_source(
+            &invalid_file,
+            customtypes::InputFormat::Auto,
+            customtypes::BitOrder::MsbFirst,
+            None,
+        )
+        .is_err());
     }
 }